@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+use ted_common_commands::FilePosition;
+
+pub const USAGE: &str = "\
+Usage: ted [OPTION]... [+LINE[:COLUMN]] [FILE[:LINE[:COLUMN]]]...
+       ted --client [FILE]
+       ted --batch SCRIPT [OPTION]... [FILE]...
+
+Open each FILE in its own window.  Files that don't exist are created
+when they are saved.  If FILE is -, the text piped into ted is read
+into the *stdin* buffer as it arrives.
+
+  +LINE[:COLUMN]  put the cursor at LINE and COLUMN in the next FILE
+  -R              open the files read only
+  --client        open a frame in a ted that is already running
+  --batch SCRIPT  run SCRIPT on the files without a terminal and exit
+  --              treat the remaining arguments as files
+  -h, --help      print this help and exit
+  -V, --version   print the version and exit
+";
+
+/// The command line arguments to ted.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    pub version: bool,
+    pub client: bool,
+    pub read_only: bool,
+    pub batch: Option<PathBuf>,
+    pub files: Vec<FilePosition>,
+}
+
+/// Parse the command line arguments, not including the program name.
+pub fn parse_args<I>(args: I) -> Result<Args, String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut parsed = Args::default();
+    let mut position = None;
+    let mut only_files = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if only_files || arg == "-" || !(arg.starts_with('-') || arg.starts_with('+')) {
+            parsed.files.push(parse_file(&arg, position.take()));
+        } else if arg.starts_with('+') {
+            position = Some(
+                parse_position(&arg[1..])
+                    .ok_or_else(|| format!("Error: Invalid position {}", arg))?,
+            );
+        } else {
+            match arg.as_ref() {
+                "--" => only_files = true,
+                "-h" | "--help" => parsed.help = true,
+                "-V" | "--version" => parsed.version = true,
+                "--client" => parsed.client = true,
+                "-R" => parsed.read_only = true,
+                "--batch" => {
+                    let script = args.next().ok_or("Error: --batch needs a script")?;
+                    parsed.batch = Some(script.into());
+                }
+                _ => return Err(format!("Error: Unknown option {}", arg)),
+            }
+        }
+    }
+    if position.is_some() {
+        return Err("Error: No file after the position".to_string());
+    }
+    Ok(parsed)
+}
+
+/// Parse `LINE[:COLUMN]`.
+fn parse_position(s: &str) -> Option<(usize, Option<usize>)> {
+    let mut parts = s.splitn(2, ':');
+    let line = parts.next()?.parse().ok()?;
+    let column = match parts.next() {
+        Some(column) => Some(column.parse().ok()?),
+        None => None,
+    };
+    Some((line, column))
+}
+
+/// Parse `FILE[:LINE[:COLUMN]]`, as printed by compilers and grep.
+///
+/// The suffix is only used as a position if no file is named `arg`.
+/// A position from a `+LINE[:COLUMN]` argument takes precedence.
+fn parse_file(arg: &str, position: Option<(usize, Option<usize>)>) -> FilePosition {
+    let mut file = FilePosition::from(arg);
+    if position.is_none() && !Path::new(arg).exists() {
+        if let Some((path, line, column)) = split_position_suffix(arg) {
+            file = FilePosition {
+                path: path.into(),
+                line: Some(line),
+                column,
+            };
+        }
+    }
+    if let Some((line, column)) = position {
+        file.line = Some(line);
+        file.column = column;
+    }
+    file
+}
+
+fn split_position_suffix(arg: &str) -> Option<(&str, usize, Option<usize>)> {
+    let (rest, last) = split_number(arg)?;
+    match split_number(rest) {
+        Some((path, line)) => Some((path, line, Some(last))),
+        None => Some((rest, last, None)),
+    }
+}
+
+/// Split `s` into the part before the last `:` and the number after
+/// it.
+fn split_number(s: &str) -> Option<(&str, usize)> {
+    let colon = s.rfind(':')?;
+    let number = s[colon + 1..].parse().ok()?;
+    Some((&s[..colon], number)).filter(|&(rest, _)| !rest.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn file(path: &str, line: Option<usize>, column: Option<usize>) -> FilePosition {
+        FilePosition {
+            path: path.into(),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn parse_args_files() {
+        assert_eq!(parse(&[]), Ok(Args::default()));
+        assert_eq!(
+            parse(&["a", "-R", "+3", "b", "+4:5", "c"]).unwrap(),
+            Args {
+                read_only: true,
+                files: vec![
+                    file("a", None, None),
+                    file("b", Some(3), None),
+                    file("c", Some(4), Some(5)),
+                ],
+                ..Args::default()
+            }
+        );
+        assert_eq!(
+            parse(&["--", "-R", "+3", "--help"]).unwrap().files,
+            vec![
+                file("-R", None, None),
+                file("+3", None, None),
+                file("--help", None, None),
+            ]
+        );
+        assert_eq!(
+            parse(&["-R", "-"]).unwrap().files,
+            vec![file("-", None, None)]
+        );
+    }
+
+    #[test]
+    fn parse_args_file_positions() {
+        assert_eq!(
+            parse(&["a.rs:12", "dir/b.rs:3:7", "c:d:2", "e:x", ":4"])
+                .unwrap()
+                .files,
+            vec![
+                file("a.rs", Some(12), None),
+                file("dir/b.rs", Some(3), Some(7)),
+                file("c:d", Some(2), None),
+                file("e:x", None, None),
+                file(":4", None, None),
+            ]
+        );
+        // An explicit position overrides the suffix.
+        assert_eq!(
+            parse(&["+1", "a.rs:12"]).unwrap().files,
+            vec![file("a.rs:12", Some(1), None)]
+        );
+    }
+
+    #[test]
+    fn parse_args_options() {
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&["-V"]).unwrap().version);
+        assert!(parse(&["--client", "a"]).unwrap().client);
+        assert_eq!(
+            parse(&["--batch", "script", "a"]).unwrap(),
+            Args {
+                batch: Some("script".into()),
+                files: vec![file("a", None, None)],
+                ..Args::default()
+            }
+        );
+        assert!(parse(&["--batch"]).is_err());
+        assert!(parse(&["-x"]).is_err());
+        assert!(parse(&["+a", "b"]).is_err());
+        assert!(parse(&["+1:"]).is_err());
+        assert!(parse(&["a", "+1"]).is_err());
+    }
+}
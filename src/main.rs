@@ -22,8 +22,11 @@ extern crate ted_core;
 extern crate ted_renderers;
 extern crate ted_user_cfg;
 
+mod args;
+
+use args::*;
 use parking_lot::Mutex;
-use std::collections::VecDeque;
+use std::path::Path;
 use std::sync::Arc;
 use ted_core::*;
 use ted_renderers::*;
@@ -38,144 +41,123 @@ fn main() {
 
 fn try_main() -> Result<(), String> {
     std::panic::set_hook(Box::new(|info| {
-        log(format!("{}", info));
+        let message = format!("{}", info);
+        // The panicking thread may hold the log, so don't wait on it.
+        if !try_log_error(message.clone()) {
+            eprintln!("{}", message);
+        }
     }));
+    let args = parse_args(std::env::args().skip(1))?;
+    if args.help {
+        print!("{}", USAGE);
+        return Ok(());
+    }
+    if args.version {
+        println!("ted {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if args.client {
+        return run_client_with_args(&args);
+    }
+    if let Some(ref script) = args.batch {
+        return run_batch(&args, script);
+    }
+    let stdin = if args.files.iter().any(|file| file.path == Path::new("-")) {
+        Some(take_piped_stdin()?)
+    } else {
+        None
+    };
     let mut state = State::new(CursesRenderer::new().unwrap());
     setup_state(&mut state)?;
     let state = Arc::new(Mutex::new(state));
-    parse_parameters(&*state)?;
-    state.lock().display.show().unwrap();
-    main_loop(state);
-    Ok(())
+    ted_common_commands::open_files(&*state, &args.files, args.read_only, stdin)?;
+    // Keep the server running until ted closes.
+    let _server = match default_socket_path()
+        .and_then(|path| start_server(&state, &path, ted_common_commands::open_file_inplace))
+    {
+        Ok(server) => Some(server),
+        Err(message) => {
+            log_error(message);
+            None
+        }
+    };
+    main_loop(state)
 }
 
-fn parse_parameters(state: &Mutex<State>) -> Result<(), String> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() == 1 {
-        Ok(())
-    } else if args.len() == 2 {
-        use std::path::PathBuf;
-        ted_common_commands::open_file_inplace(state, &PathBuf::from(&args[1]))
-    } else {
-        Err("Error: Too many arguments".to_string())
+fn run_client_with_args(args: &Args) -> Result<(), String> {
+    if args.read_only || args.files.len() > 1 {
+        return Err("Error: --client only takes one file".to_string());
+    }
+    if args.files.iter().any(|file| file.line.is_some()) {
+        return Err("Error: --client doesn't take a position".to_string());
     }
+    if args.files.iter().any(|file| file.path == Path::new("-")) {
+        return Err("Error: --client can't read stdin".to_string());
+    }
+    let file = args.files.first().map(|file| file.path.as_path());
+    run_client(&default_socket_path()?, file)
 }
 
-fn main_loop(state: Arc<Mutex<State>>) {
-    loop {
-        match increment(state.clone()) {
-            Ok(()) => (),
-            Err(message) => {
-                if ted_common_commands::was_closed_successfully() {
-                    return;
-                } else {
-                    let mut state = state.lock();
-                    state.display.selected_frame.lock().messages.add(message);
-                    state.display.show().unwrap();
-                }
-            }
-        }
+/// Run the batch `script` on the files without a terminal.
+fn run_batch(args: &Args, script: &Path) -> Result<(), String> {
+    if args.files.iter().any(|file| file.path == Path::new("-")) {
+        return Err("Error: --batch can't read stdin".to_string());
     }
+    let script = std::fs::read_to_string(script)
+        .map_err(|e| format!("Error: Reading {}: {}", script.display(), e))?;
+    let mut state = State::new(DebugRenderer::new());
+    setup_state(&mut state)?;
+    let state = Arc::new(Mutex::new(state));
+    ted_common_commands::open_files(&*state, &args.files, args.read_only, None)?;
+    ted_common_commands::run_batch_script(&state, &script)
 }
 
-fn increment(state: Arc<Mutex<State>>) -> Result<(), String> {
-    fn increment_(state: Arc<Mutex<State>>, inputs: &mut VecDeque<Input>) -> Result<(), String> {
-        loop {
-            match {
-                let state = state.lock();
-                state.display.getch()
-            } {
-                Some(input) => {
-                    state.lock().display.show()?;
-                    inputs.push_back(input);
-                    match {
-                        let state = state.lock();
-                        state.lookup(inputs)
-                    } {
-                        Ok(action) => {
-                            let r = action.execute(state.clone());
-                            state.lock().display.show().unwrap();
-                            return r;
-                        }
-                        Err(Ok(())) => (),
-                        Err(Err(())) => return Ok(()),
-                    }
-                }
-                None => state.lock().display.show()?,
-            }
+fn main_loop(state: Arc<Mutex<State>>) -> Result<(), String> {
+    DisplayCoordinator::new(state).run(|selected_frame, message| {
+        if ted_common_commands::was_closed_successfully() {
+            false
+        } else {
+            selected_frame.messages.add_error(message);
+            true
         }
-    }
-
-    increment_(state, &mut VecDeque::new())
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::VecDeque;
     use std::sync::Arc;
     use ted_common_commands::close_ted_command;
 
+    fn new_state(inputs: Vec<Input>) -> Arc<Mutex<State>> {
+        let state = State::new(DebugRenderer::from(inputs));
+        state
+            .default_key_map
+            .lock()
+            .bind(vec![kbd("C-q")], close_ted_command());
+        Arc::new(Mutex::new(state))
+    }
+
     #[test]
-    fn increment_1() {
-        let state = Arc::new(Mutex::new(State::new(DebugRenderer::from(vec![
-            kbd("a"),
-            kbd("b"),
-            kbd("c"),
-            kbd("q"),
-        ]))));
-        {
-            let selected_window = state.lock().display.selected_window();
-            let selected_window = selected_window.lock();
-            assert_eq!(selected_window.cursor.get(), 0);
-        }
-        increment(state.clone()).unwrap();
+    fn main_loop_1() {
+        let state = new_state(vec![kbd("a"), kbd("b"), kbd("c"), kbd("q"), kbd("C-q")]);
+        main_loop(state.clone()).unwrap();
         {
             let selected_window = state.lock().display.selected_window();
             let selected_window = selected_window.lock();
-            assert_eq!(selected_window.cursor.get(), 1);
-
+            assert_eq!(selected_window.cursor.get(), 4);
             let buffer = selected_window.buffer.lock();
-            assert_eq!(format!("{}", *buffer), "a");
-        }
-        {
-            let state = state.lock();
-            assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
-                vec![
-                    "a                   ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
-                ]
-            );
+            assert_eq!(format!("{}", *buffer), "abcq");
         }
 
-        increment(state.clone()).unwrap();
-        {
-            let selected_window = state.lock().display.selected_window();
-            let selected_window = selected_window.lock();
-            assert_eq!(selected_window.cursor.get(), 2);
-            let buffer = selected_window.buffer.lock();
-            assert_eq!(format!("{}", *buffer), "ab");
-        }
+        state.lock().display.show().unwrap();
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
-                    "ab                  ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
+                    "abcq                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -188,118 +170,109 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
         }
+    }
 
-        increment(state.clone()).unwrap();
-        {
-            let selected_window = state.lock().display.selected_window();
-            let selected_window = selected_window.lock();
-            assert_eq!(selected_window.cursor.get(), 3);
-            let buffer = selected_window.buffer.lock();
-            assert_eq!(format!("{}", *buffer), "abc");
-        }
-        {
-            let state = state.lock();
-            assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
-                vec![
-                    "abc                 ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
-                ]
-            );
-        }
+    /// Handle the next input like the UI loop does and then show the
+    /// result.
+    fn step(state: &Arc<Mutex<State>>, inputs: &mut VecDeque<Input>) -> Result<(), String> {
+        let event = read_input_event(&state.lock().display).unwrap();
+        let result = handle_input_event(state, inputs, event);
+        state.lock().display.show().unwrap();
+        result
+    }
 
-        increment(state.clone()).unwrap();
-        {
-            let selected_window = state.lock().display.selected_window();
-            let selected_window = selected_window.lock();
-            assert_eq!(selected_window.cursor.get(), 4);
-            let buffer = selected_window.buffer.lock();
-            assert_eq!(format!("{}", *buffer), "abcq");
+    fn assert_window(state: &Arc<Mutex<State>>, cursor: usize, contents: &str) {
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        assert_eq!(selected_window.cursor.get(), cursor);
+        let buffer = selected_window.buffer.lock();
+        assert_eq!(buffer.to_string(), contents);
+    }
+
+    fn assert_first_line(state: &Arc<Mutex<State>>, line: &str) {
+        let state = state.lock();
+        let mut expected = vec![vec![' '; 20]; 15];
+        for (column, ch) in line.chars().enumerate() {
+            expected[0][column] = ch;
         }
-        {
-            let state = state.lock();
-            assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
-                vec![
-                    "abcq                ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
-                ]
-            );
+        for (column, ch) in "*scratch*".chars().enumerate() {
+            expected[13][column] = ch;
         }
+        assert_eq!(state.display.debug_renderer().unwrap().buffer, expected);
+    }
+
+    #[test]
+    fn handle_input_event_1() {
+        let state = new_state(vec![kbd("a"), kbd("b"), kbd("c"), kbd("q")]);
+        let mut inputs = VecDeque::new();
+        assert_window(&state, 0, "");
+
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 1, "a");
+        assert_first_line(&state, "a");
+
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 2, "ab");
+        assert_first_line(&state, "ab");
+
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 3, "abc");
+        assert_first_line(&state, "abc");
+
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 4, "abcq");
+        assert_first_line(&state, "abcq");
 
         {
             let default_key_map = state.lock().default_key_map.clone();
             let mut default_key_map = default_key_map.lock();
             default_key_map.bind(vec![kbd("q")], close_ted_command());
         }
-        {
-            let state = state.lock();
-            unsafe { state.display.debug_renderer() }
-                .inputs
-                .push_back(kbd("q"));
-        }
-        increment(state.clone()).unwrap_err();
+        state
+            .lock()
+            .display
+            .debug_renderer()
+            .unwrap()
+            .inputs
+            .push_back(kbd("q"));
+        step(&state, &mut inputs).unwrap_err();
+        assert_window(&state, 4, "abcq");
+    }
 
-        state.lock().display.show().unwrap();
+    #[test]
+    fn handle_input_event_double_bind() {
+        let state = new_state(vec![kbd("a"), kbd("a"), kbd("b")]);
         {
-            let state = state.lock();
-            assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
-                vec![
-                    "abcq                ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
-                ]
-            );
+            let default_key_map = state.lock().default_key_map.clone();
+            default_key_map
+                .lock()
+                .bind(vec![kbd("a"), kbd("b")], close_ted_command());
         }
+        let mut inputs = VecDeque::new();
+
+        // "a" might start "a b" so nothing happens yet.
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 0, "");
+        assert_eq!(inputs.len(), 1);
+
+        // "a a" isn't bound so it is dropped.
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 0, "");
+        assert!(inputs.is_empty());
+
+        step(&state, &mut inputs).unwrap();
+        assert_window(&state, 1, "b");
     }
 
     #[test]
-    fn increment_new_line() {
-        let state = Arc::new(Mutex::new(State::new(DebugRenderer::from(vec![kbd("\n")]))));
-        increment(state.clone()).unwrap();
+    fn main_loop_new_line() {
+        let state = new_state(vec![kbd("\n"), kbd("C-q")]);
+        main_loop(state.clone()).unwrap();
         {
             let selected_window = state.lock().display.selected_window();
             let selected_window = selected_window.lock();
@@ -311,12 +284,8 @@ mod tests {
     }
 
     #[test]
-    fn increment_double_bind() {
-        let state = Arc::new(Mutex::new(State::new(DebugRenderer::from(vec![
-            kbd("a"),
-            kbd("a"),
-            kbd("b"),
-        ]))));
+    fn main_loop_double_bind() {
+        let state = new_state(vec![kbd("a"), kbd("a"), kbd("b"), kbd("C-q")]);
         {
             let default_key_map = state.lock().default_key_map.clone();
             default_key_map
@@ -324,18 +293,7 @@ mod tests {
                 .bind(vec![kbd("a"), kbd("b")], close_ted_command());
         }
 
-        increment(state.clone()).unwrap();
-        {
-            let selected_window = state.lock().display.selected_window();
-            let selected_window = selected_window.lock();
-
-            let buffer = selected_window.buffer.lock();
-            assert_eq!(buffer.to_string(), "");
-
-            assert_eq!(selected_window.cursor.get(), 0);
-        }
-
-        increment(state.clone()).unwrap();
+        main_loop(state.clone()).unwrap();
         {
             let selected_window = state.lock().display.selected_window();
             let selected_window = selected_window.lock();
@@ -0,0 +1,185 @@
+use close_commands::{was_closed_successfully, CLOSED_SUCCESSFULLY_MESSAGE};
+use open_file::open_file_inplace;
+use parking_lot::Mutex;
+use save_file::save_file_command;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use ted_core::*;
+
+/// Run a batch `script` without user interaction.
+///
+/// Each line of the `script` is one of:
+///
+/// * `open FILE` opens `FILE` in the selected [`Window`].
+/// * `keys KEY...` types the keys, written as for [`try_kbd`].
+/// * `type TEXT` types the rest of the line.
+/// * `run NAME` runs the [`Command`] called `NAME` in
+///   [`State::commands`].
+/// * `save` saves the selected [`Buffer`].
+///
+/// Blank lines and lines starting with `#` are ignored.  Keys are
+/// looked up with [`State::lookup`] just like keys typed by the user.
+///
+/// The script stops at the first [`Command`] that fails or key that
+/// isn't bound, returning the error along with its line number.  It
+/// also stops if ted is closed.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate parking_lot;
+/// # extern crate ted_common_commands;
+/// # extern crate ted_core;
+/// # use parking_lot::Mutex;
+/// # use std::sync::Arc;
+/// # use ted_common_commands::*;
+/// # use ted_core::*;
+/// # fn main() {
+/// let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+/// state
+///     .lock()
+///     .commands
+///     .insert("begin-of-line".to_string(), begin_of_line_command());
+///
+/// run_batch_script(&state, "type world\nrun begin-of-line\nkeys H i Space").unwrap();
+/// let buffer = state.lock().display.selected_window_buffer();
+/// assert_eq!(buffer.lock().to_string(), "Hi world");
+/// # }
+/// ```
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+/// [`Command`]: ../ted_core/trait.Command.html
+/// [`try_kbd`]: ../ted_core/fn.try_kbd.html
+/// [`State::commands`]: ../ted_core/struct.State.html#structfield.commands
+/// [`State::lookup`]: ../ted_core/struct.State.html#method.lookup
+pub fn run_batch_script(state: &Arc<Mutex<State>>, script: &str) -> Result<(), String> {
+    for (index, line) in script.lines().enumerate() {
+        match run_batch_line(state, line) {
+            Ok(()) => {}
+            Err(ref message) if is_closed(message) => return Ok(()),
+            Err(message) => return Err(format!("Line {}: {}", index + 1, message)),
+        }
+    }
+    Ok(())
+}
+
+fn is_closed(message: &str) -> bool {
+    message == CLOSED_SUCCESSFULLY_MESSAGE && was_closed_successfully()
+}
+
+fn run_batch_line(state: &Arc<Mutex<State>>, line: &str) -> Result<(), String> {
+    let line = line.trim_start();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(());
+    }
+    let (word, rest) = match line.find(' ') {
+        Some(space) => (&line[..space], &line[space + 1..]),
+        None => (line, ""),
+    };
+    match word {
+        "open" => open_file_inplace(state, Path::new(rest.trim())),
+        "keys" => {
+            let mut inputs = Vec::new();
+            for key in rest.split_whitespace() {
+                inputs.push(try_kbd(key).map_err(|e| format!("Error: {}", e))?);
+            }
+            type_inputs(state, inputs)
+        }
+        "type" => type_inputs(
+            state,
+            rest.chars()
+                .map(|c| Input {
+                    control: false,
+                    alt: false,
+                    key: Key::Key(c),
+                })
+                .collect(),
+        ),
+        "run" => {
+            let command = state.lock().commands.get(rest.trim()).cloned();
+            match command {
+                Some(command) => command.execute(state.clone()),
+                None => Err(format!("Error: No command named {}", rest.trim())),
+            }
+        }
+        "save" if rest.trim().is_empty() => save_file_command().execute(state.clone()),
+        _ => Err(format!("Error: Invalid line {:?}", line)),
+    }
+}
+
+fn type_inputs(state: &Arc<Mutex<State>>, inputs: Vec<Input>) -> Result<(), String> {
+    let mut typed = VecDeque::new();
+    for input in inputs {
+        typed.push_back(input);
+        // Looking up unbound keys consumes them.
+        let sequence = typed.clone();
+        let command = state.lock().lookup(&mut typed);
+        match command {
+            Ok(command) => {
+                typed.clear();
+                command.execute(state.clone())?;
+            }
+            Err(Ok(())) => {}
+            Err(Err(())) => return Err(format!("Error: {:?} is not bound", sequence)),
+        }
+    }
+    if typed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Error: {:?} is not a complete key sequence", typed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_commands::*;
+
+    fn new_state() -> Arc<Mutex<State>> {
+        let state = State::new(DebugRenderer::new());
+        state
+            .default_key_map
+            .lock()
+            .bind(vec![kbd("C-x"), kbd("n")], end_of_buffer_command());
+        Arc::new(Mutex::new(state))
+    }
+
+    #[test]
+    fn run_batch_script_edits_and_saves() {
+        let path = std::env::temp_dir().join(format!("ted-batch-{}", std::process::id()));
+        std::fs::write(&path, "first\n").unwrap();
+        let state = new_state();
+        let script = format!(
+            "# Append a line.\n\nopen {}\nkeys C-x n\ntype second line\nkeys Return\nsave\n",
+            path.display()
+        );
+        run_batch_script(&state, &script).unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "first\nsecond line\n");
+    }
+
+    #[test]
+    fn run_batch_script_errors() {
+        let state = new_state();
+        assert_eq!(
+            run_batch_script(&state, "type a\nrun missing"),
+            Err("Line 2: Error: No command named missing".to_string())
+        );
+        assert_eq!(
+            run_batch_script(&state, "keys C-x a"),
+            Err("Line 1: Error: [C-x, a] is not bound".to_string())
+        );
+        assert_eq!(
+            run_batch_script(&state, "keys C-x"),
+            Err("Line 1: Error: [C-x] is not a complete key sequence".to_string())
+        );
+        assert!(run_batch_script(&state, "keys Mouse").is_err());
+        assert!(run_batch_script(&state, "jump").is_err());
+        assert!(run_batch_script(&state, "save").is_err());
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().to_string(), "a");
+    }
+}
@@ -6,6 +6,11 @@ lazy_static! {
     static ref CLOSED_SUCCESSFULLY: Mutex<bool> = Mutex::new(false);
 }
 
+/// The error [`CloseTedCommand`] returns to stop ted.
+///
+/// [`CloseTedCommand`]: struct.CloseTedCommand.html
+pub const CLOSED_SUCCESSFULLY_MESSAGE: &str = "Closed Successfully";
+
 pub fn was_closed_successfully() -> bool {
     *CLOSED_SUCCESSFULLY.lock()
 }
@@ -25,14 +30,38 @@ impl Command for CloseTedCommand {
     fn execute(&self, _: Arc<Mutex<State>>) -> Result<(), String> {
         let mut closed_successfully = CLOSED_SUCCESSFULLY.lock();
         *closed_successfully = true;
-        Err("Closed Successfully".to_string())
+        Err(CLOSED_SUCCESSFULLY_MESSAGE.to_string())
+    }
+}
+
+/// Close the selected [`Frame`].
+///
+/// If it belongs to a client, the client quits.  The last [`Frame`]
+/// can't be closed.
+///
+/// [`Frame`]: ../ted_core/struct.Frame.html
+#[derive(Debug)]
+pub struct CloseFrameCommand;
+
+/// Construct a [`CloseFrameCommand`].
+///
+/// [`CloseFrameCommand`]: struct.CloseFrameCommand.html
+pub fn close_frame_command() -> Arc<CloseFrameCommand> {
+    Arc::new(CloseFrameCommand)
+}
+
+impl Command for CloseFrameCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let mut state = state.lock();
+        let selected_frame = state.display.selected_frame.clone();
+        state.display.remove_frame(&selected_frame)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ted_core::DebugRenderer;
+    use ted_core::{DebugRenderer, Frame};
 
     #[test]
     fn close_ted_command_test() {
@@ -42,4 +71,25 @@ mod tests {
         assert!(r.is_err());
         assert!(was_closed_successfully());
     }
+
+    #[test]
+    fn close_frame_command_test() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        assert!(close_frame_command().execute(state.clone()).is_err());
+
+        let frame = {
+            let window = state.lock().display.selected_window();
+            let frame = Frame::new(window, Box::new(DebugRenderer::new()));
+            Arc::new(Mutex::new(frame))
+        };
+        {
+            let mut state = state.lock();
+            state.display.frames.push(frame.clone());
+            state.display.selected_frame = frame.clone();
+        }
+        close_frame_command().execute(state.clone()).unwrap();
+        let state = state.lock();
+        assert_eq!(state.display.frames.len(), 1);
+        assert!(!Arc::ptr_eq(&state.display.selected_frame, &frame));
+    }
 }
@@ -0,0 +1,363 @@
+use move_commands::move_lines;
+use parking_lot::Mutex;
+use std::ops::Range;
+use std::sync::Arc;
+use ted_core::*;
+
+/// Add a cursor on the next line of the selected [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct AddCursorNextLineCommand;
+
+/// Construct an [`AddCursorNextLineCommand`].
+///
+/// [`AddCursorNextLineCommand`]: struct.AddCursorNextLineCommand.html
+pub fn add_cursor_next_line_command() -> Arc<AddCursorNextLineCommand> {
+    Arc::new(AddCursorNextLineCommand)
+}
+
+impl Command for AddCursorNextLineCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        add_cursor_lines(state, 1)
+    }
+}
+
+/// Add a cursor on the previous line of the selected [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct AddCursorPreviousLineCommand;
+
+/// Construct an [`AddCursorPreviousLineCommand`].
+///
+/// [`AddCursorPreviousLineCommand`]: struct.AddCursorPreviousLineCommand.html
+pub fn add_cursor_previous_line_command() -> Arc<AddCursorPreviousLineCommand> {
+    Arc::new(AddCursorPreviousLineCommand)
+}
+
+impl Command for AddCursorPreviousLineCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        add_cursor_lines(state, -1)
+    }
+}
+
+/// Leave a [`SecondaryCursor`] behind and move the cursor `times`
+/// lines, keeping the goal column.
+///
+/// [`SecondaryCursor`]: ../ted_core/struct.SecondaryCursor.html
+fn add_cursor_lines(state: Arc<Mutex<State>>, times: isize) -> Result<(), String> {
+    let selected_frame = state.lock().display.selected_frame.clone();
+    let selected_frame = selected_frame.lock();
+    let selected_window = selected_frame.selected_window.clone();
+    let columns = selected_frame
+        .window_size(&selected_window)
+        .map(|(_, columns)| columns);
+    let mut selected_window = selected_window.lock();
+    selected_window.push_cursor();
+    move_lines(&mut selected_window, times, columns);
+    selected_window.merge_cursors();
+    Ok(())
+}
+
+/// Add a cursor at the next occurrence of the word at the cursor in
+/// the selected [`Window`](../ted_core/struct.Window.html).
+///
+/// The new cursor is at the same place in the word as the cursor.
+/// The search wraps around to the start of the buffer and skips
+/// occurrences that already have a cursor.  The cursor is always the
+/// newest one, so repeating the command keeps going forward.
+#[derive(Debug)]
+pub struct AddCursorNextMatchCommand;
+
+/// Construct an [`AddCursorNextMatchCommand`].
+///
+/// [`AddCursorNextMatchCommand`]: struct.AddCursorNextMatchCommand.html
+pub fn add_cursor_next_match_command() -> Arc<AddCursorNextMatchCommand> {
+    Arc::new(AddCursorNextMatchCommand)
+}
+
+impl Command for AddCursorNextMatchCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        selected_window.update_cursor();
+        let location = {
+            let buffer = selected_window.buffer.lock();
+            let cursor = selected_window.cursor.get();
+            let word = word_at(&buffer, cursor).ok_or("Error: No word at the cursor")?;
+            let offset = cursor - word.start;
+            let mut taken: Vec<usize> = selected_window
+                .cursors
+                .iter()
+                .map(|secondary| secondary.marker.get())
+                .collect();
+            taken.push(cursor);
+            let start = next_match(&buffer, &word, |start| taken.contains(&(start + offset)))
+                .ok_or("Error: No more matches")?;
+            start + offset
+        };
+        selected_window.add_cursor(location);
+        Ok(())
+    }
+}
+
+/// Remove all the [`SecondaryCursor`]s of the selected [`Window`].
+///
+/// [`SecondaryCursor`]: ../ted_core/struct.SecondaryCursor.html
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct RemoveCursorsCommand;
+
+/// Construct a [`RemoveCursorsCommand`].
+///
+/// [`RemoveCursorsCommand`]: struct.RemoveCursorsCommand.html
+pub fn remove_cursors_command() -> Arc<RemoveCursorsCommand> {
+    Arc::new(RemoveCursorsCommand)
+}
+
+impl Command for RemoveCursorsCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        selected_window.lock().cursors.clear();
+        Ok(())
+    }
+}
+
+fn word_character(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_word_at(buffer: &Buffer, location: usize) -> bool {
+    buffer.get(location).map(word_character).unwrap_or(false)
+}
+
+/// Find the word containing or ending at `location`.
+fn word_at(buffer: &Buffer, location: usize) -> Option<Range<usize>> {
+    let mut start = location;
+    while start > 0 && is_word_at(buffer, start - 1) {
+        start -= 1;
+    }
+    let mut end = location;
+    while is_word_at(buffer, end) {
+        end += 1;
+    }
+    if start == end {
+        None
+    } else {
+        Some(start..end)
+    }
+}
+
+/// Find the start of the next whole word after `word` with the same
+/// text, wrapping around to the start of `buffer`.
+///
+/// Matches that `is_taken` returns `true` for are skipped.
+fn next_match<F>(buffer: &Buffer, word: &Range<usize>, is_taken: F) -> Option<usize>
+where
+    F: Fn(usize) -> bool,
+{
+    let needle: Vec<char> = buffer
+        .iter_at(word.start)
+        .take(word.end - word.start)
+        .collect();
+    let is_match = |start: usize| {
+        (start == 0 || !is_word_at(buffer, start - 1))
+            && !is_word_at(buffer, start + needle.len())
+            && !is_taken(start)
+    };
+    find(buffer, &needle, word.end..buffer.len(), &is_match)
+        .or_else(|| find(buffer, &needle, 0..word.end, &is_match))
+}
+
+/// Find the first occurrence of `needle` in `range` of `buffer` that
+/// `is_match` accepts.
+///
+/// This is the Knuth-Morris-Pratt algorithm, so it takes
+/// `O(range.len() + needle.len())` time.
+fn find<F>(buffer: &Buffer, needle: &[char], range: Range<usize>, is_match: &F) -> Option<usize>
+where
+    F: Fn(usize) -> bool,
+{
+    if needle.is_empty() {
+        return None;
+    }
+    // `fallback[i]` is the length of the longest proper prefix of
+    // `needle[..i + 1]` that is also a suffix of it.
+    let mut fallback = vec![0; needle.len()];
+    let mut matched = 0;
+    for i in 1..needle.len() {
+        while matched > 0 && needle[i] != needle[matched] {
+            matched = fallback[matched - 1];
+        }
+        if needle[i] == needle[matched] {
+            matched += 1;
+        }
+        fallback[i] = matched;
+    }
+    matched = 0;
+    let iter = buffer.iter_at(range.start).take(range.end - range.start);
+    for (location, ch) in (range.start..).zip(iter) {
+        while matched > 0 && ch != needle[matched] {
+            matched = fallback[matched - 1];
+        }
+        if ch == needle[matched] {
+            matched += 1;
+        }
+        if matched == needle.len() {
+            let start = location + 1 - needle.len();
+            if is_match(start) {
+                return Some(start);
+            }
+            matched = fallback[matched - 1];
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use change_commands::*;
+    use delete_commands::*;
+    use move_commands::*;
+
+    fn new_state(contents: &str, cursor: usize) -> Arc<Mutex<State>> {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        {
+            let selected_window = state.lock().display.selected_window();
+            let mut selected_window = selected_window.lock();
+            selected_window.insert_str(contents).unwrap();
+            selected_window.set_cursor(cursor);
+        }
+        state
+    }
+
+    fn cursors(state: &Arc<Mutex<State>>) -> (usize, Vec<usize>) {
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        let cursors = selected_window
+            .cursors
+            .iter()
+            .map(|secondary| secondary.marker.get())
+            .collect();
+        (selected_window.cursor.get(), cursors)
+    }
+
+    fn contents(state: &Arc<Mutex<State>>) -> String {
+        let buffer = state.lock().display.selected_window_buffer();
+        let contents = buffer.lock().to_string();
+        contents
+    }
+
+    #[test]
+    fn add_cursor_lines() {
+        let state = new_state("abc\nd\nefg\nhij", 2);
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        // The goal column is kept across the short line.
+        assert_eq!(cursors(&state), (8, vec![2, 5]));
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (12, vec![2, 5, 8]));
+
+        // Moving back onto a cursor merges them.
+        add_cursor_previous_line_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (8, vec![2, 5, 12]));
+
+        remove_cursors_command().execute(state.clone()).unwrap();
+        assert_eq!(cursors(&state), (8, vec![]));
+    }
+
+    #[test]
+    fn edit_at_each_cursor() {
+        let state = new_state("abc\nd\nefg", 1);
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        add_cursor_next_line_command()
+            .execute(state.clone())
+            .unwrap();
+        insert_command('x').execute(state.clone()).unwrap();
+        assert_eq!(contents(&state), "axbc\ndx\nexfg");
+        begin_of_line_command().execute(state.clone()).unwrap();
+        delete_forward_char_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(contents(&state), "xbc\nx\nxfg");
+        assert_eq!(cursors(&state), (6, vec![0, 4]));
+        end_of_line_command().execute(state.clone()).unwrap();
+        delete_backward_char_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(contents(&state), "xb\n\nxf");
+        assert_eq!(cursors(&state), (6, vec![2, 3]));
+
+        // Each command is undone in one step.
+        undo_command().execute(state.clone()).unwrap();
+        assert_eq!(contents(&state), "xbc\nx\nxfg");
+        undo_command().execute(state.clone()).unwrap();
+        assert_eq!(contents(&state), "axbc\ndx\nexfg");
+        undo_command().execute(state.clone()).unwrap();
+        assert_eq!(contents(&state), "abc\nd\nefg");
+    }
+
+    #[test]
+    fn add_cursor_next_match() {
+        let state = new_state("foo bar foobar foo\nfoo", 1);
+        add_cursor_next_match_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (16, vec![1]));
+        add_cursor_next_match_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (20, vec![1, 16]));
+        assert_eq!(
+            add_cursor_next_match_command().execute(state.clone()),
+            Err("Error: No more matches".to_string())
+        );
+
+        let state = new_state("a  b", 2);
+        assert_eq!(
+            add_cursor_next_match_command().execute(state.clone()),
+            Err("Error: No word at the cursor".to_string())
+        );
+    }
+
+    #[test]
+    fn add_cursor_next_match_wraps() {
+        let state = new_state("foo bar foo", 9);
+        add_cursor_next_match_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (1, vec![9]));
+
+        // Partial matches that overlap the next match aren't missed.
+        let state = new_state("abab ababab abab", 0);
+        add_cursor_next_match_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (12, vec![0]));
+    }
+
+    #[test]
+    fn add_cursor_next_match_skips_cursors() {
+        let state = new_state("foo foo", 0);
+        add_cursor_next_match_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(cursors(&state), (4, vec![0]));
+        assert_eq!(
+            add_cursor_next_match_command().execute(state.clone()),
+            Err("Error: No more matches".to_string())
+        );
+        assert_eq!(cursors(&state), (4, vec![0]));
+    }
+}
@@ -17,14 +17,15 @@ impl Command for DeleteBackwardCharCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        if selected_window.cursor.get() != 0 {
-            let mut buffer = selected_window.buffer.lock();
-            debug_assert!(selected_window.cursor.is_updated(&buffer));
-            buffer.delete(selected_window.cursor.get() - 1)?;
-            selected_window.cursor.update(&buffer);
-        }
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            if window.cursor.get() != 0 {
+                let mut buffer = window.buffer.lock();
+                debug_assert!(window.cursor.is_updated(&buffer));
+                buffer.delete(window.cursor.get() - 1)?;
+                window.cursor.update(&buffer);
+            }
+            Ok(())
+        })
     }
 }
 
@@ -43,14 +44,15 @@ impl Command for DeleteForwardCharCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let mut buffer = selected_window.buffer.lock();
-        debug_assert!(selected_window.cursor.is_updated(&buffer));
-        if selected_window.cursor.get() != buffer.len() {
-            buffer.delete(selected_window.cursor.get())?;
-            selected_window.cursor.update(&buffer);
-        }
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let mut buffer = window.buffer.lock();
+            debug_assert!(window.cursor.is_updated(&buffer));
+            if window.cursor.get() != buffer.len() {
+                buffer.delete(window.cursor.get())?;
+                window.cursor.update(&buffer);
+            }
+            Ok(())
+        })
     }
 }
 
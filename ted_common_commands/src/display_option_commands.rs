@@ -0,0 +1,161 @@
+use parking_lot::Mutex;
+use std::sync::Arc;
+use ted_core::*;
+
+/// Cycle the selected [`Window`] through not showing line numbers,
+/// showing absolute line numbers, and showing relative line numbers.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ToggleLineNumbersCommand;
+
+/// Construct a [`ToggleLineNumbersCommand`].
+///
+/// [`ToggleLineNumbersCommand`]: struct.ToggleLineNumbersCommand.html
+pub fn toggle_line_numbers_command() -> Arc<ToggleLineNumbersCommand> {
+    Arc::new(ToggleLineNumbersCommand)
+}
+
+impl Command for ToggleLineNumbersCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let options = &mut selected_window.display_options;
+        options.line_numbers = match options.line_numbers {
+            LineNumbers::None => LineNumbers::Absolute,
+            LineNumbers::Absolute => LineNumbers::Relative,
+            LineNumbers::Relative => LineNumbers::None,
+        };
+        Ok(())
+    }
+}
+
+/// Toggle whether the selected [`Window`] wraps or truncates long lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ToggleWrapCommand;
+
+/// Construct a [`ToggleWrapCommand`].
+///
+/// [`ToggleWrapCommand`]: struct.ToggleWrapCommand.html
+pub fn toggle_wrap_command() -> Arc<ToggleWrapCommand> {
+    Arc::new(ToggleWrapCommand)
+}
+
+impl Command for ToggleWrapCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let options = &mut selected_window.display_options;
+        options.wrap_mode = match options.wrap_mode {
+            WrapMode::Wrap => WrapMode::Truncate,
+            WrapMode::Truncate => WrapMode::Wrap,
+        };
+        Ok(())
+    }
+}
+
+/// Toggle whether the selected [`Window`] shows spaces and tabs.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ToggleShowWhitespaceCommand;
+
+/// Construct a [`ToggleShowWhitespaceCommand`].
+///
+/// [`ToggleShowWhitespaceCommand`]: struct.ToggleShowWhitespaceCommand.html
+pub fn toggle_show_whitespace_command() -> Arc<ToggleShowWhitespaceCommand> {
+    Arc::new(ToggleShowWhitespaceCommand)
+}
+
+impl Command for ToggleShowWhitespaceCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let options = &mut selected_window.display_options;
+        options.show_whitespace = !options.show_whitespace;
+        Ok(())
+    }
+}
+
+/// Toggle whether moving up and down in the selected [`Window`]
+/// moves by rows on the screen or by lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ToggleVisualLineMovementCommand;
+
+/// Construct a [`ToggleVisualLineMovementCommand`].
+///
+/// [`ToggleVisualLineMovementCommand`]: struct.ToggleVisualLineMovementCommand.html
+pub fn toggle_visual_line_movement_command() -> Arc<ToggleVisualLineMovementCommand> {
+    Arc::new(ToggleVisualLineMovementCommand)
+}
+
+impl Command for ToggleVisualLineMovementCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let options = &mut selected_window.display_options;
+        options.visual_line_movement = !options.visual_line_movement;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use split_commands::vertical_split_command;
+
+    #[test]
+    fn toggle_line_numbers_cycles() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        toggle_line_numbers_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(
+            window.lock().display_options.line_numbers,
+            LineNumbers::Absolute
+        );
+        toggle_line_numbers_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(
+            window.lock().display_options.line_numbers,
+            LineNumbers::Relative
+        );
+        toggle_line_numbers_command()
+            .execute(state.clone())
+            .unwrap();
+        assert_eq!(
+            window.lock().display_options.line_numbers,
+            LineNumbers::None
+        );
+    }
+
+    #[test]
+    fn split_windows_have_separate_options() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        state
+            .lock()
+            .display
+            .selected_window()
+            .lock()
+            .insert_str("a b")
+            .unwrap();
+        vertical_split_command().execute(state.clone()).unwrap();
+        toggle_line_numbers_command()
+            .execute(state.clone())
+            .unwrap();
+        toggle_show_whitespace_command()
+            .execute(state.clone())
+            .unwrap();
+        toggle_wrap_command().execute(state.clone()).unwrap();
+        state.lock().display.show().unwrap();
+        let state = state.lock();
+        let buffer = &state.display.debug_renderer().unwrap().buffer;
+        assert_eq!(buffer[0].iter().collect::<String>(), "1 a·b     |a b      ");
+    }
+}
@@ -0,0 +1,189 @@
+use move_commands::{begin_of_line, end_of_line};
+use parking_lot::Mutex;
+use std::sync::Arc;
+use ted_core::*;
+
+lazy_static! {
+    static ref JOBS_MODE: Arc<Mutex<Mode>> = create_jobs_mode();
+}
+
+fn create_jobs_mode() -> Arc<Mutex<Mode>> {
+    let mut mode = Mode::new();
+    mode.name = "Jobs".to_string();
+    {
+        let mut key_map = mode.key_map.lock();
+        key_map.bind(vec![kbd("g")], refresh_jobs_command());
+        key_map.bind(vec![kbd("k")], cancel_job_command());
+    }
+    Arc::new(Mutex::new(mode))
+}
+
+fn is_jobs_buffer(buffer: &Buffer) -> bool {
+    buffer
+        .buffer_modes
+        .iter()
+        .any(|mode| Arc::ptr_eq(mode, &JOBS_MODE))
+}
+
+/// Describe each [`Job`] on its own line, starting with its id.
+///
+/// [`Job`]: ../ted_core/struct.Job.html
+fn jobs_contents(jobs: &Jobs) -> String {
+    let mut contents = String::new();
+    for job in jobs.iter() {
+        let progress = match job.progress() {
+            Some(progress) => format!("{}%", progress),
+            None => "-".to_string(),
+        };
+        contents.push_str(&format!("{} {} {}", job.id, progress, job.name));
+        if job.is_cancelled() {
+            contents.push_str(" (cancelled)");
+        }
+        contents.push('\n');
+    }
+    contents
+}
+
+/// List the running [`Job`]s in a `*Jobs*` buffer.
+///
+/// In the buffer, `g` refreshes the list and `k` cancels the [`Job`]
+/// on the cursor's line.
+///
+/// [`Job`]: ../ted_core/struct.Job.html
+#[derive(Debug)]
+pub struct ListJobsCommand;
+
+/// Construct a [`ListJobsCommand`].
+///
+/// [`ListJobsCommand`]: struct.ListJobsCommand.html
+pub fn list_jobs_command() -> Arc<ListJobsCommand> {
+    Arc::new(ListJobsCommand)
+}
+
+impl Command for ListJobsCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let state = state.lock();
+        let contents = jobs_contents(&state.jobs.lock());
+        let mut buffer = Buffer::new_with_contents("*Jobs*".into(), &contents);
+        buffer.read_only = true;
+        buffer.buffer_modes.push(JOBS_MODE.clone());
+        let window = Arc::new(Mutex::new(Window::from(buffer)));
+        state
+            .display
+            .selected_frame
+            .lock()
+            .replace_selected_window(window);
+        Ok(())
+    }
+}
+
+/// Update the `*Jobs*` buffer to show the [`Job`]s that are running
+/// now.
+///
+/// [`Job`]: ../ted_core/struct.Job.html
+#[derive(Debug)]
+pub struct RefreshJobsCommand;
+
+/// Construct a [`RefreshJobsCommand`].
+///
+/// [`RefreshJobsCommand`]: struct.RefreshJobsCommand.html
+pub fn refresh_jobs_command() -> Arc<RefreshJobsCommand> {
+    Arc::new(RefreshJobsCommand)
+}
+
+impl Command for RefreshJobsCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        refresh_jobs(&state.lock())
+    }
+}
+
+fn refresh_jobs(state: &State) -> Result<(), String> {
+    let buffer = state.display.selected_window_buffer();
+    if !is_jobs_buffer(&buffer.lock()) {
+        return Err("Error: Not in the *Jobs* buffer".to_string());
+    }
+    let contents = jobs_contents(&state.jobs.lock());
+    replace_buffer_contents(&buffer, &contents)
+}
+
+/// Cancel the [`Job`] on the cursor's line of the `*Jobs*` buffer.
+///
+/// [`Job`]: ../ted_core/struct.Job.html
+#[derive(Debug)]
+pub struct CancelJobCommand;
+
+/// Construct a [`CancelJobCommand`].
+///
+/// [`CancelJobCommand`]: struct.CancelJobCommand.html
+pub fn cancel_job_command() -> Arc<CancelJobCommand> {
+    Arc::new(CancelJobCommand)
+}
+
+impl Command for CancelJobCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let state = state.lock();
+        let line = {
+            let window = state.display.selected_window();
+            let window = window.lock();
+            let buffer = window.buffer.lock();
+            if !is_jobs_buffer(&buffer) {
+                return Err("Error: Not in the *Jobs* buffer".to_string());
+            }
+            let cursor = window.cursor.get();
+            buffer.substring(begin_of_line(&buffer, cursor), end_of_line(&buffer, cursor))?
+        };
+        let id = line
+            .split(' ')
+            .next()
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| "Error: No job on this line".to_string())?;
+        state.jobs.lock().cancel(id)?;
+        refresh_jobs(&state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn list_and_cancel_jobs() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let (send_started, receive_started) = channel();
+        let (send_cancelled, receive_cancelled) = channel();
+        let handle = spawn_job(
+            &state,
+            "wait",
+            move |handle| {
+                handle.set_progress(25);
+                send_started.send(()).unwrap();
+                receive_cancelled.recv().unwrap();
+                assert!(handle.is_cancelled());
+                Ok(())
+            },
+            |_, ()| Ok(()),
+        )
+        .unwrap();
+        receive_started.recv().unwrap();
+
+        list_jobs_command().execute(state.clone()).unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().name.name, "*Jobs*");
+        assert_eq!(buffer.lock().to_string(), "0 25% wait\n");
+        assert!(buffer.lock().read_only);
+
+        cancel_job_command().execute(state.clone()).unwrap();
+        send_cancelled.send(()).unwrap();
+        handle.join();
+        refresh_jobs_command().execute(state.clone()).unwrap();
+        assert_eq!(buffer.lock().to_string(), "");
+    }
+
+    #[test]
+    fn cancel_job_outside_jobs_buffer() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        assert!(cancel_job_command().execute(state.clone()).is_err());
+        assert!(refresh_jobs_command().execute(state.clone()).is_err());
+    }
+}
@@ -3,6 +3,9 @@ extern crate lazy_static;
 extern crate parking_lot;
 extern crate ted_core;
 
+mod cursor_commands;
+pub use cursor_commands::*;
+
 mod delete_commands;
 pub use delete_commands::*;
 
@@ -32,3 +35,18 @@ pub use save_file::*;
 
 mod read_only_commands;
 pub use read_only_commands::*;
+
+mod scroll_commands;
+pub use scroll_commands::*;
+
+mod display_option_commands;
+pub use display_option_commands::*;
+
+mod jobs_commands;
+pub use jobs_commands::*;
+
+mod messages_commands;
+pub use messages_commands::*;
+
+mod batch;
+pub use batch::*;
@@ -0,0 +1,144 @@
+use parking_lot::Mutex;
+use std::sync::Arc;
+use ted_core::*;
+
+/// Show the `*Messages*` buffer in the selected window.
+///
+/// The cursor is placed at the end so the newest messages are
+/// visible.
+#[derive(Debug)]
+pub struct ShowMessagesCommand;
+
+/// Construct a [`ShowMessagesCommand`].
+///
+/// [`ShowMessagesCommand`]: struct.ShowMessagesCommand.html
+pub fn show_messages_command() -> Arc<ShowMessagesCommand> {
+    Arc::new(ShowMessagesCommand)
+}
+
+impl Command for ShowMessagesCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let buffer = messages_buffer();
+        let len = buffer.lock().len();
+        let mut window = Window::from(buffer);
+        window.set_cursor(len);
+        let state = state.lock();
+        state
+            .display
+            .selected_frame
+            .lock()
+            .replace_selected_window(Arc::new(Mutex::new(window)));
+        Ok(())
+    }
+}
+
+/// Show the message before the one shown in the echo area.
+///
+/// See [`Messages::show_previous`].
+///
+/// [`Messages::show_previous`]: ../ted_core/struct.Messages.html#method.show_previous
+#[derive(Debug)]
+pub struct PreviousMessageCommand;
+
+/// Construct a [`PreviousMessageCommand`].
+///
+/// [`PreviousMessageCommand`]: struct.PreviousMessageCommand.html
+pub fn previous_message_command() -> Arc<PreviousMessageCommand> {
+    Arc::new(PreviousMessageCommand)
+}
+
+impl Command for PreviousMessageCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let state = state.lock();
+        state.display.selected_frame.lock().messages.show_previous();
+        Ok(())
+    }
+}
+
+/// Show the message after the one shown in the echo area.
+///
+/// See [`Messages::show_next`].
+///
+/// [`Messages::show_next`]: ../ted_core/struct.Messages.html#method.show_next
+#[derive(Debug)]
+pub struct NextMessageCommand;
+
+/// Construct a [`NextMessageCommand`].
+///
+/// [`NextMessageCommand`]: struct.NextMessageCommand.html
+pub fn next_message_command() -> Arc<NextMessageCommand> {
+    Arc::new(NextMessageCommand)
+}
+
+impl Command for NextMessageCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let state = state.lock();
+        state.display.selected_frame.lock().messages.show_next();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn show_messages_shows_errors() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        state
+            .lock()
+            .display
+            .selected_frame
+            .lock()
+            .messages
+            .add_error("Error: Shown in *Messages*");
+        show_messages_command().execute(state.clone()).unwrap();
+        let window = state.lock().display.selected_window();
+        let window = window.lock();
+        let buffer = window.buffer.lock();
+        assert_eq!(buffer.name.name, "*Messages*");
+        assert!(buffer
+            .to_string()
+            .contains(" ERROR Error: Shown in *Messages*\n"));
+        assert_eq!(window.cursor.get(), buffer.len());
+    }
+
+    #[test]
+    fn browse_messages_in_echo_area() {
+        let renderer = DebugRenderer::from(vec![kbd("C-h"), kbd("p"), kbd("C-h"), kbd("p")]);
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        {
+            let state = state.lock();
+            let mut key_map = state.default_key_map.lock();
+            key_map.bind(vec![kbd("C-h"), kbd("p")], previous_message_command());
+            let mut selected_frame = state.display.selected_frame.lock();
+            selected_frame.messages.add_error("Error: First");
+            selected_frame.messages.add_error("Error: Second");
+        }
+        let show_echo_area = |state: &Arc<Mutex<State>>| {
+            let state = state.lock();
+            state.display.show().unwrap();
+            let echo_area = state.display.debug_renderer().unwrap().buffer[14]
+                .iter()
+                .collect::<String>();
+            echo_area
+        };
+        assert_eq!(show_echo_area(&state), "Error: Second       ");
+
+        let mut inputs = VecDeque::new();
+        for _ in 0..2 {
+            let event = read_input_event(&state.lock().display).unwrap();
+            handle_input_event(&state, &mut inputs, event).unwrap();
+        }
+        assert_eq!(show_echo_area(&state), "Error: Second       ");
+        for _ in 0..2 {
+            let event = read_input_event(&state.lock().display).unwrap();
+            handle_input_event(&state, &mut inputs, event).unwrap();
+        }
+        assert_eq!(show_echo_area(&state), "Error: First        ");
+
+        next_message_command().execute(state.clone()).unwrap();
+        assert_eq!(show_echo_area(&state), "Error: Second       ");
+    }
+}
@@ -1,5 +1,6 @@
 use parking_lot::Mutex;
 use std::sync::Arc;
+use ted_core::draw::{line_column, location_at, position_of};
 use ted_core::*;
 
 /// Move backwards one char on the selected [`Window`](../ted_core/struct.Window.html).
@@ -17,10 +18,11 @@ impl Command for BeginOfBufferCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        selected_window.cursor.set(&buffer, 0);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            window.cursor.set(&buffer, 0);
+            Ok(())
+        })
     }
 }
 
@@ -39,10 +41,11 @@ impl Command for EndOfBufferCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        selected_window.cursor.set(&buffer, buffer.len());
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            window.cursor.set(&buffer, buffer.len());
+            Ok(())
+        })
     }
 }
 
@@ -61,8 +64,10 @@ impl Command for BackwardCharCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        selected_window.increment_cursor(-1);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            window.increment_cursor(-1);
+            Ok(())
+        })
     }
 }
 
@@ -81,8 +86,10 @@ impl Command for ForwardCharCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        selected_window.increment_cursor(1);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            window.increment_cursor(1);
+            Ok(())
+        })
     }
 }
 
@@ -101,11 +108,12 @@ impl Command for BeginOfLineCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = begin_of_line(&buffer, selected_window.cursor.get());
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = begin_of_line(&buffer, window.cursor.get());
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -139,11 +147,12 @@ impl Command for EndOfLineCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = end_of_line(&buffer, selected_window.cursor.get());
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = end_of_line(&buffer, window.cursor.get());
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -175,13 +184,7 @@ pub fn forward_line_command() -> Arc<ForwardLineCommand> {
 
 impl Command for ForwardLineCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
-        let selected_window = state.lock().display.selected_window();
-        let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_line(&buffer, selected_window.cursor.get(), 1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        move_selected_window_lines(state, 1)
     }
 }
 
@@ -198,16 +201,111 @@ pub fn backward_line_command() -> Arc<BackwardLineCommand> {
 
 impl Command for BackwardLineCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
-        let selected_window = state.lock().display.selected_window();
-        let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_line(&buffer, selected_window.cursor.get(), -1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        move_selected_window_lines(state, -1)
     }
 }
 
+fn move_selected_window_lines(state: Arc<Mutex<State>>, times: isize) -> Result<(), String> {
+    let selected_frame = state.lock().display.selected_frame.clone();
+    let selected_frame = selected_frame.lock();
+    let selected_window = selected_frame.selected_window.clone();
+    let columns = selected_frame
+        .window_size(&selected_window)
+        .map(|(_, columns)| columns);
+    let mut selected_window = selected_window.lock();
+    selected_window.at_each_cursor(|window| {
+        move_lines(window, times, columns);
+        Ok(())
+    })
+}
+
+/// Find the location drawn at `column` on the line starting at
+/// `begin`, ignoring wrapping.
+fn line_location(buffer: &Buffer, options: &DisplayOptions, begin: usize, column: usize) -> usize {
+    let options = DisplayOptions {
+        wrap_mode: WrapMode::Truncate,
+        ..*options
+    };
+    begin
+        + location_at(
+            buffer.display_iter().skip(begin),
+            &options,
+            0,
+            usize::MAX,
+            0,
+            column,
+        )
+}
+
+/// Move the cursor of `window` `times` lines down, keeping it at its
+/// [`GoalColumn`].
+///
+/// If `window` wraps lines and uses visual line movement then this
+/// moves by rows on the screen instead.  `columns` is the number of
+/// columns of text in `window`, if it is on the screen.
+///
+/// [`GoalColumn`]: ../ted_core/struct.GoalColumn.html
+pub fn move_lines(window: &mut Window, times: isize, columns: Option<usize>) {
+    let options = window.display_options;
+    let buffer = window.buffer.lock();
+    window.cursor.update(&buffer);
+    let cursor = window.cursor.get();
+    let goal_column = match window.goal_column {
+        Some(goal) if goal.location == cursor => Some(goal.column),
+        _ => None,
+    };
+    let columns = columns.filter(|&columns| {
+        options.visual_line_movement && options.wrap_mode == WrapMode::Wrap && columns != 0
+    });
+    let (column, location) = match columns {
+        Some(columns) => {
+            let mut begin = begin_of_line(&buffer, cursor);
+            let rows_until = |begin: usize, location: usize| {
+                position_of(
+                    buffer.display_iter().skip(begin),
+                    &options,
+                    columns,
+                    location - begin,
+                )
+            };
+            let (mut row, column) = rows_until(begin, cursor);
+            let column = goal_column.unwrap_or(column);
+            for _ in 0..times.abs() {
+                if times > 0 {
+                    let end = end_of_line(&buffer, begin);
+                    if row < rows_until(begin, end).0 {
+                        row += 1;
+                    } else if end < buffer.len() {
+                        begin = end + 1;
+                        row = 0;
+                    }
+                } else if row > 0 {
+                    row -= 1;
+                } else if begin > 0 {
+                    begin = begin_of_line(&buffer, begin - 1);
+                    row = rows_until(begin, end_of_line(&buffer, begin)).0;
+                }
+            }
+            let offset = location_at(
+                buffer.display_iter().skip(begin),
+                &options,
+                0,
+                columns,
+                row,
+                column,
+            );
+            (column, begin + offset)
+        }
+        None => {
+            let column = goal_column.unwrap_or_else(|| line_column(&buffer, &options, cursor));
+            let begin = forward_line(&buffer, begin_of_line(&buffer, cursor), times);
+            (column, line_location(&buffer, &options, begin, column))
+        }
+    };
+    window.cursor.set(&buffer, location);
+    window.goal_column = Some(GoalColumn { column, location });
+}
+
 /// Get the point `times` lines after `location`.
 pub fn forward_line(buffer: &Buffer, location: usize, times: isize) -> usize {
     let begin = begin_of_line(buffer, location);
@@ -249,11 +347,12 @@ impl Command for ForwardWordCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_word(&buffer, selected_window.cursor.get(), 1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = forward_word(&buffer, window.cursor.get(), 1);
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -272,11 +371,12 @@ impl Command for BackwardWordCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_word(&buffer, selected_window.cursor.get(), -1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = forward_word(&buffer, window.cursor.get(), -1);
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -594,4 +694,48 @@ mod tests {
         buffer.insert_str(0, "a_b").unwrap();
         assert_eq!(forward_word(&buffer, 0, 1), 3);
     }
+
+    #[test]
+    fn forward_line_keeps_goal_column() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window.lock().insert_str("abcdef\nab\n\tcdefgh").unwrap();
+        window.lock().display_options.tab_width = 4;
+        window.lock().set_cursor(5);
+
+        forward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 9);
+        forward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 12);
+        backward_line_command().execute(state.clone()).unwrap();
+        backward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 5);
+
+        // Moving horizontally forgets the goal column.
+        forward_line_command().execute(state.clone()).unwrap();
+        backward_char_command().execute(state.clone()).unwrap();
+        forward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 10);
+    }
+
+    #[test]
+    fn forward_line_visual_lines() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window
+            .lock()
+            .insert_str("0123456789012345678901234\nabc")
+            .unwrap();
+        window.lock().display_options.visual_line_movement = true;
+        window.lock().set_cursor(2);
+
+        forward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 22);
+        forward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 28);
+        backward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 22);
+        backward_line_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().cursor.get(), 2);
+    }
 }
@@ -17,11 +17,12 @@ impl Command for ForwardGroupCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_group(&buffer, selected_window.cursor.get(), 1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = forward_group(&buffer, window.cursor.get(), 1);
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -39,11 +40,12 @@ impl Command for BackwardGroupCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = forward_group(&buffer, selected_window.cursor.get(), -1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = forward_group(&buffer, window.cursor.get(), -1);
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -140,11 +142,12 @@ impl Command for UpGroupCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        let selected_window = &mut *selected_window;
-        let buffer = selected_window.buffer.lock();
-        let new_location = up_group(&buffer, selected_window.cursor.get(), 1);
-        selected_window.cursor.set(&buffer, new_location);
-        Ok(())
+        selected_window.at_each_cursor(|window| {
+            let buffer = window.buffer.lock();
+            let new_location = up_group(&buffer, window.cursor.get(), 1);
+            window.cursor.set(&buffer, new_location);
+            Ok(())
+        })
     }
 }
 
@@ -1,8 +1,9 @@
 use parking_lot::Mutex;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{self, BufReader, Read};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::thread;
 use ted_core::*;
 
 pub fn open_file(path: &Path) -> Result<Buffer, String> {
@@ -41,12 +42,220 @@ pub fn open_file(path: &Path) -> Result<Buffer, String> {
             let mut reader = BufReader::new(file);
             check(reader.read_to_string(&mut buf))?;
         }
-        Ok(Buffer::new_with_contents(path.into(), &buf))
+        let mut buffer = Buffer::new_with_contents(path.into(), &buf);
+        buffer.detect_line_ending();
+        Ok(buffer)
     }
 }
 
+/// Open the file at `path`, or create an empty [`Buffer`] for it if it
+/// doesn't exist yet.  The file is created when the [`Buffer`] is
+/// saved.
+///
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn open_or_create_file(path: &Path) -> Result<Buffer, String> {
+    if path.exists() {
+        return open_file(path);
+    }
+    let path = std::env::current_dir()
+        .map_err(|e| e.to_string())?
+        .join(path);
+    if path.file_name().is_none() {
+        return Err(format!("Error: Invalid file name {}", path.display()));
+    }
+    Ok(Buffer::new(path.into()))
+}
+
+/// Find the location of `column` in `line` of `buffer`.
+///
+/// Lines and columns count from 1.  A position past the end of its
+/// line or the end of the `buffer` is moved back to the end.
+pub fn line_column_location(buffer: &Buffer, line: usize, column: usize) -> usize {
+    let mut chars = buffer.iter();
+    let mut location = 0;
+    let mut current_line = 1;
+    while current_line < line {
+        match chars.next() {
+            Some('\n') => current_line += 1,
+            Some(_) => {}
+            None => return location,
+        }
+        location += 1;
+    }
+    for _ in 1..column {
+        match chars.next() {
+            Some('\n') | None => break,
+            Some(_) => location += 1,
+        }
+    }
+    location
+}
+
+/// A file to open along with the line and column to put the cursor
+/// at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilePosition {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl<'a> From<&'a str> for FilePosition {
+    fn from(path: &'a str) -> Self {
+        FilePosition {
+            path: path.into(),
+            line: None,
+            column: None,
+        }
+    }
+}
+
+/// Read `input` into the end of `buffer` on another thread as the
+/// text arrives.
+///
+/// Text that isn't UTF-8 is replaced with `U+FFFD`.  Errors reading
+/// are logged.  What is read can't be undone.
+pub fn stream_into_buffer<R>(
+    mut input: R,
+    buffer: Arc<Mutex<Buffer>>,
+) -> Result<thread::JoinHandle<()>, String>
+where
+    R: Read + Send + 'static,
+{
+    thread::Builder::new()
+        .name("ted stream".to_string())
+        .spawn(move || {
+            let mut bytes = Vec::new();
+            let mut chunk = [0; 4096];
+            loop {
+                let len = match input.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(len) => len,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        log_error(format!(
+                            "Error: Reading into {}: {}",
+                            buffer.lock().name.name,
+                            e
+                        ));
+                        break;
+                    }
+                };
+                bytes.extend_from_slice(&chunk[..len]);
+                let text = decode_utf8(&mut bytes);
+                append_to_buffer(&buffer, &text);
+            }
+            if !bytes.is_empty() {
+                append_to_buffer(&buffer, &String::from_utf8_lossy(&bytes));
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+/// Decode and remove `bytes`, replacing invalid sequences with
+/// `U+FFFD`.
+///
+/// A character split at the end is kept until the rest of it arrives.
+fn decode_utf8(bytes: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    let mut start = 0;
+    loop {
+        match std::str::from_utf8(&bytes[start..]) {
+            Ok(valid) => {
+                text.push_str(valid);
+                start = bytes.len();
+                break;
+            }
+            Err(e) => {
+                let end = start + e.valid_up_to();
+                text.push_str(std::str::from_utf8(&bytes[start..end]).unwrap());
+                match e.error_len() {
+                    Some(error_len) => {
+                        text.push(std::char::REPLACEMENT_CHARACTER);
+                        start = end + error_len;
+                    }
+                    None => {
+                        start = end;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    bytes.drain(..start);
+    text
+}
+
+fn append_to_buffer(buffer: &Mutex<Buffer>, text: &str) {
+    // Undoing shouldn't remove what was read.
+    if let Err(message) = buffer.lock().append_without_history(text) {
+        log_error(message);
+    }
+}
+
+/// Open each of the `files` in its own [`Window`], splitting the
+/// selected [`Window`] into rows.  The first file is selected.
+///
+/// Files that don't exist are created when they are saved.  If
+/// `read_only` is set the [`Buffer`]s can't be edited.
+///
+/// A file named `-` is the `*stdin*` [`Buffer`], which `stdin` is
+/// read into as the text arrives.  Its cursor stays at the start.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn open_files(
+    state: &Mutex<State>,
+    files: &[FilePosition],
+    read_only: bool,
+    mut stdin: Option<File>,
+) -> Result<(), String> {
+    let mut windows = Vec::new();
+    for file in files {
+        let (buffer, location) = if file.path == Path::new("-") {
+            let input = stdin
+                .take()
+                .ok_or_else(|| "Error: stdin can only be opened once".to_string())?;
+            let buffer = Arc::new(Mutex::new(Buffer::new("*stdin*".into())));
+            stream_into_buffer(input, buffer.clone())?;
+            (buffer, 0)
+        } else {
+            let buffer = open_or_create_file(&file.path)?;
+            let location = match file.line {
+                Some(line) => line_column_location(&buffer, line, file.column.unwrap_or(1)),
+                None => 0,
+            };
+            (Arc::new(Mutex::new(buffer)), location)
+        };
+        buffer.lock().read_only = read_only;
+        let mut window = Window::from(buffer);
+        window.set_cursor(location);
+        windows.push(Arc::new(Mutex::new(window)));
+    }
+    let first_window = match windows.first() {
+        Some(window) => window.clone(),
+        None => return Ok(()),
+    };
+    let mut windows = windows.into_iter().rev();
+    let mut layout = Layout::Window(windows.next().unwrap());
+    for window in windows {
+        layout = Layout::HSplit {
+            top: Box::new(Layout::Window(window)),
+            bottom: Box::new(layout),
+        };
+    }
+    let selected_frame = state.lock().display.selected_frame.clone();
+    let mut selected_frame = selected_frame.lock();
+    let selected_frame = &mut *selected_frame;
+    selected_frame
+        .layout
+        .replace_window(&selected_frame.selected_window, layout);
+    selected_frame.selected_window = first_window;
+    Ok(())
+}
+
 pub fn open_file_inplace(state: &Mutex<State>, path: &Path) -> Result<(), String> {
-    let buffer = open_file(path)?;
+    let buffer = open_or_create_file(path)?;
     let window = Arc::new(Mutex::new(Window::from(buffer)));
     let selected_frame = state.lock().display.selected_frame.clone();
     selected_frame.lock().replace_selected_window(window);
@@ -84,4 +293,143 @@ mod tests {
             .is_updated(&selected_window.buffer.lock()));
         assert_eq!(selected_window.cursor.get(), 0);
     }
+
+    #[test]
+    fn open_file_detects_line_ending() {
+        let path = std::env::temp_dir().join(format!("ted-crlf-{}", std::process::id()));
+        std::fs::write(&path, "a\r\nb\r\n").unwrap();
+        let buffer = open_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(buffer.unwrap().line_ending, LineEnding::Crlf);
+    }
+
+    #[test]
+    fn line_column_location_1() {
+        let buffer = Buffer::new_with_contents("*scratch*".into(), "ab\ncde\n\nf");
+        assert_eq!(line_column_location(&buffer, 1, 1), 0);
+        assert_eq!(line_column_location(&buffer, 1, 2), 1);
+        assert_eq!(line_column_location(&buffer, 1, 10), 2);
+        assert_eq!(line_column_location(&buffer, 2, 3), 5);
+        assert_eq!(line_column_location(&buffer, 3, 2), 7);
+        assert_eq!(line_column_location(&buffer, 4, 1), 8);
+        assert_eq!(line_column_location(&buffer, 10, 1), 9);
+    }
+
+    #[test]
+    fn open_files_splits_windows() {
+        let dir = std::env::temp_dir().join(format!("ted-open-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a"), "1\n2\n345\n").unwrap();
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let files = vec![
+            FilePosition {
+                path: dir.join("a"),
+                line: Some(3),
+                column: Some(2),
+            },
+            FilePosition::from(dir.join("new").to_str().unwrap()),
+        ];
+        open_files(&state, &files, true, None).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let selected_frame = selected_frame.lock();
+        let (top, bottom) = match &selected_frame.layout {
+            Layout::HSplit { top, bottom } => (top.unwrap_window(), bottom.unwrap_window()),
+            _ => panic!("The selected window wasn't split"),
+        };
+        assert!(Arc::ptr_eq(top, &selected_frame.selected_window));
+        let top = top.lock();
+        assert_eq!(top.cursor.get(), 5);
+        let mut buffer = top.buffer.lock();
+        assert_eq!(buffer.to_string(), "1\n2\n345\n");
+        assert!(buffer.insert(0, 'x').is_err());
+
+        let bottom = bottom.lock();
+        let buffer = bottom.buffer.lock();
+        assert_eq!(buffer.name.name, "new");
+        assert_eq!(buffer.len(), 0);
+        assert!(buffer.read_only);
+    }
+
+    /// Reads one byte at a time to split up characters.
+    struct ByteReader(Vec<u8>);
+
+    impl Read for ByteReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0.remove(0);
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn stream_into_buffer_decodes_split_characters() {
+        let buffer = Arc::new(Mutex::new(Buffer::new("*stdin*".into())));
+        buffer.lock().read_only = true;
+        let mut bytes = "a\u{4e2d}b".as_bytes().to_vec();
+        bytes.insert(2, 0xff);
+        bytes.push(0xe4);
+        stream_into_buffer(ByteReader(bytes), buffer.clone())
+            .unwrap()
+            .join()
+            .unwrap();
+        let mut buffer = buffer.lock();
+        assert_eq!(
+            buffer.to_string(),
+            "a\u{fffd}\u{fffd}\u{fffd}\u{fffd}b\u{fffd}"
+        );
+        assert!(buffer.read_only);
+        buffer.read_only = false;
+        assert!(!buffer.undo().unwrap());
+    }
+
+    #[test]
+    fn stream_into_buffer_keeps_history() {
+        let buffer = Arc::new(Mutex::new(Buffer::new("*stdin*".into())));
+        buffer.lock().insert_str(0, "x").unwrap();
+        stream_into_buffer(ByteReader(b"ab".to_vec()), buffer.clone())
+            .unwrap()
+            .join()
+            .unwrap();
+        let mut buffer = buffer.lock();
+        assert_eq!(buffer.to_string(), "xab");
+        assert!(buffer.undo().unwrap());
+        assert_eq!(buffer.to_string(), "ab");
+    }
+
+    #[test]
+    fn decode_utf8_whole_chunk() {
+        let mut bytes = b"a\xffb\xfe\xe4\xb8\xadc\xe4\xb8".to_vec();
+        assert_eq!(decode_utf8(&mut bytes), "a\u{fffd}b\u{fffd}\u{4e2d}c");
+        assert_eq!(bytes, b"\xe4\xb8");
+        bytes.push(0xad);
+        assert_eq!(decode_utf8(&mut bytes), "\u{4e2d}");
+        assert!(bytes.is_empty());
+    }
+
+    fn piped(text: &str) -> File {
+        let path = std::env::temp_dir().join(format!("ted-stdin-{}", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let file = File::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        file
+    }
+
+    #[test]
+    fn open_files_reads_stdin() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let files = vec![FilePosition::from("-"), FilePosition::from("-")];
+        assert!(open_files(&state, &files[..1], false, None).is_err());
+        assert!(open_files(&state, &files, false, Some(piped(""))).is_err());
+
+        open_files(&state, &files[..1], false, Some(piped("piped\n"))).unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().name.name, "*stdin*");
+        while buffer.lock().to_string() != "piped\n" {
+            thread::yield_now();
+        }
+    }
 }
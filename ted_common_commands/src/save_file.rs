@@ -23,7 +23,9 @@ pub fn save_file_command() -> Arc<SaveFileCommand> {
 impl Command for SaveFileCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let buffer = state.lock().display.selected_window_buffer();
-        let buffer = buffer.lock();
-        save_buffer(&*buffer)
+        let mut buffer = buffer.lock();
+        save_buffer(&buffer)?;
+        buffer.mark_saved();
+        Ok(())
     }
 }
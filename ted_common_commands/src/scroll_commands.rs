@@ -0,0 +1,257 @@
+use move_commands::*;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use ted_core::draw::{line_column, location_at};
+use ted_core::*;
+
+/// Scroll `window` by `lines` lines, keeping its cursor on the screen.
+///
+/// `rows` and `columns` are the size of the text area of `window`.
+pub fn scroll_window(window: &mut Window, lines: isize, rows: usize, columns: usize) {
+    let buffer = window.buffer.lock();
+    window.cursor.update(&buffer);
+    let start = begin_of_line(&buffer, std::cmp::min(window.start, buffer.len()));
+    let start = begin_of_line(&buffer, forward_line(&buffer, start, lines));
+    window.start = start;
+    let cursor = window.cursor.get();
+    if cursor < start {
+        window.cursor.set(&buffer, start);
+    } else {
+        let end = start
+            + location_at(
+                buffer.display_iter().skip(start),
+                &window.display_options,
+                window.hscroll,
+                columns,
+                rows.saturating_sub(1),
+                columns.saturating_sub(1),
+            );
+        if cursor > end {
+            window.cursor.set(&buffer, begin_of_line(&buffer, end));
+        }
+    }
+}
+
+/// Scroll `window` horizontally by `columns` columns, keeping its
+/// cursor on the screen if its line is long enough.
+///
+/// This does nothing unless `window` truncates lines.  `text_columns`
+/// is the number of columns of text displayed by `window`.
+pub fn scroll_window_horizontally(window: &mut Window, columns: isize, text_columns: usize) {
+    if window.display_options.wrap_mode != WrapMode::Truncate {
+        return;
+    }
+    window.hscroll = if columns < 0 {
+        window.hscroll.saturating_sub(-columns as usize)
+    } else {
+        window.hscroll + columns as usize
+    };
+    let buffer = window.buffer.lock();
+    window.cursor.update(&buffer);
+    let cursor = window.cursor.get();
+    let begin = begin_of_line(&buffer, cursor);
+    let column = line_column(&buffer, &window.display_options, cursor);
+    // Stay off the continuation markers on either edge.
+    let first = window.hscroll + if window.hscroll == 0 { 0 } else { 1 };
+    let last = (window.hscroll + text_columns).saturating_sub(2);
+    let column = std::cmp::min(std::cmp::max(column, first), last);
+    let location = begin
+        + location_at(
+            buffer.display_iter().skip(begin),
+            &window.display_options,
+            0,
+            usize::MAX,
+            0,
+            column,
+        );
+    window.cursor.set(&buffer, location);
+}
+
+fn scroll_horizontally(state: Arc<Mutex<State>>, direction: isize) -> Result<(), String> {
+    let selected_frame = state.lock().display.selected_frame.clone();
+    let selected_frame = selected_frame.lock();
+    let window = selected_frame.selected_window.clone();
+    if let Some((_, columns)) = selected_frame.window_size(&window) {
+        let amount = std::cmp::max(columns as isize - 2, 1);
+        scroll_window_horizontally(&mut window.lock(), direction * amount, columns);
+    }
+    Ok(())
+}
+
+fn scroll(state: Arc<Mutex<State>>, lines: isize) -> Result<(), String> {
+    let selected_frame = state.lock().display.selected_frame.clone();
+    let selected_frame = selected_frame.lock();
+    let window = match selected_frame.window_at_mouse() {
+        Some((window, _)) => window,
+        None => selected_frame.selected_window.clone(),
+    };
+    if let Some((rows, columns)) = selected_frame.window_size(&window) {
+        scroll_window(&mut window.lock(), lines, rows, columns);
+    }
+    Ok(())
+}
+
+/// Scroll the [`Window`] under the mouse, or the selected [`Window`]
+/// if the mouse wasn't used, up three lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ScrollUpCommand;
+
+/// Construct a [`ScrollUpCommand`].
+///
+/// [`ScrollUpCommand`]: struct.ScrollUpCommand.html
+pub fn scroll_up_command() -> Arc<ScrollUpCommand> {
+    Arc::new(ScrollUpCommand)
+}
+
+impl Command for ScrollUpCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        scroll(state, -3)
+    }
+}
+
+/// Scroll the [`Window`] under the mouse, or the selected [`Window`]
+/// if the mouse wasn't used, down three lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ScrollDownCommand;
+
+/// Construct a [`ScrollDownCommand`].
+///
+/// [`ScrollDownCommand`]: struct.ScrollDownCommand.html
+pub fn scroll_down_command() -> Arc<ScrollDownCommand> {
+    Arc::new(ScrollDownCommand)
+}
+
+impl Command for ScrollDownCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        scroll(state, 3)
+    }
+}
+
+/// Scroll the text in the selected [`Window`] to the left, showing
+/// the columns to the right.
+///
+/// This only applies when the [`Window`] truncates lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ScrollLeftCommand;
+
+/// Construct a [`ScrollLeftCommand`].
+///
+/// [`ScrollLeftCommand`]: struct.ScrollLeftCommand.html
+pub fn scroll_left_command() -> Arc<ScrollLeftCommand> {
+    Arc::new(ScrollLeftCommand)
+}
+
+impl Command for ScrollLeftCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        scroll_horizontally(state, 1)
+    }
+}
+
+/// Scroll the text in the selected [`Window`] to the right, showing
+/// the columns to the left.
+///
+/// This only applies when the [`Window`] truncates lines.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct ScrollRightCommand;
+
+/// Construct a [`ScrollRightCommand`].
+///
+/// [`ScrollRightCommand`]: struct.ScrollRightCommand.html
+pub fn scroll_right_command() -> Arc<ScrollRightCommand> {
+    Arc::new(ScrollRightCommand)
+}
+
+impl Command for ScrollRightCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        scroll_horizontally(state, -1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_down_moves_cursor_onto_screen() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window
+            .lock()
+            .insert_str("a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt")
+            .unwrap();
+        window.lock().set_cursor(2);
+
+        scroll_down_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().start, 6);
+        assert_eq!(window.lock().cursor.get(), 6);
+
+        state.lock().display.show().unwrap();
+        let state = state.lock();
+        let debug_renderer = state.display.debug_renderer().unwrap();
+        assert_eq!(debug_renderer.buffer[0][0], 'd');
+        assert_eq!(debug_renderer.buffer[12][0], 'p');
+        assert_eq!(debug_renderer.selected_cursors, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn scroll_up_moves_cursor_onto_screen() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window
+            .lock()
+            .insert_str("a\nb\nc\nd\ne\nf\ng\nh\ni\nj\nk\nl\nm\nn\no\np\nq\nr\ns\nt")
+            .unwrap();
+        {
+            let mut window = window.lock();
+            window.start = 10;
+            window.set_cursor(36);
+        }
+
+        scroll_up_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().start, 4);
+        assert_eq!(window.lock().cursor.get(), 30);
+
+        scroll_up_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().start, 0);
+        assert_eq!(window.lock().cursor.get(), 26);
+    }
+
+    #[test]
+    fn scroll_left_and_right() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window
+            .lock()
+            .insert_str("0123456789abcdefghijklmnopqrstuvwxyz\nshort")
+            .unwrap();
+        window.lock().display_options.wrap_mode = WrapMode::Truncate;
+        window.lock().set_cursor(3);
+
+        scroll_left_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().hscroll, 18);
+        assert_eq!(window.lock().cursor.get(), 19);
+
+        state.lock().display.show().unwrap();
+        {
+            let state = state.lock();
+            let debug_renderer = state.display.debug_renderer().unwrap();
+            assert_eq!(
+                debug_renderer.buffer[0].iter().collect::<String>(),
+                "$jklmnopqrstuvwxyz  "
+            );
+            assert_eq!(debug_renderer.selected_cursors, vec![(0, 1)]);
+        }
+
+        scroll_right_command().execute(state.clone()).unwrap();
+        assert_eq!(window.lock().hscroll, 0);
+        assert_eq!(window.lock().cursor.get(), 18);
+    }
+}
@@ -123,7 +123,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -138,8 +138,8 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
         }
@@ -154,7 +154,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -169,10 +169,22 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
         }
     }
+
+    #[test]
+    fn horizontal_split_snapshot() {
+        let state = State::new(DebugRenderer::with_size(9, 12));
+        state
+            .default_key_map
+            .lock()
+            .bind(vec![kbd("C-x"), kbd("2")], horizontal_split_command());
+        let state = Arc::new(Mutex::new(state));
+        testing::type_keys(&state, "C-x 2 abc").unwrap();
+        testing::assert_snapshot("horizontal_split", &testing::render_screen(&state).unwrap());
+    }
 }
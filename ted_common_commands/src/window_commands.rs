@@ -242,7 +242,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -257,16 +257,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![]
             );
         }
@@ -294,7 +294,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -309,16 +309,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 2)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 15), (8, 4)]
             );
         }
@@ -328,7 +328,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -343,16 +343,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(8, 4)]
             );
         }
@@ -371,7 +371,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -386,16 +386,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![]
             );
         }
@@ -416,7 +416,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -431,16 +431,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 2)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 11), (8, 0)]
             );
         }
@@ -452,7 +452,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -467,16 +467,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 2)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![]
             );
         }
@@ -495,7 +495,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -510,8 +510,8 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
         }
@@ -522,7 +522,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -537,16 +537,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 15), (8, 4)]
             );
         }
@@ -558,7 +558,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -573,16 +573,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 15)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (8, 4)]
             );
         }
@@ -594,7 +594,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -609,16 +609,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(8, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (0, 15)]
             );
         }
@@ -630,7 +630,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -645,16 +645,16 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 15), (8, 4)]
             );
         }
@@ -673,7 +673,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd                ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
@@ -688,8 +688,8 @@ mod tests {
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
                     "                    ".chars().collect::<Vec<_>>(),
-                    "                    ".chars().collect::<Vec<_>>(),
-                    "*scratch*           ".chars().collect::<Vec<_>>()
+                    "*scratch*           ".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
         }
@@ -708,7 +708,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -723,16 +723,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 13)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (8, 15)]
             );
         }
@@ -744,7 +744,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -759,16 +759,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(8, 15)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (0, 13)]
             );
         }
@@ -780,7 +780,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -795,16 +795,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 13), (8, 15)]
             );
         }
@@ -816,7 +816,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -831,16 +831,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 13)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (8, 15)]
             );
         }
@@ -866,7 +866,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -881,16 +881,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 15)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (8, 15)]
             );
         }
@@ -902,7 +902,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -917,16 +917,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 4)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 15), (8, 15)]
             );
         }
@@ -938,7 +938,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -953,16 +953,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(8, 15)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (0, 15)]
             );
         }
@@ -974,7 +974,7 @@ mod tests {
         {
             let state = state.lock();
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.buffer,
+                state.display.debug_renderer().unwrap().buffer,
                 vec![
                     "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
@@ -989,16 +989,16 @@ mod tests {
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
                     "          |         ".chars().collect::<Vec<_>>(),
-                    "          |         ".chars().collect::<Vec<_>>(),
-                    "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                    "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                    "                    ".chars().collect::<Vec<_>>()
                 ]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.selected_cursors,
+                state.display.debug_renderer().unwrap().selected_cursors,
                 vec![(0, 15)]
             );
             assert_eq!(
-                unsafe { state.display.debug_renderer() }.unselected_cursors,
+                state.display.debug_renderer().unwrap().unselected_cursors,
                 vec![(0, 4), (8, 15)]
             );
         }
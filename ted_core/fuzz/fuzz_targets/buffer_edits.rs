@@ -0,0 +1,17 @@
+//! Run with `cargo fuzz run buffer_edits` from `ted_core`.
+//!
+//! `ted_core` is built with the `small-chunks` feature so that edits
+//! cross the boundaries between chunks of text as often as they do
+//! in the unit tests.
+//!
+//! See `ted_core::testing::check_buffer_edits` for how the input is
+//! interpreted.
+
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate ted_core;
+
+fuzz_target!(|data: &[u8]| {
+    ted_core::testing::check_buffer_edits(data);
+});
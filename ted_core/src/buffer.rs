@@ -1,14 +1,23 @@
 use buffer_contents::*;
 use by_address::ByAddress;
 use change::*;
+use marker::*;
 use mode::Mode;
+use overlay::Overlay;
 use parking_lot::Mutex;
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
+use std::ops::Range;
 use std::path::*;
 use std::sync::{Arc, Weak};
 
 const ERROR_READ_ONLY: &'static str = "Error: Buffer is read only";
+const ERROR_READ_ONLY_OVERLAY: &str = "Error: Text is read only";
+
+/// The character that invisible text is displayed as.
+///
+/// It takes up no space on the screen.
+const INVISIBLE: char = '\u{200B}';
 
 /// The actual text storage structure
 ///
@@ -40,10 +49,55 @@ pub struct Buffer {
     buffer_contents: BufferContents,
     initial_state: Arc<Mutex<StateNode>>,
     current_state: Arc<Mutex<StateNode>>,
+    saved_state: Weak<Mutex<StateNode>>,
+    markers: Arc<Mutex<MarkerSet>>,
+    /// The number of undo groups that are open.
+    undo_groups: usize,
+    /// Whether a change was made since the outermost undo group was
+    /// opened.
+    is_group_started: bool,
     /// The name of the buffer.
     pub name: BufferName,
     pub buffer_modes: Vec<Arc<Mutex<Mode>>>,
     pub read_only: bool,
+    /// The [`Overlay`]s on the text.  Later ones take precedence.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    pub overlays: Vec<Overlay>,
+    /// The line endings of the file, worked out when it is opened and
+    /// saved.  See [`detect_line_ending`].
+    ///
+    /// [`detect_line_ending`]: #method.detect_line_ending
+    pub line_ending: LineEnding,
+    /// The starts of the lines as they are drawn, cached until the
+    /// invisible [`Overlay`]s change.  Edits shift them in place.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    line_starts: Mutex<Option<LineStarts>>,
+}
+
+/// The line endings a file is written with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// `"\n"`
+    #[default]
+    Lf,
+    /// `"\r\n"`
+    Crlf,
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::Crlf => write!(f, "CRLF"),
+        }
+    }
+}
+
+struct LineStarts {
+    hidden: Vec<Range<usize>>,
+    starts: Arc<Vec<usize>>,
 }
 
 impl Buffer {
@@ -79,10 +133,17 @@ impl Buffer {
         Buffer {
             buffer_contents: contents,
             initial_state: state.clone(),
+            saved_state: Arc::downgrade(&state),
             current_state: state,
+            markers: Arc::default(),
+            undo_groups: 0,
+            is_group_started: false,
             name,
             buffer_modes: Vec::new(),
             read_only: false,
+            overlays: Vec::new(),
+            line_ending: LineEnding::default(),
+            line_starts: Mutex::new(None),
         }
     }
 
@@ -119,6 +180,20 @@ impl Buffer {
         self.buffer_contents.iter()
     }
 
+    /// Iterate over the contents of the `Buffer` starting at `begin`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "abc\ndef").unwrap();
+    /// assert_eq!(buffer.iter_at(2).collect::<String>(), "c\ndef");
+    /// ```
+    pub fn iter_at(&self, begin: usize) -> BufferContentsIterator<'_> {
+        self.buffer_contents.iter_at(begin)
+    }
+
     /// Get the character at position `loc`.
     ///
     /// # Examples
@@ -137,6 +212,222 @@ impl Buffer {
         self.buffer_contents.get(loc)
     }
 
+    /// Iterate over the contents of the `Buffer` as they are drawn.
+    ///
+    /// Characters in invisible [`Overlay`]s are replaced by a zero
+    /// width space so they take up no room on the screen.  Locations
+    /// are the same as for [`iter`].
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    /// [`iter`]: #method.iter
+    pub fn display_iter<'a>(&'a self) -> impl Iterator<Item = char> + 'a {
+        self.display_iter_at(0)
+    }
+
+    /// Iterate over the contents of the `Buffer` as they are drawn,
+    /// starting at `begin`.
+    ///
+    /// This is the same as skipping `begin` characters of
+    /// [`display_iter`] without going through them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "abc\ndef").unwrap();
+    /// assert_eq!(buffer.display_iter_at(2).collect::<String>(), "c\ndef");
+    /// ```
+    ///
+    /// [`display_iter`]: #method.display_iter
+    pub fn display_iter_at<'a>(&'a self, begin: usize) -> impl Iterator<Item = char> + 'a {
+        let mut hidden = self.hidden_ranges().into_iter().peekable();
+        self.buffer_contents
+            .iter_at(begin)
+            .enumerate()
+            .map(move |(offset, ch)| {
+                let location = begin + offset;
+                while hidden.peek().is_some_and(|range| range.end <= location) {
+                    hidden.next();
+                }
+                match hidden.peek() {
+                    Some(range) if range.start <= location => INVISIBLE,
+                    _ => ch,
+                }
+            })
+    }
+
+    /// The ranges of the invisible [`Overlay`]s, sorted by start.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    fn hidden_ranges(&self) -> Vec<Range<usize>> {
+        let mut hidden: Vec<Range<usize>> = self
+            .overlays
+            .iter()
+            .filter(|overlay| overlay.invisible)
+            .map(Overlay::range)
+            .collect();
+        hidden.sort_by_key(|range| range.start);
+        hidden
+    }
+
+    /// Get the starts of the lines as they are drawn.
+    ///
+    /// They are only recomputed when the invisible [`Overlay`]s have
+    /// changed since the last call.  Edits update them through
+    /// [`insert_line_starts`] and [`delete_line_starts`].
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    /// [`insert_line_starts`]: #method.insert_line_starts
+    /// [`delete_line_starts`]: #method.delete_line_starts
+    fn line_starts(&self) -> Arc<Vec<usize>> {
+        let hidden = self.hidden_ranges();
+        let mut line_starts = self.line_starts.lock();
+        if let Some(ref line_starts) = *line_starts {
+            if line_starts.hidden == hidden {
+                return line_starts.starts.clone();
+            }
+        }
+        let starts: Vec<usize> = std::iter::once(0)
+            .chain(
+                self.display_iter()
+                    .enumerate()
+                    .filter(|&(_, ch)| ch == '\n')
+                    .map(|(location, _)| location + 1),
+            )
+            .collect();
+        let starts = Arc::new(starts);
+        *line_starts = Some(LineStarts {
+            hidden,
+            starts: starts.clone(),
+        });
+        starts
+    }
+
+    /// Get the cached line starts so they can be changed along with
+    /// the text.
+    ///
+    /// If there is text hidden by invisible [`Overlay`]s the cache is
+    /// dropped instead, as the [`Overlay`]s move with the edit too.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    fn edited_line_starts(&mut self) -> Option<&mut Vec<usize>> {
+        let line_starts = self.line_starts.get_mut();
+        if line_starts
+            .as_ref()
+            .is_some_and(|line_starts| !line_starts.hidden.is_empty())
+        {
+            *line_starts = None;
+        }
+        line_starts
+            .as_mut()
+            .map(|line_starts| Arc::make_mut(&mut line_starts.starts))
+    }
+
+    /// Update the cached line starts after `s` is inserted at `loc`.
+    ///
+    /// The lines after `loc` are shifted by the length of `s` and the
+    /// newlines in `s` start new ones, so the text isn't rescanned.
+    fn insert_line_starts(&mut self, loc: usize, s: &str) {
+        if let Some(starts) = self.edited_line_starts() {
+            let after = match starts.binary_search(&loc) {
+                Ok(line) => line + 1,
+                Err(line) => line,
+            };
+            let mut len_chars = 0;
+            let mut new_starts = Vec::new();
+            for ch in s.chars() {
+                len_chars += 1;
+                if ch == '\n' {
+                    new_starts.push(loc + len_chars);
+                }
+            }
+            for start in &mut starts[after..] {
+                *start += len_chars;
+            }
+            starts.splice(after..after, new_starts);
+        }
+    }
+
+    /// Update the cached line starts after the region from `begin` up
+    /// until `end` is deleted.
+    ///
+    /// The lines started by the deleted newlines are removed and the
+    /// ones after `end` are shifted back.
+    fn delete_line_starts(&mut self, begin: usize, end: usize) {
+        if let Some(starts) = self.edited_line_starts() {
+            let after = match starts.binary_search(&begin) {
+                Ok(line) => line + 1,
+                Err(line) => line,
+            };
+            let deleted = match starts.binary_search(&end) {
+                Ok(line) => line + 1,
+                Err(line) => line,
+            };
+            starts.drain(after..deleted);
+            for start in &mut starts[after..] {
+                *start -= end - begin;
+            }
+        }
+    }
+
+    /// Get the number of lines as they are drawn.
+    ///
+    /// Newlines in invisible [`Overlay`]s don't start new lines.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// assert_eq!(buffer.line_count(), 1);
+    /// buffer.insert_str(0, "abc\ndef\n").unwrap();
+    /// assert_eq!(buffer.line_count(), 3);
+    /// ```
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    pub fn line_count(&self) -> usize {
+        self.line_starts().len()
+    }
+
+    /// Get the line `location` is on as it is drawn, starting at 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "abc\ndef").unwrap();
+    /// assert_eq!(buffer.line_at(3), 0);
+    /// assert_eq!(buffer.line_at(4), 1);
+    /// ```
+    pub fn line_at(&self, location: usize) -> usize {
+        match self.line_starts().binary_search(&location) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        }
+    }
+
+    /// Get the location of the start of the line `location` is on as
+    /// it is drawn.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "abc\ndef").unwrap();
+    /// assert_eq!(buffer.line_start(3), 0);
+    /// assert_eq!(buffer.line_start(6), 4);
+    /// ```
+    pub fn line_start(&self, location: usize) -> usize {
+        let line_starts = self.line_starts();
+        match line_starts.binary_search(&location) {
+            Ok(line) => line_starts[line],
+            Err(line) => line_starts[line - 1],
+        }
+    }
+
     /// Retrieve a substring from position `begin` up until `end`.
     pub fn substring(&self, begin: usize, end: usize) -> Result<String, String> {
         self.buffer_contents
@@ -149,12 +440,16 @@ impl Buffer {
         if self.read_only {
             Err(ERROR_READ_ONLY)?
         }
+        self.check_overlays_writable(loc..loc)?;
         self.buffer_contents
             .insert(loc, c)
             .map_err(|()| "Error: Index out of bounds in Buffer::insert()")?;
+        self.markers.lock().insert(loc, 1);
+        let s = c.to_string();
+        self.insert_line_starts(loc, &s);
         self.add_change(Change {
             loc,
-            s: c.to_string(),
+            s,
             len_chars: 1,
             is_insert: true,
         });
@@ -166,13 +461,17 @@ impl Buffer {
         if self.read_only {
             Err(ERROR_READ_ONLY)?
         }
+        self.check_overlays_writable(loc..loc)?;
         self.buffer_contents
             .insert_str(loc, s)
             .map_err(|()| "Error: Index out of bounds in Buffer::insert_str()".to_string())?;
+        let len_chars = s.chars().count();
+        self.markers.lock().insert(loc, len_chars);
+        self.insert_line_starts(loc, s);
         self.add_change(Change {
             loc,
             s: s.to_string(),
-            len_chars: s.chars().count(),
+            len_chars,
             is_insert: true,
         });
         Ok(())
@@ -186,9 +485,12 @@ impl Buffer {
         let c = self
             .get(loc)
             .map_err(|()| "Error: Index out of bounds in Buffer::delete()".to_string())?;
+        self.check_overlays_writable(loc..loc + 1)?;
         self.buffer_contents
             .delete(loc)
             .map_err(|()| "Error: Index out of bounds in Buffer::delete()".to_string())?;
+        self.markers.lock().delete(loc, 1);
+        self.delete_line_starts(loc, loc + 1);
         self.add_change(Change {
             loc,
             s: c.to_string(),
@@ -203,11 +505,17 @@ impl Buffer {
         if self.read_only {
             Err(ERROR_READ_ONLY)?
         }
+        if begin > end || end > self.len() {
+            Err("Error: Index out of bounds in Buffer::delete_region()")?
+        }
+        self.check_overlays_writable(begin..end)?;
         let s = self.substring(begin, end)?;
         self.buffer_contents
             .delete_region(begin, end)
             .map_err(|()| "Error: Index out of bounds in Buffer::delete_region()".to_string())?;
         let len_chars = s.chars().count();
+        self.markers.lock().delete(begin, len_chars);
+        self.delete_line_starts(begin, end);
         self.add_change(Change {
             loc: begin,
             s: s,
@@ -223,13 +531,51 @@ impl Buffer {
         self.delete_region(0, len)
     }
 
+    /// Replace the contents of the `Buffer` with `contents`.
+    ///
+    /// Only the part that differs is changed so cursors before and
+    /// after it stay on the same characters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "a\nb\nc").unwrap();
+    /// buffer.replace_contents("a\nB\nX\nc").unwrap();
+    /// assert_eq!(buffer.to_string(), "a\nB\nX\nc");
+    /// ```
+    pub fn replace_contents(&mut self, contents: &str) -> Result<(), String> {
+        let old: Vec<char> = self.iter().collect();
+        let new: Vec<char> = contents.chars().collect();
+        let prefix = old.iter().zip(&new).take_while(|&(a, b)| a == b).count();
+        let suffix = old[prefix..]
+            .iter()
+            .rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|&(a, b)| a == b)
+            .count();
+        if prefix + suffix < old.len() {
+            self.delete_region(prefix, old.len() - suffix)?;
+        }
+        if prefix + suffix < new.len() {
+            let s: String = new[prefix..new.len() - suffix].iter().collect();
+            self.insert_str(prefix, &s)?;
+        }
+        Ok(())
+    }
+
     /// Handle adding another node to the state graph and pointing `current_state` to it.
     fn add_change(&mut self, change: Change) {
         let node = Arc::new(Mutex::new(StateNode {
             pred: Arc::downgrade(&self.current_state),
             succ: Vec::new(),
             change,
+            joined: self.undo_groups != 0 && self.is_group_started,
         }));
+        if self.undo_groups != 0 {
+            self.is_group_started = true;
+        }
         {
             let mut current_state = self.current_state.lock();
             current_state.succ.push(node.clone());
@@ -241,7 +587,8 @@ impl Buffer {
     ///
     /// This will revert the last change made to the buffer.  Any call
     /// to `insert`, `insert_str`, `delete`, or `delete_range` is
-    /// considered a change.
+    /// considered a change.  Changes made in an undo group are undone
+    /// together.  See [`begin_undo_group`].
     ///
     /// If there are no edits to undo, returns false.
     ///
@@ -263,28 +610,55 @@ impl Buffer {
     /// assert!(!buffer.undo().unwrap());
     /// assert_eq!(format!("{}", buffer), "");
     /// ```
+    ///
+    /// [`begin_undo_group`]: #method.begin_undo_group
     pub fn undo(&mut self) -> Result<bool, String> {
         if self.read_only {
             Err(ERROR_READ_ONLY)?
         }
+        let mut joined = self.current_state.lock().joined;
+        if !self.undo_change()? {
+            return Ok(false);
+        }
+        while joined {
+            joined = self.current_state.lock().joined;
+            self.undo_change()?;
+        }
+        Ok(true)
+    }
+
+    /// Check if the change that would be redone next is part of the
+    /// same undo group as the current state.
+    fn is_next_joined(&self) -> bool {
+        let current_state = self.current_state.lock();
+        current_state
+            .succ
+            .last()
+            .is_some_and(|next_state| next_state.lock().joined)
+    }
+
+    fn undo_change(&mut self) -> Result<bool, String> {
         let pred;
         {
-            let current_state = self.current_state.lock();
+            let current_state = self.current_state.clone();
+            let current_state = current_state.lock();
             pred = match current_state.pred.upgrade() {
                 Some(pred) => pred,
                 None => return Ok(false),
             };
-            if current_state.change.is_insert {
+            let change = &current_state.change;
+            if change.is_insert {
                 self.buffer_contents
-                    .delete_region(
-                        current_state.change.loc,
-                        current_state.change.loc + current_state.change.len_chars,
-                    )
+                    .delete_region(change.loc, change.loc + change.len_chars)
                     .map_err(|()| "Error: Index out of bounds in Buffer::undo()".to_string())?;
+                self.markers.lock().delete(change.loc, change.len_chars);
+                self.delete_line_starts(change.loc, change.loc + change.len_chars);
             } else {
                 self.buffer_contents
-                    .insert_str(current_state.change.loc, &current_state.change.s)
+                    .insert_str(change.loc, &change.s)
                     .map_err(|()| "Error: Index out of bounds in Buffer::undo()".to_string())?;
+                self.markers.lock().insert(change.loc, change.len_chars);
+                self.insert_line_starts(change.loc, &change.s);
             }
         }
         self.current_state = pred;
@@ -317,22 +691,32 @@ impl Buffer {
         if self.read_only {
             Err(ERROR_READ_ONLY)?
         }
+        if !self.redo_change()? {
+            return Ok(false);
+        }
+        while self.is_next_joined() && self.redo_change()? {}
+        Ok(true)
+    }
+
+    fn redo_change(&mut self) -> Result<bool, String> {
         let current_state = self.current_state.clone();
         let current_state = current_state.lock();
         if let Some(next_state) = current_state.succ.last() {
             {
                 let next_state = next_state.lock();
-                if !next_state.change.is_insert {
+                let change = &next_state.change;
+                if !change.is_insert {
                     self.buffer_contents
-                        .delete_region(
-                            next_state.change.loc,
-                            next_state.change.loc + next_state.change.len_chars,
-                        )
+                        .delete_region(change.loc, change.loc + change.len_chars)
                         .map_err(|()| "Error: Index out of bounds in Buffer::redo()")?;
+                    self.markers.lock().delete(change.loc, change.len_chars);
+                    self.delete_line_starts(change.loc, change.loc + change.len_chars);
                 } else {
                     self.buffer_contents
-                        .insert_str(next_state.change.loc, &next_state.change.s)
+                        .insert_str(change.loc, &change.s)
                         .map_err(|()| "Error: Index out of bounds in Buffer::redo()")?;
+                    self.markers.lock().insert(change.loc, change.len_chars);
+                    self.insert_line_starts(change.loc, &change.s);
                 }
             }
             self.current_state = next_state.clone();
@@ -341,12 +725,264 @@ impl Buffer {
         Ok(false)
     }
 
+    /// Start grouping changes so they are undone and redone together.
+    ///
+    /// Groups can be nested.  The changes are grouped until the
+    /// outermost group is closed by [`end_undo_group`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "ac").unwrap();
+    /// buffer.begin_undo_group();
+    /// buffer.insert(0, '(').unwrap();
+    /// buffer.insert(3, ')').unwrap();
+    /// buffer.end_undo_group();
+    /// assert_eq!(buffer.to_string(), "(ac)");
+    ///
+    /// assert!(buffer.undo().unwrap());
+    /// assert_eq!(buffer.to_string(), "ac");
+    /// assert!(buffer.redo().unwrap());
+    /// assert_eq!(buffer.to_string(), "(ac)");
+    /// ```
+    ///
+    /// [`end_undo_group`]: #method.end_undo_group
+    pub fn begin_undo_group(&mut self) {
+        if self.undo_groups == 0 {
+            self.is_group_started = false;
+        }
+        self.undo_groups += 1;
+    }
+
+    /// Close the undo group opened by the last call to
+    /// [`begin_undo_group`].
+    ///
+    /// [`begin_undo_group`]: #method.begin_undo_group
+    pub fn end_undo_group(&mut self) {
+        self.undo_groups = self.undo_groups.saturating_sub(1);
+    }
+
+    /// Append `s` to the end of the `Buffer` without it being undoable.
+    ///
+    /// The history is kept, so the changes made before and after can
+    /// still be undone, and whether the `Buffer` is modified doesn't
+    /// change.  The text is appended even if the `Buffer` is
+    /// [`read_only`].  It is meant for text the user didn't type, such
+    /// as a log or a file being read in.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.insert_str(0, "ab").unwrap();
+    /// buffer.read_only = true;
+    /// buffer.append_without_history("cd").unwrap();
+    /// assert_eq!(buffer.to_string(), "abcd");
+    /// assert!(buffer.read_only);
+    ///
+    /// buffer.read_only = false;
+    /// assert!(buffer.undo().unwrap());
+    /// assert_eq!(buffer.to_string(), "cd");
+    /// assert!(!buffer.is_modified());
+    /// ```
+    ///
+    /// [`read_only`]: #structfield.read_only
+    pub fn append_without_history(&mut self, s: &str) -> Result<(), String> {
+        let len = self.len();
+        self.buffer_contents.insert_str(len, s).map_err(|()| {
+            "Error: Index out of bounds in Buffer::append_without_history()".to_string()
+        })?;
+        self.markers.lock().insert(len, s.chars().count());
+        self.insert_line_starts(len, s);
+        Ok(())
+    }
+
+    /// Delete the region from `begin` up until `end` without it being
+    /// undoable.
+    ///
+    /// Like [`append_without_history`], the history, whether the
+    /// `Buffer` is modified and [`read_only`] are left alone.  Changes
+    /// in the history after `end` are moved back so they can still be
+    /// undone.  It is meant for dropping old text the user didn't
+    /// type, such as the start of a log.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// buffer.append_without_history("abc\n").unwrap();
+    /// buffer.insert(4, 'd').unwrap();
+    /// buffer.delete_without_history(0, 4).unwrap();
+    /// assert_eq!(buffer.to_string(), "d");
+    ///
+    /// assert!(buffer.undo().unwrap());
+    /// assert_eq!(buffer.to_string(), "");
+    /// ```
+    ///
+    /// [`append_without_history`]: #method.append_without_history
+    /// [`read_only`]: #structfield.read_only
+    pub fn delete_without_history(&mut self, begin: usize, end: usize) -> Result<(), String> {
+        if begin > end || end > self.len() {
+            Err("Error: Index out of bounds in Buffer::delete_without_history()")?
+        }
+        self.buffer_contents
+            .delete_region(begin, end)
+            .map_err(|()| {
+                "Error: Index out of bounds in Buffer::delete_without_history()".to_string()
+            })?;
+        let len = end - begin;
+        self.markers.lock().delete(begin, len);
+        self.delete_line_starts(begin, end);
+        let mut states = vec![self.initial_state.clone()];
+        while let Some(state) = states.pop() {
+            let mut state = state.lock();
+            if state.change.loc >= end {
+                state.change.loc -= len;
+            } else if state.change.loc > begin {
+                state.change.loc = begin;
+            }
+            states.extend(state.succ.iter().cloned());
+        }
+        Ok(())
+    }
+
     /// Erase the history of the `Buffer`.
+    ///
+    /// The `Buffer` is then considered unmodified.
     pub fn erase_history(&mut self) {
         let state: Arc<Mutex<StateNode>> = Arc::default();
         self.initial_state = state.clone();
+        self.saved_state = Arc::downgrade(&state);
         self.current_state = state;
     }
+
+    /// Check if the `Buffer` has been changed since it was last saved.
+    ///
+    /// Undoing back to the saved state makes the `Buffer` unmodified.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName};
+    /// let mut buffer = Buffer::new("*scratch*".into());
+    /// assert!(!buffer.is_modified());
+    /// buffer.insert_str(0, "abc").unwrap();
+    /// assert!(buffer.is_modified());
+    /// buffer.mark_saved();
+    /// assert!(!buffer.is_modified());
+    /// buffer.undo().unwrap();
+    /// assert!(buffer.is_modified());
+    /// buffer.redo().unwrap();
+    /// assert!(!buffer.is_modified());
+    /// ```
+    pub fn is_modified(&self) -> bool {
+        match self.saved_state.upgrade() {
+            Some(saved_state) => !Arc::ptr_eq(&saved_state, &self.current_state),
+            None => true,
+        }
+    }
+
+    /// Mark the current state of the `Buffer` as saved.
+    ///
+    /// The [`line_ending`] is worked out again from what was saved.
+    ///
+    /// [`line_ending`]: #structfield.line_ending
+    pub fn mark_saved(&mut self) {
+        self.saved_state = Arc::downgrade(&self.current_state);
+        self.detect_line_ending();
+    }
+
+    /// Set the [`line_ending`] from the contents.
+    ///
+    /// Line endings are [`Crlf`] if the first line ends in `"\r\n"`,
+    /// otherwise they are [`Lf`].  Only the first line is looked at.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{Buffer, BufferName, LineEnding};
+    /// let mut buffer = Buffer::new_with_contents("*scratch*".into(), "ab\r\ncd\n");
+    /// assert_eq!(buffer.line_ending, LineEnding::Lf);
+    /// buffer.detect_line_ending();
+    /// assert_eq!(buffer.line_ending, LineEnding::Crlf);
+    /// ```
+    ///
+    /// [`line_ending`]: #structfield.line_ending
+    /// [`Crlf`]: enum.LineEnding.html#variant.Crlf
+    /// [`Lf`]: enum.LineEnding.html#variant.Lf
+    pub fn detect_line_ending(&mut self) {
+        let mut previous = None;
+        self.line_ending = LineEnding::Lf;
+        for c in self.iter() {
+            if c == '\n' {
+                if previous == Some('\r') {
+                    self.line_ending = LineEnding::Crlf;
+                }
+                break;
+            }
+            previous = Some(c);
+        }
+    }
+
+    /// Add a [`Marker`] at `location`.
+    ///
+    /// The `location` is kept inside the `Buffer`.
+    ///
+    /// [`Marker`]: struct.Marker.html
+    pub fn add_marker(&self, location: usize, gravity: Gravity) -> Marker {
+        MarkerSet::add(&self.markers, location.min(self.len()), gravity)
+    }
+
+    /// Move `marker` to `location`.
+    ///
+    /// The `location` is kept inside the `Buffer`.  It is an error if
+    /// `marker` belongs to a different `Buffer`.
+    pub fn set_marker(&self, marker: &mut Marker, location: usize) -> Result<(), String> {
+        if !MarkerSet::contains(&self.markers, marker) {
+            Err("Error: Marker belongs to a different buffer")?
+        }
+        self.markers.lock().set(marker, location.min(self.len()));
+        Ok(())
+    }
+
+    /// Iterate over the [`Overlay`]s containing the character at
+    /// `location`, starting with the one that takes precedence.
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    pub fn overlays_at<'a>(&'a self, location: usize) -> impl Iterator<Item = &'a Overlay> + 'a {
+        self.overlays
+            .iter()
+            .rev()
+            .filter(move |overlay| overlay.contains(location))
+    }
+
+    /// Check that changing `range` doesn't touch a read only
+    /// [`Overlay`].  An empty `range` is an insertion, which is
+    /// allowed at the ends of an [`Overlay`].
+    ///
+    /// [`Overlay`]: struct.Overlay.html
+    fn check_overlays_writable(&self, range: Range<usize>) -> Result<(), String> {
+        let is_blocked = |overlay: &Overlay| {
+            let overlay = overlay.range();
+            if range.start == range.end {
+                overlay.start < range.start && range.start < overlay.end
+            } else {
+                overlay.start < range.end && range.start < overlay.end
+            }
+        };
+        if self
+            .overlays
+            .iter()
+            .any(|overlay| overlay.read_only && is_blocked(overlay))
+        {
+            Err(ERROR_READ_ONLY_OVERLAY)?
+        }
+        Ok(())
+    }
 }
 
 impl fmt::Display for Buffer {
@@ -415,8 +1051,8 @@ pub fn update_cursor(
                     .lock()
                     .change
                     .offset_cursor_redo(*ret_location);
-                *ret_state = Arc::downgrade(&buffer.current_state)
             }
+            *ret_state = Arc::downgrade(&buffer.current_state);
             return;
         }
     };
@@ -433,7 +1069,7 @@ pub fn update_cursor(
                 }
             }
         }
-        *ret_state = Weak::new();
+        *ret_state = Arc::downgrade(&buffer.initial_state);
         *ret_location = location;
         return;
     }
@@ -472,6 +1108,13 @@ pub fn update_cursor(
     }
 }
 
+/// Get the current state of `buffer`.
+///
+/// The state changes whenever the contents of `buffer` change.
+pub fn current_state(buffer: &Buffer) -> Weak<Mutex<StateNode>> {
+    Arc::downgrade(&buffer.current_state)
+}
+
 pub fn is_updated_cursor(buffer: &Buffer, state: &Weak<Mutex<StateNode>>) -> bool {
     Arc::ptr_eq(
         &buffer.current_state,
@@ -583,6 +1226,56 @@ mod tests {
         assert_eq!(cursor.get(), 0);
     }
 
+    #[test]
+    fn update_cursor_across_changes_from_initial_state() {
+        use cursor::Cursor;
+        let mut buffer = Buffer::new("*scratch*".into());
+        let mut cursor = Cursor::new();
+        cursor.update(&buffer);
+        buffer.insert_str(0, "ab").unwrap();
+        buffer.insert_str(2, "cd").unwrap();
+        cursor.update(&buffer);
+        assert_eq!(cursor.get(), 4);
+
+        buffer.undo().unwrap();
+        buffer.undo().unwrap();
+        cursor.update(&buffer);
+        assert_eq!(cursor.get(), 0);
+        buffer.redo().unwrap();
+        buffer.redo().unwrap();
+        cursor.update(&buffer);
+        assert_eq!(cursor.get(), 4);
+    }
+
+    #[test]
+    fn delete_region_out_of_bounds() {
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "abcd");
+        assert!(buffer.delete_region(2, 5).is_err());
+        assert!(buffer.delete_region(3, 1).is_err());
+        assert_eq!(buffer.to_string(), "abcd");
+        assert!(!buffer.undo().unwrap());
+    }
+
+    #[test]
+    fn read_only_overlays() {
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "abcdef");
+        let mut overlay = Overlay::new(&buffer, 2..4);
+        overlay.read_only = true;
+        buffer.overlays.push(overlay);
+        buffer.overlays.push(Overlay::new(&buffer, 0..6));
+        assert!(buffer.insert(3, 'x').is_err());
+        assert!(buffer.delete(2).is_err());
+        assert!(buffer.delete_region(0, 3).is_err());
+        assert!(buffer.delete_region(3, 6).is_err());
+        buffer.insert_str(2, "<").unwrap();
+        buffer.insert_str(5, ">").unwrap();
+        buffer.delete_region(0, 2).unwrap();
+        assert_eq!(buffer.to_string(), "<cd>ef");
+        assert_eq!(buffer.overlays[0].range(), 1..3);
+        assert_eq!(buffer.overlays_at(1).count(), 2);
+        assert_eq!(buffer.overlays_at(4).count(), 1);
+    }
+
     #[test]
     fn new_with_contents_redo_after_undo() {
         let mut buffer = Buffer::new_with_contents("*scratch*".into(), "Example text");
@@ -595,4 +1288,58 @@ mod tests {
         buffer.redo().unwrap();
         assert_eq!(buffer.to_string(), "Some Example text");
     }
+
+    #[test]
+    fn line_starts_follow_changes() {
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "ab\ncd\nef");
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!(buffer.line_at(7), 2);
+        assert_eq!(buffer.line_start(7), 6);
+
+        buffer.insert_str(1, "\n").unwrap();
+        assert_eq!(buffer.line_count(), 4);
+        assert_eq!(buffer.line_at(8), 3);
+        assert_eq!(buffer.line_start(8), 7);
+
+        let mut overlay = Overlay::new(&buffer, 3..7);
+        overlay.invisible = true;
+        buffer.overlays.push(overlay);
+        assert_eq!(buffer.line_count(), 2);
+        assert_eq!(buffer.line_at(8), 1);
+        assert_eq!(buffer.line_start(8), 2);
+
+        buffer.undo().unwrap();
+        buffer.overlays.clear();
+        assert_eq!(buffer.line_count(), 3);
+        assert_eq!(buffer.line_start(4), 3);
+    }
+
+    #[test]
+    fn line_starts_shift_with_edits() {
+        fn assert_rescanned(buffer: &Buffer) {
+            let rescanned = Buffer::new_with_contents("*scratch*".into(), &buffer.to_string());
+            assert_eq!(buffer.line_starts(), rescanned.line_starts());
+        }
+
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "ab\ncd\nef");
+        assert_eq!(buffer.line_count(), 3);
+        buffer.insert_str(4, "x\ny\n").unwrap();
+        assert_rescanned(&buffer);
+        buffer.insert(0, '\n').unwrap();
+        assert_rescanned(&buffer);
+        buffer.delete(3).unwrap();
+        assert_rescanned(&buffer);
+        buffer.delete_region(2, 8).unwrap();
+        assert_rescanned(&buffer);
+        buffer.undo().unwrap();
+        assert_rescanned(&buffer);
+        buffer.undo().unwrap();
+        assert_rescanned(&buffer);
+        buffer.redo().unwrap();
+        assert_rescanned(&buffer);
+        buffer.append_without_history("g\nh").unwrap();
+        assert_rescanned(&buffer);
+        buffer.delete_without_history(0, 5).unwrap();
+        assert_rescanned(&buffer);
+    }
 }
@@ -1,13 +1,15 @@
 pub type BufferContentsError = ();
 pub type BufferContentsResult<T> = Result<T, BufferContentsError>;
 
-#[cfg(not(test))]
+// Tests and the fuzz target use tiny chunks so that edits often cross
+// chunk boundaries.
+#[cfg(not(any(test, feature = "small-chunks")))]
 const MAX_LENGTH_CHARS: usize = 1024;
-#[cfg(test)]
+#[cfg(any(test, feature = "small-chunks"))]
 const MAX_LENGTH_CHARS: usize = 4;
-#[cfg(not(test))]
+#[cfg(not(any(test, feature = "small-chunks")))]
 const PREFERRED_LENGTH_CHARS: usize = 512;
-#[cfg(test)]
+#[cfg(any(test, feature = "small-chunks"))]
 const PREFERRED_LENGTH_CHARS: usize = 2;
 
 #[derive(Debug, Clone)]
@@ -50,6 +52,30 @@ impl BufferContents {
         }
     }
 
+    /// Iterate over the contents starting at `loc`.
+    pub fn iter_at<'a>(&'a self, mut loc: usize) -> BufferContentsIterator<'a> {
+        for (outer, s) in self.array.iter().enumerate() {
+            if loc < s.len_chars {
+                let mut inner = s.str.chars();
+                for _ in 0..loc {
+                    inner.next();
+                }
+                return BufferContentsIterator {
+                    buffer_contents: self,
+                    outer,
+                    inner: Some(inner),
+                };
+            } else {
+                loc -= s.len_chars;
+            }
+        }
+        BufferContentsIterator {
+            buffer_contents: self,
+            outer: self.array.len(),
+            inner: None,
+        }
+    }
+
     pub fn get(&self, mut loc: usize) -> BufferContentsResult<char> {
         for s in &self.array {
             if loc < s.len_chars {
@@ -6,6 +6,8 @@ pub struct StateNode {
     pub pred: Weak<Mutex<StateNode>>,
     pub succ: Vec<Arc<Mutex<StateNode>>>,
     pub change: Change,
+    /// Whether the change is undone along with the one before it.
+    pub joined: bool,
 }
 
 pub struct Change {
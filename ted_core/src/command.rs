@@ -51,10 +51,13 @@ use std::sync::Arc;
 /// # }
 /// ```
 ///
-/// If the event is moderately short, then the [`Display`] can be safely ignored.
+/// Commands are ran on a worker thread by the [`DisplayCoordinator`],
+/// which keeps drawing the [`Display`] while they run.  So the
+/// [`Display`] can be safely ignored, even by long running commands.
 ///
 /// [`Buffer`]: struct.Buffer.html
-/// [`Display`]: trait.Display.html
+/// [`Display`]: struct.Display.html
+/// [`DisplayCoordinator`]: struct.DisplayCoordinator.html
 /// [`State`]: struct.State.html
 pub trait Command: fmt::Debug + Send + Sync {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String>;
@@ -1,8 +1,10 @@
 use draw::*;
 use input::Input;
 use layout::Layout;
+use mode_line::ModeLine;
 use parking_lot::Mutex;
 use renderer::Renderer;
+use screen::Screen;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use window::Window;
@@ -10,9 +12,13 @@ use window::Window;
 /// An implementation of [`Renderer`] for debugging.
 ///
 /// This renders a 20 column, 15 row renderer via a 2D array of
-/// characters and allow for normal usage as a [`Renderer`].
+/// characters and allow for normal usage as a [`Renderer`].  Use
+/// [`with_size`] for other sizes and [`render`] to see the screen as
+/// text.
 ///
-/// You can emulate the user typing by pushing to `inputs`.
+/// You can emulate the user typing by pushing to `inputs`.  Mouse
+/// events are reported at `mouse_position` and pastes insert
+/// `pasted_text`.
 ///
 /// # Examples
 ///
@@ -38,7 +44,7 @@ use window::Window;
 ///
 /// let state = State::new(renderer);
 /// state.display.show().unwrap();
-/// assert_eq!(unsafe { state.display.debug_renderer() }.buffer,
+/// assert_eq!(state.display.debug_renderer().unwrap().buffer,
 ///            vec!["                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
@@ -52,8 +58,8 @@ use window::Window;
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
-///                 "                    ".chars().collect::<Vec<_>>(),
-///                 "*scratch*           ".chars().collect::<Vec<_>>()]);
+///                 "*scratch*           ".chars().collect::<Vec<_>>(),
+///                 "                    ".chars().collect::<Vec<_>>()]);
 ///
 /// {
 ///     let buffer = state.display.selected_window_buffer();
@@ -61,9 +67,9 @@ use window::Window;
 ///     buffer.insert_str(0, "abcd").unwrap();
 /// }
 /// state.display.show().unwrap();
-/// assert_eq!(unsafe { state.display.debug_renderer() }.selected_cursors, vec![(0, 4)]);
-/// assert_eq!(unsafe { state.display.debug_renderer() }.unselected_cursors, vec![]);
-/// assert_eq!(unsafe { state.display.debug_renderer() }.buffer,
+/// assert_eq!(state.display.debug_renderer().unwrap().selected_cursors, vec![(0, 4)]);
+/// assert_eq!(state.display.debug_renderer().unwrap().unselected_cursors, vec![]);
+/// assert_eq!(state.display.debug_renderer().unwrap().buffer,
 ///            vec!["abcd                ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
@@ -77,45 +83,117 @@ use window::Window;
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
 ///                 "                    ".chars().collect::<Vec<_>>(),
-///                 "                    ".chars().collect::<Vec<_>>(),
-///                 "*scratch*           ".chars().collect::<Vec<_>>()]);
+///                 "*scratch*           ".chars().collect::<Vec<_>>(),
+///                 "                    ".chars().collect::<Vec<_>>()]);
 /// ```
 ///
 /// [`Renderer`]: trait.Renderer.html
+/// [`with_size`]: #method.with_size
+/// [`render`]: #method.render
 pub struct DebugRenderer {
     pub inputs: VecDeque<Input>,
     pub buffer: Vec<Vec<char>>,
+    /// The [`Attribute`] of each character in `buffer` other than
+    /// the cursors.
+    ///
+    /// [`Attribute`]: draw/enum.Attribute.html
+    pub attributes: Vec<Vec<Option<Attribute>>>,
     pub selected_cursors: Vec<(usize, usize)>,
     pub unselected_cursors: Vec<(usize, usize)>,
-}
-
-pub unsafe fn debug_renderer(renderer: &mut Renderer) -> &mut DebugRenderer {
-    &mut *((renderer as *mut Renderer) as *mut DebugRenderer)
+    pub mouse_position: Option<(usize, usize)>,
+    pub pasted_text: Option<String>,
+    pub screen: Screen,
 }
 
 impl DebugRenderer {
     pub fn new() -> Self {
         DebugRenderer::from(Vec::new())
     }
-}
 
-impl From<Vec<Input>> for DebugRenderer {
-    fn from(inputs: Vec<Input>) -> Self {
-        let mut row = Vec::with_capacity(20);
-        for _ in 0..row.capacity() {
-            row.push(' ');
-        }
-        let mut cols = Vec::with_capacity(15);
-        for _ in 0..cols.capacity() {
-            cols.push(row.clone());
-        }
+    /// Create a `DebugRenderer` with `rows` rows and `columns` columns.
+    pub fn with_size(rows: usize, columns: usize) -> Self {
         DebugRenderer {
-            inputs: inputs.into(),
-            buffer: cols,
+            inputs: VecDeque::new(),
+            buffer: vec![vec![' '; columns]; rows],
+            attributes: vec![vec![None; columns]; rows],
             selected_cursors: Vec::new(),
             unselected_cursors: Vec::new(),
+            mouse_position: None,
+            pasted_text: None,
+            screen: Screen::new(),
         }
     }
+
+    /// Render the screen as text to compare against in tests.
+    ///
+    /// Each row is surrounded by `|`.  If a row has any cursors or
+    /// [`Attribute`]s, it is followed by a line surrounded by `:`
+    /// that marks the selected cursor with `^`, other cursors with
+    /// `'`, inverted text with `~`, the selected mode line with `=`
+    /// and other mode lines with `-`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::{DebugRenderer, State};
+    /// let state = State::new(DebugRenderer::with_size(3, 12));
+    /// state.display.selected_window().lock().insert_str("ab").unwrap();
+    /// state.display.show().unwrap();
+    /// assert_eq!(
+    ///     state.display.debug_renderer().unwrap().render(),
+    ///     "|ab          |\n\
+    ///      :  ^         :\n\
+    ///      |*scratch*   |\n\
+    ///      :============:\n\
+    ///      |            |\n"
+    /// );
+    /// ```
+    ///
+    /// [`Attribute`]: draw/enum.Attribute.html
+    pub fn render(&self) -> String {
+        let mut rendered = String::new();
+        for (y, row) in self.buffer.iter().enumerate() {
+            rendered.push('|');
+            rendered.extend(row);
+            rendered.push_str("|\n");
+
+            let mut marks: Vec<char> = self.attributes[y]
+                .iter()
+                .map(|attribute| match attribute {
+                    Some(Attribute::Inverted) => '~',
+                    Some(Attribute::SelectedModeLine) => '=',
+                    Some(Attribute::UnselectedModeLine) => '-',
+                    Some(Attribute::SelectedCursor) | Some(Attribute::UnselectedCursor) | None => {
+                        ' '
+                    }
+                })
+                .collect();
+            for &(cursor_y, x) in &self.unselected_cursors {
+                if cursor_y == y && x < marks.len() {
+                    marks[x] = '\'';
+                }
+            }
+            for &(cursor_y, x) in &self.selected_cursors {
+                if cursor_y == y && x < marks.len() {
+                    marks[x] = '^';
+                }
+            }
+            if marks.iter().any(|&mark| mark != ' ') {
+                rendered.push(':');
+                rendered.extend(marks);
+                rendered.push_str(":\n");
+            }
+        }
+        rendered
+    }
+}
+
+impl From<Vec<Input>> for DebugRenderer {
+    fn from(inputs: Vec<Input>) -> Self {
+        let mut renderer = DebugRenderer::with_size(15, 20);
+        renderer.inputs = inputs.into();
+        renderer
+    }
 }
 
 impl Renderer for DebugRenderer {
@@ -123,24 +201,50 @@ impl Renderer for DebugRenderer {
         &mut self,
         layout: &Layout,
         selected_window: Option<&Arc<Mutex<Window>>>,
-        _message: Option<&str>,
+        mode_line: &ModeLine,
+        message: Option<&str>,
     ) -> Result<(), String> {
-        draw(self, layout, selected_window, 15, 20)?;
-        Ok(())
+        let (rows, columns) = self.size();
+        draw_with(self, rows, columns, |screen| {
+            draw_frame(screen, layout, selected_window, mode_line, message)
+        })
     }
     fn getch(&mut self) -> Option<Input> {
         self.inputs.pop_front()
     }
+    fn size(&self) -> (usize, usize) {
+        (
+            self.buffer.len(),
+            self.buffer.first().map_or(0, |row| row.len()),
+        )
+    }
+    fn mouse_position(&self) -> Option<(usize, usize)> {
+        self.mouse_position
+    }
+    fn take_pasted_text(&mut self) -> Option<String> {
+        self.pasted_text.take()
+    }
 }
 impl DrawableRenderer for DebugRenderer {
+    fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
     fn erase(&mut self) -> Result<(), String> {
-        self.selected_cursors.clear();
-        self.unselected_cursors.clear();
         for r in &mut self.buffer {
             for c in r {
                 *c = ' ';
             }
         }
+        for r in &mut self.attributes {
+            for a in r {
+                *a = None;
+            }
+        }
+        Ok(())
+    }
+    fn clear_cursors(&mut self) -> Result<(), String> {
+        self.selected_cursors.clear();
+        self.unselected_cursors.clear();
         Ok(())
     }
     fn putch(&mut self, y: usize, x: usize, ch: Character) -> Result<(), String> {
@@ -149,14 +253,16 @@ impl DrawableRenderer for DebugRenderer {
             Character::VLine => '|',
             Character::HLine => '-',
         };
-        if y > self.buffer.len() {
+        if y >= self.buffer.len() {
             Err("Error: DebugRenderer::putch(): y out of bounds".to_string())
         } else {
             let row = &mut self.buffer[y];
-            if x > row.len() {
+            if x >= row.len() {
                 Err("Error: DebugRenderer::putch(): x out of bounds".to_string())
             } else {
                 row[x] = c;
+                // Like curses, writing a character resets its attribute.
+                self.attributes[y][x] = None;
                 Ok(())
             }
         }
@@ -165,7 +271,11 @@ impl DrawableRenderer for DebugRenderer {
         match at {
             Attribute::SelectedCursor => self.selected_cursors.push((y, x)),
             Attribute::UnselectedCursor => self.unselected_cursors.push((y, x)),
-            Attribute::Inverted => {}
+            Attribute::Inverted | Attribute::SelectedModeLine | Attribute::UnselectedModeLine => {
+                if let Some(a) = self.attributes.get_mut(y).and_then(|row| row.get_mut(x)) {
+                    *a = Some(at);
+                }
+            }
         }
         Ok(())
     }
@@ -179,7 +289,7 @@ mod tests {
     use window::clone_window;
 
     fn debug_renderer(renderer: &Renderer) -> &DebugRenderer {
-        unsafe { &*((&*renderer as *const Renderer) as *const DebugRenderer) }
+        renderer.downcast_ref().unwrap()
     }
 
     #[test]
@@ -212,7 +322,7 @@ mod tests {
         let state = State::new(DebugRenderer::new());
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
@@ -227,8 +337,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
 
@@ -239,15 +349,15 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.selected_cursors,
+            state.display.debug_renderer().unwrap().selected_cursors,
             vec![(0, 4)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.unselected_cursors,
+            state.display.debug_renderer().unwrap().unselected_cursors,
             vec![]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abcd                ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
@@ -262,8 +372,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -291,11 +401,11 @@ mod tests {
             vec![(0, 0)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.unselected_cursors,
+            state.display.debug_renderer().unwrap().unselected_cursors,
             vec![(8, 0)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
@@ -310,8 +420,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
 
@@ -324,15 +434,15 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.selected_cursors,
+            state.display.debug_renderer().unwrap().selected_cursors,
             vec![(0, 4)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.unselected_cursors,
+            state.display.debug_renderer().unwrap().unselected_cursors,
             vec![(8, 4)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abcd                ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
@@ -347,8 +457,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -372,7 +482,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
@@ -387,8 +497,8 @@ mod tests {
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
-                "          |         ".chars().collect::<Vec<_>>(),
-                "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
 
@@ -399,15 +509,15 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.selected_cursors,
+            state.display.debug_renderer().unwrap().selected_cursors,
             vec![(0, 4)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.unselected_cursors,
+            state.display.debug_renderer().unwrap().unselected_cursors,
             vec![(0, 15)]
         );
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abcd      |abcd     ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
@@ -422,8 +532,8 @@ mod tests {
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
-                "          |         ".chars().collect::<Vec<_>>(),
-                "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -457,7 +567,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "cd        |cd       ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
@@ -472,8 +582,8 @@ mod tests {
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
                 "          |         ".chars().collect::<Vec<_>>(),
-                "          |         ".chars().collect::<Vec<_>>(),
-                "*scratch* |*scratch*".chars().collect::<Vec<_>>()
+                "*scratch* |*scratch*".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -488,7 +598,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abc                 ".chars().collect::<Vec<_>>(),
                 "def                 ".chars().collect::<Vec<_>>(),
@@ -503,8 +613,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -519,7 +629,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abcdefghijklmnopqrst".chars().collect::<Vec<_>>(),
                 "uvwxyz              ".chars().collect::<Vec<_>>(),
@@ -534,8 +644,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
 
@@ -555,7 +665,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "abcdefghijklmnopqrst".chars().collect::<Vec<_>>(),
                 "uvwxyz              ".chars().collect::<Vec<_>>(),
@@ -570,8 +680,8 @@ mod tests {
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
                 "                    ".chars().collect::<Vec<_>>(),
-                "                    ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -591,7 +701,7 @@ mod tests {
         }
         state.display.show().unwrap();
         assert_eq!(
-            unsafe { state.display.debug_renderer() }.buffer,
+            state.display.debug_renderer().unwrap().buffer,
             vec![
                 "a                   ".chars().collect::<Vec<_>>(),
                 "b                   ".chars().collect::<Vec<_>>(),
@@ -606,8 +716,8 @@ mod tests {
                 "k                   ".chars().collect::<Vec<_>>(),
                 "l                   ".chars().collect::<Vec<_>>(),
                 "m                   ".chars().collect::<Vec<_>>(),
-                "n                   ".chars().collect::<Vec<_>>(),
-                "*scratch*           ".chars().collect::<Vec<_>>()
+                "*scratch*           ".chars().collect::<Vec<_>>(),
+                "                    ".chars().collect::<Vec<_>>()
             ]
         );
     }
@@ -30,10 +30,36 @@ impl Display {
         self.selected_frame.lock().selected_window_buffer()
     }
 
-    pub unsafe fn debug_renderer(&self) -> MappedMutexGuard<DebugRenderer> {
-        MutexGuard::map(self.selected_frame.lock(), |f| {
-            debug_renderer(&mut *f.renderer)
+    /// Get the [`DebugRenderer`] drawing the selected [`Frame`].
+    ///
+    /// It is an error if the [`Frame`] is drawn by a different kind
+    /// of [`Renderer`].
+    ///
+    /// [`DebugRenderer`]: struct.DebugRenderer.html
+    /// [`Frame`]: struct.Frame.html
+    /// [`Renderer`]: trait.Renderer.html
+    pub fn debug_renderer(&self) -> Result<MappedMutexGuard<'_, DebugRenderer>, String> {
+        MutexGuard::try_map(self.selected_frame.lock(), |f| {
+            f.renderer.downcast_mut::<DebugRenderer>()
         })
+        .map_err(|_| "Error: The selected frame isn't drawn by a DebugRenderer".to_string())
+    }
+
+    /// Remove `frame` from the `Display`.
+    ///
+    /// If it was selected, the first remaining [`Frame`] is selected
+    /// instead.  The last [`Frame`] can't be removed.
+    ///
+    /// [`Frame`]: struct.Frame.html
+    pub fn remove_frame(&mut self, frame: &Arc<Mutex<Frame>>) -> Result<(), String> {
+        if self.frames.len() == 1 && Arc::ptr_eq(&self.frames[0], frame) {
+            return Err("Error: Can't remove the last frame".to_string());
+        }
+        self.frames.retain(|f| !Arc::ptr_eq(f, frame));
+        if Arc::ptr_eq(&self.selected_frame, frame) {
+            self.selected_frame = self.frames[0].clone();
+        }
+        Ok(())
     }
 
     pub fn update_cursors(&self) {
@@ -53,9 +79,8 @@ impl Display {
 
     pub fn getch(&self) -> Option<Input> {
         for frame in &self.frames {
-            match frame.lock().renderer.getch() {
-                Some(input) => return Some(input),
-                None => (),
+            if let Some(input) = frame.lock().getch() {
+                return Some(input);
             }
         }
         None
@@ -1,58 +1,511 @@
-use std::sync::mpsc::*;
 use display::Display;
-use state::State;
+use frame::Frame;
 use input::Input;
+use libc;
+use parking_lot::Mutex;
+use state::State;
+use std::collections::VecDeque;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-enum DisplayRequest {
-    Show(*const State),
-    Getch,
+/// An [`Input`] along with the [`Frame`] it was read from and what
+/// the [`Frame`] knew about it when it was read.
+///
+/// The [`Frame`] may read more input before the [`Command`] bound to
+/// this one runs, so the mouse position and pasted text are sent
+/// along with it.
+///
+/// [`Input`]: struct.Input.html
+/// [`Frame`]: struct.Frame.html
+/// [`Command`]: trait.Command.html
+pub struct InputEvent {
+    pub frame: Arc<Mutex<Frame>>,
+    pub input: Input,
+    pub mouse_position: Option<(usize, usize)>,
+    pub pasted_text: Option<String>,
 }
-enum DisplayResult {
-    Show(Result<(), ()>),
-    Getch(Option<Input>),
+
+/// Read the next [`InputEvent`] from any [`Frame`] of `display`.
+///
+/// [`InputEvent`]: struct.InputEvent.html
+/// [`Frame`]: struct.Frame.html
+pub fn read_input_event(display: &Display) -> Option<InputEvent> {
+    read_frames_input_event(&display.frames)
 }
 
-pub struct DisplayReceiver<D> {
-    display: D,
-    receiveRequest: Receiver<DisplayRequest>,
-    sendResult: Sender<DisplayResult>,
+fn read_frames_input_event(frames: &[Arc<Mutex<Frame>>]) -> Option<InputEvent> {
+    for frame_arc in frames {
+        let mut frame = frame_arc.lock();
+        if let Some(input) = frame.getch() {
+            return Some(InputEvent {
+                frame: frame_arc.clone(),
+                input,
+                mouse_position: frame.mouse_position,
+                pasted_text: frame.pasted_text.take(),
+            });
+        }
+    }
+    None
 }
 
-pub struct DisplaySender {
-    sendRequest: Sender<DisplayRequest>,
-    receiveResult: Receiver<DisplayResult>,
+/// Handle one [`InputEvent`], running the [`Command`] it completes.
+///
+/// The [`Frame`] the [`InputEvent`] came from is selected first.  If
+/// it was removed since then, the [`InputEvent`] is dropped.
+///
+/// `inputs` holds the keys of the key sequence typed so far.  It is
+/// cleared once a [`Command`] is found or the sequence isn't bound.
+///
+/// [`InputEvent`]: struct.InputEvent.html
+/// [`Frame`]: struct.Frame.html
+/// [`Command`]: trait.Command.html
+pub fn handle_input_event(
+    state: &Arc<Mutex<State>>,
+    inputs: &mut VecDeque<Input>,
+    event: InputEvent,
+) -> Result<(), String> {
+    let command = {
+        let mut state = state.lock();
+        if !state
+            .display
+            .frames
+            .iter()
+            .any(|frame| Arc::ptr_eq(frame, &event.frame))
+        {
+            return Ok(());
+        }
+        state.display.selected_frame = event.frame;
+        {
+            let mut selected_frame = state.display.selected_frame.lock();
+            selected_frame.mouse_position = event.mouse_position;
+            selected_frame.pasted_text = event.pasted_text;
+        }
+        inputs.push_back(event.input);
+        state.lookup(inputs)
+    };
+    match command {
+        Ok(command) => {
+            inputs.clear();
+            command.execute(state.clone())
+        }
+        Err(Ok(())) => Ok(()),
+        Err(Err(())) => {
+            inputs.clear();
+            Ok(())
+        }
+    }
 }
 
-pub fn new_display_coordinator<D>(display: D) -> (DisplaySender, DisplayReceiver<D>) {
-    let (sendRequest, receiveRequest) = channel();
-    let (sendResult, receiveResult) = channel();
-    (DisplaySender {
-    },
-     DisplayReceiver {
-         display
-     })
+/// Runs the [`Display`] on the UI thread while [`Command`]s run on a
+/// worker thread.
+///
+/// The UI thread is the only thread that reads input and shows the
+/// [`Display`].  It keeps redrawing while a long running [`Command`]
+/// executes so that the editor stays responsive.  [`Command`]s are
+/// run in the order their keys were typed.
+///
+/// The UI thread only locks the [`State`] briefly to see which
+/// [`Frame`]s there are, and skips that while a [`Command`] holds
+/// it, so a [`Command`] can keep the [`State`] locked while it runs.
+/// When the loop stops, the keys that were typed but not handled yet
+/// are dropped.
+///
+/// While no [`Command`] is running, the UI thread sleeps until a
+/// [`Renderer::input_fd`] is readable or it is time to redraw.  While
+/// one is running, it sleeps until it finishes, waking up every
+/// `poll_interval` to read input typed in the meantime.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate parking_lot;
+/// # extern crate ted_core;
+/// # use parking_lot::Mutex;
+/// # use std::sync::Arc;
+/// # use ted_core::*;
+/// # fn main() {
+/// let renderer = DebugRenderer::from(vec![kbd("a"), kbd("b"), kbd("C-x"), kbd("c")]);
+/// let state = Arc::new(Mutex::new(State::new(renderer)));
+/// let quit: fn(Arc<Mutex<State>>) -> Result<(), String> = |_| Err("quit".to_string());
+/// state
+///     .lock()
+///     .default_key_map
+///     .lock()
+///     .bind(vec![kbd("C-x")], function_command(quit));
+///
+/// DisplayCoordinator::new(state.clone())
+///     .run(|_, message| message != "quit")
+///     .unwrap();
+/// let buffer = state.lock().display.selected_window_buffer();
+/// assert_eq!(buffer.lock().to_string(), "ab");
+/// # }
+/// ```
+///
+/// [`Display`]: struct.Display.html
+/// [`Command`]: trait.Command.html
+/// [`State`]: struct.State.html
+/// [`Frame`]: struct.Frame.html
+/// [`Renderer::input_fd`]: trait.Renderer.html#method.input_fd
+pub struct DisplayCoordinator {
+    state: Arc<Mutex<State>>,
+    /// How long to wait before polling for input again when there
+    /// was none and it can't be waited for.
+    pub poll_interval: Duration,
+    /// How often to redraw while waiting for a [`Command`] to finish.
+    ///
+    /// [`Command`]: trait.Command.html
+    pub redraw_interval: Duration,
 }
 
-impl<D: Display> Display for DisplayReceiver<D> {
-    fn show(&mut self, state: &State) -> Result<(), ()> {
-        unimplemented!()
+impl DisplayCoordinator {
+    pub fn new(state: Arc<Mutex<State>>) -> Self {
+        DisplayCoordinator {
+            state,
+            poll_interval: Duration::from_millis(5),
+            redraw_interval: Duration::from_millis(100),
+        }
+    }
+
+    /// Run the UI loop on the current thread.
+    ///
+    /// When a [`Command`] fails, `handle_error` is called on the UI
+    /// thread with the selected [`Frame`] and its message.  No other
+    /// [`Command`] runs until it returns.  If it returns `false` then
+    /// the loop stops.
+    ///
+    /// [`Command`]: trait.Command.html
+    /// [`Frame`]: struct.Frame.html
+    pub fn run<F>(&self, mut handle_error: F) -> Result<(), String>
+    where
+        F: FnMut(&mut Frame, String) -> bool,
+    {
+        let (send_event, receive_event) = channel();
+        let (send_result, receive_result) = channel();
+        let (send_continue, receive_continue) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let worker = Worker {
+                state: self.state.clone(),
+                receive_event,
+                send_result,
+                receive_continue,
+                stop: stop.clone(),
+            };
+            thread::Builder::new()
+                .name("ted worker".to_string())
+                .spawn(move || worker.run())
+                .map_err(|e| e.to_string())?
+        };
+        let result = self.run_ui(
+            &send_event,
+            &receive_result,
+            &send_continue,
+            &mut handle_error,
+        );
+        // Skip the events that are still queued.  Disconnecting the
+        // channels wakes the worker up if it is waiting.
+        stop.store(true, Ordering::SeqCst);
+        drop(send_event);
+        drop(send_continue);
+        let _ = worker.join();
+        result
     }
 
-    fn getch(&mut self) -> Option<Input> {
-        unimplemented!()
+    fn run_ui<F>(
+        &self,
+        send_event: &Sender<InputEvent>,
+        receive_result: &Receiver<Result<(), String>>,
+        send_continue: &Sender<()>,
+        handle_error: &mut F,
+    ) -> Result<(), String>
+    where
+        F: FnMut(&mut Frame, String) -> bool,
+    {
+        let mut frames = Frames::new(&self.state.lock().display);
+        frames.show()?;
+        let mut last_show = Instant::now();
+        // The number of events sent to the worker that it hasn't
+        // finished handling.
+        let mut pending = 0;
+        let mut received = None;
+        loop {
+            let mut needs_show = false;
+            loop {
+                let result = match received.take() {
+                    Some(result) => result,
+                    None => match receive_result.try_recv() {
+                        Ok(result) => result,
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            return Err("Error: The worker thread stopped".to_string())
+                        }
+                    },
+                };
+                pending -= 1;
+                frames.update(&self.state);
+                if let Err(message) = result {
+                    if !handle_error(&mut frames.selected_frame.lock(), message) {
+                        return Ok(());
+                    }
+                    send_continue
+                        .send(())
+                        .map_err(|_| "Error: The worker thread stopped".to_string())?;
+                }
+                needs_show = true;
+            }
+            frames.update(&self.state);
+            let event = read_frames_input_event(&frames.frames);
+            let had_event = event.is_some();
+            if let Some(event) = event {
+                send_event
+                    .send(event)
+                    .map_err(|_| "Error: The worker thread stopped".to_string())?;
+                pending += 1;
+                needs_show = true;
+            }
+            if needs_show || last_show.elapsed() >= self.redraw_interval {
+                frames.show()?;
+                last_show = Instant::now();
+            }
+            if !had_event {
+                let until_redraw =
+                    self.redraw_interval - last_show.elapsed().min(self.redraw_interval);
+                if pending > 0 {
+                    match receive_result.recv_timeout(until_redraw.min(self.poll_interval)) {
+                        Ok(result) => received = Some(result),
+                        Err(RecvTimeoutError::Timeout) => (),
+                        Err(RecvTimeoutError::Disconnected) => {
+                            return Err("Error: The worker thread stopped".to_string())
+                        }
+                    }
+                } else {
+                    match frames.input_fds() {
+                        Some(fds) => wait_for_input(&fds, until_redraw),
+                        None => thread::sleep(until_redraw.min(self.poll_interval)),
+                    }
+                }
+            }
+        }
     }
 }
 
-impl Display for DisplaySender {
-    fn show(&mut self, state: &State) -> Result<(), ()> {
-        unimplemented!()
+/// The [`Frame`]s of the [`Display`] as the UI thread last saw them.
+///
+/// [`Frame`]: struct.Frame.html
+/// [`Display`]: struct.Display.html
+struct Frames {
+    selected_frame: Arc<Mutex<Frame>>,
+    frames: Vec<Arc<Mutex<Frame>>>,
+}
+
+impl Frames {
+    fn new(display: &Display) -> Self {
+        Frames {
+            selected_frame: display.selected_frame.clone(),
+            frames: display.frames.clone(),
+        }
+    }
+
+    /// Look at the [`Display`] again unless a [`Command`] holds the
+    /// [`State`] lock.
+    ///
+    /// [`Display`]: struct.Display.html
+    /// [`Command`]: trait.Command.html
+    /// [`State`]: struct.State.html
+    fn update(&mut self, state: &Mutex<State>) {
+        if let Some(state) = state.try_lock() {
+            *self = Frames::new(&state.display);
+        }
+    }
+
+    fn show(&self) -> Result<(), String> {
+        for frame in &self.frames {
+            frame
+                .lock()
+                .show(Arc::ptr_eq(&self.selected_frame, frame))?
+        }
+        Ok(())
+    }
+
+    /// Get the [`Renderer::input_fd`] of every [`Frame`], or `None` if
+    /// one of them doesn't have one.
+    ///
+    /// [`Renderer::input_fd`]: trait.Renderer.html#method.input_fd
+    /// [`Frame`]: struct.Frame.html
+    fn input_fds(&self) -> Option<Vec<RawFd>> {
+        self.frames
+            .iter()
+            .map(|frame| frame.lock().renderer.input_fd())
+            .collect()
     }
+}
 
-    fn getch(&mut self) -> Option<Input> {
-        unimplemented!()
+/// Sleep until one of `fds` is readable or `timeout` has passed.
+fn wait_for_input(fds: &[RawFd], timeout: Duration) {
+    let mut fds: Vec<libc::pollfd> = fds
+        .iter()
+        .map(|&fd| libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        })
+        .collect();
+    // Round up so a short timeout doesn't become a busy loop.
+    let millis = timeout.as_micros().div_ceil(1000);
+    let millis = std::cmp::min(millis, libc::c_int::MAX as u128) as libc::c_int;
+    // Errors such as being interrupted by a signal just wake us up
+    // early.
+    unsafe {
+        libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, millis);
     }
 }
 
-fn f() {
-    let _: Box<Send> = Box::new(DisplayCoordinator::new());
+/// Runs the [`Command`]s on the worker thread.
+///
+/// [`Command`]: trait.Command.html
+struct Worker {
+    state: Arc<Mutex<State>>,
+    receive_event: Receiver<InputEvent>,
+    send_result: Sender<Result<(), String>>,
+    /// Receives a message once the UI thread has handled an error.
+    receive_continue: Receiver<()>,
+    /// Set when the UI thread stops.
+    stop: Arc<AtomicBool>,
+}
+
+impl Worker {
+    fn run(self) {
+        let mut inputs = VecDeque::new();
+        for event in &self.receive_event {
+            if self.stop.load(Ordering::SeqCst) {
+                return;
+            }
+            let result = handle_input_event(&self.state, &mut inputs, event);
+            let is_err = result.is_err();
+            if self.send_result.send(result).is_err() {
+                return;
+            }
+            // The error may stop the UI thread, in which case the
+            // events after it must not run.
+            if is_err && self.receive_continue.recv().is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use command::function_command;
+    use debug_renderer::DebugRenderer;
+    use input::kbd;
+
+    fn quit_on(state: &Arc<Mutex<State>>, input: &str) {
+        let quit: fn(Arc<Mutex<State>>) -> Result<(), String> = |_| Err("quit".to_string());
+        state
+            .lock()
+            .default_key_map
+            .lock()
+            .bind(vec![kbd(input)], function_command(quit));
+    }
+
+    #[test]
+    fn coordinator_runs_commands_in_order() {
+        let renderer = DebugRenderer::from(vec![kbd("a"), kbd("\n"), kbd("b"), kbd("C-q")]);
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        quit_on(&state, "C-q");
+        let mut errors = Vec::new();
+        DisplayCoordinator::new(state.clone())
+            .run(|_, message| {
+                errors.push(message);
+                false
+            })
+            .unwrap();
+        assert_eq!(errors, vec!["quit".to_string()]);
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().to_string(), "a\nb");
+    }
+
+    #[test]
+    fn coordinator_key_sequences() {
+        let renderer = DebugRenderer::from(vec![
+            kbd("a"),
+            kbd("a"),
+            kbd("b"),
+            kbd("a"),
+            kbd("b"),
+            kbd("C-q"),
+        ]);
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        quit_on(&state, "C-q");
+        let ab: fn(Arc<Mutex<State>>) -> Result<(), String> = |state| {
+            let window = state.lock().display.selected_window();
+            let mut window = window.lock();
+            window.insert_str("AB")
+        };
+        state
+            .lock()
+            .default_key_map
+            .lock()
+            .bind(vec![kbd("a"), kbd("b")], function_command(ab));
+        DisplayCoordinator::new(state.clone())
+            .run(|_, message| message != "quit")
+            .unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        // "a a" isn't bound so it is dropped.
+        assert_eq!(buffer.lock().to_string(), "bAB");
+    }
+
+    #[test]
+    fn coordinator_redraws_during_long_commands() {
+        let renderer = DebugRenderer::from(vec![kbd("C-w"), kbd("C-q")]);
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        quit_on(&state, "C-q");
+        let wait: fn(Arc<Mutex<State>>) -> Result<(), String> = |state| {
+            // Hold the state lock for the whole command.
+            let state = state.lock();
+            state.display.selected_window().lock().insert('x')?;
+            // Keep running until the UI thread draws the insertion.
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(10) {
+                if state.display.debug_renderer().unwrap().buffer[0][0] == 'x' {
+                    return Ok(());
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            Err("Error: Not redrawn".to_string())
+        };
+        state
+            .lock()
+            .default_key_map
+            .lock()
+            .bind(vec![kbd("C-w")], function_command(wait));
+        let mut coordinator = DisplayCoordinator::new(state.clone());
+        coordinator.redraw_interval = Duration::from_millis(1);
+        let mut errors = Vec::new();
+        coordinator
+            .run(|_, message| {
+                errors.push(message);
+                false
+            })
+            .unwrap();
+        assert_eq!(errors, vec!["quit".to_string()]);
+    }
+
+    #[test]
+    fn coordinator_quit_skips_queued_events() {
+        let renderer = DebugRenderer::from(vec![kbd("a"), kbd("C-q"), kbd("b"), kbd("c")]);
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        quit_on(&state, "C-q");
+        DisplayCoordinator::new(state.clone())
+            .run(|_, message| message != "quit")
+            .unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().to_string(), "a");
+    }
 }
@@ -0,0 +1,56 @@
+/// How a [`Window`] numbers its lines.
+///
+/// [`Window`]: struct.Window.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineNumbers {
+    /// Don't draw line numbers.
+    None,
+    /// Draw the line number of each line.
+    Absolute,
+    /// Draw the distance of each line from the cursor's line.  The
+    /// cursor's line has its absolute line number.
+    Relative,
+}
+
+/// How a [`Window`] draws lines that are wider than it.
+///
+/// [`Window`]: struct.Window.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WrapMode {
+    /// Continue the line on the next row.
+    Wrap,
+    /// Cut off the end of the line.
+    Truncate,
+}
+
+/// Options controlling how a [`Window`] draws its [`Buffer`].
+///
+/// Each [`Window`] has its own options so that two [`Window`]s
+/// showing the same [`Buffer`] can display it differently.
+///
+/// [`Window`]: struct.Window.html
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisplayOptions {
+    pub line_numbers: LineNumbers,
+    pub wrap_mode: WrapMode,
+    /// Draw spaces as `·` and tabs as `→`.
+    pub show_whitespace: bool,
+    /// The number of columns between tab stops.
+    pub tab_width: usize,
+    /// Move up and down by rows on the screen instead of by lines
+    /// when lines are wrapped.
+    pub visual_line_movement: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        DisplayOptions {
+            line_numbers: LineNumbers::None,
+            wrap_mode: WrapMode::Wrap,
+            show_whitespace: false,
+            tab_width: 8,
+            visual_line_movement: false,
+        }
+    }
+}
@@ -1,87 +1,572 @@
+use buffer::Buffer;
+use char_width::char_width;
+use display_options::{DisplayOptions, LineNumbers, WrapMode};
 use layout::Layout;
+use mode_line::ModeLine;
 use parking_lot::Mutex;
 use renderer::Renderer;
+use screen::{Rect, Screen, WindowKey};
+use std::ops::Range;
 use std::sync::Arc;
 use window::Window;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Character {
     Character(char),
     VLine,
     HLine,
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Attribute {
     SelectedCursor,
     UnselectedCursor,
     Inverted,
+    /// The mode line of the selected [`Window`].
+    ///
+    /// [`Window`]: ../struct.Window.html
+    SelectedModeLine,
+    /// The mode line of a [`Window`] that isn't selected.
+    ///
+    /// [`Window`]: ../struct.Window.html
+    UnselectedModeLine,
 }
 
+/// A [`Renderer`] that is drawn to one cell at a time.
+///
+/// Frames are drawn into the [`Screen`] first, which then only
+/// sends the cells that changed to the `DrawableRenderer`.
+///
+/// [`Renderer`]: ../trait.Renderer.html
+/// [`Screen`]: ../struct.Screen.html
 pub trait DrawableRenderer: Renderer {
+    /// Get the [`Screen`] that frames are drawn into.
+    ///
+    /// [`Screen`]: ../struct.Screen.html
+    fn screen(&mut self) -> &mut Screen;
     fn erase(&mut self) -> Result<(), String>;
+    /// Draw `ch` at `(y, x)`, removing the attributes of the cell.
     fn putch(&mut self, y: usize, x: usize, ch: Character) -> Result<(), String>;
     fn set_attribute(&mut self, y: usize, x: usize, at: Attribute) -> Result<(), String>;
+    /// Forget the cursors drawn in the last frame.  They are all
+    /// drawn again every frame.
+    fn clear_cursors(&mut self) -> Result<(), String> {
+        Ok(())
+    }
 }
 
+/// Draw `layout` to `display`.
+///
+/// Only the cells that changed since the last frame are drawn.
 pub fn draw<D>(
     display: &mut D,
     layout: &Layout,
     selected_window: Option<&Arc<Mutex<Window>>>,
+    mode_line: &ModeLine,
     rows: usize,
     columns: usize,
 ) -> Result<(), String>
 where
     D: DrawableRenderer,
 {
+    draw_with(display, rows, columns, |screen| {
+        draw_layout(screen, layout, selected_window, mode_line)
+    })
+}
+
+/// Draw a frame to `display` by calling `f` to draw into the
+/// [`Screen`].
+///
+/// Only the cells that changed since the last frame are drawn.
+///
+/// [`Screen`]: ../struct.Screen.html
+pub fn draw_with<D, F>(display: &mut D, rows: usize, columns: usize, f: F) -> Result<(), String>
+where
+    D: DrawableRenderer,
+    F: FnOnce(&mut Screen) -> Result<(), String>,
+{
+    // Take the screen so it can be flushed to the display.
+    let mut screen = std::mem::replace(display.screen(), Screen::new());
+    screen.resize(rows, columns);
+    screen.erase();
+    let result = f(&mut screen).and_then(|()| screen.flush(display));
+    if result.is_err() {
+        screen.invalidate();
+    }
+    *display.screen() = screen;
+    result
+}
+
+/// Draw `layout` so that it fills the `screen`.
+pub fn draw_layout(
+    screen: &mut Screen,
+    layout: &Layout,
+    selected_window: Option<&Arc<Mutex<Window>>>,
+    mode_line: &ModeLine,
+) -> Result<(), String> {
+    layout.update_window_cursors();
+    let (rows, columns) = screen.size();
+    draw_rect(
+        screen,
+        layout,
+        selected_window,
+        mode_line,
+        Rect {
+            y: 0,
+            x: 0,
+            rows,
+            columns,
+        },
+    )
+}
+
+/// Draw `layout` above an echo area line showing `message`.
+///
+/// The echo area is always the last row of the `screen` so that the
+/// [`Window`]s don't move when a message is shown.  Only the first
+/// line of `message` is drawn.
+///
+/// [`Window`]: ../struct.Window.html
+pub fn draw_frame(
+    screen: &mut Screen,
+    layout: &Layout,
+    selected_window: Option<&Arc<Mutex<Window>>>,
+    mode_line: &ModeLine,
+    message: Option<&str>,
+) -> Result<(), String> {
     layout.update_window_cursors();
-    display.erase()?;
-    draw_rect(display, layout, selected_window, 0, 0, rows, columns)?;
+    let (rows, columns) = screen.size();
+    if rows == 0 {
+        return Ok(());
+    }
+    draw_rect(
+        screen,
+        layout,
+        selected_window,
+        mode_line,
+        Rect {
+            y: 0,
+            x: 0,
+            rows: rows - 1,
+            columns,
+        },
+    )?;
+    if let Some(message) = message {
+        let options = DisplayOptions {
+            wrap_mode: WrapMode::Truncate,
+            ..DisplayOptions::default()
+        };
+        let line = message.lines().next().unwrap_or("");
+        draw_window(
+            screen,
+            line.chars(),
+            &WindowView {
+                is_selected_window: false,
+                cursor_location: None,
+                secondary_cursors: &[],
+                faces: &[],
+                name: None,
+                gutter: None,
+                options: &options,
+                hscroll: 0,
+                y: rows - 1,
+                x: 0,
+                rows: 1,
+                columns,
+            },
+        )?;
+    }
     Ok(())
 }
 
-pub fn draw_window<D, I>(
-    display: &mut D,
-    mut iter: I,
-    is_selected_window: bool,
-    cursor_location: Option<usize>,
-    name: Option<&str>,
+/// The line numbers drawn to the left of a [`Window`].
+///
+/// [`Window`]: ../struct.Window.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gutter {
+    /// The line number of the first line drawn, starting at 0.
+    pub first_line: usize,
+    /// The line number of the cursor, starting at 0.
+    pub cursor_line: usize,
+    /// The width of the widest line number.
+    pub width: usize,
+    pub relative: bool,
+}
+
+impl Gutter {
+    /// Get the [`Gutter`] of `window`, if it draws line numbers.
+    ///
+    /// The width is based on the number of lines in the [`Buffer`].
+    /// Line numbers are looked up in the [`Buffer`]'s cached line
+    /// starts, so this doesn't scan the text.
+    ///
+    /// [`Gutter`]: struct.Gutter.html
+    /// [`Buffer`]: ../struct.Buffer.html
+    pub fn new(window: &Window) -> Option<Self> {
+        let relative = match window.display_options.line_numbers {
+            LineNumbers::None => return None,
+            LineNumbers::Absolute => false,
+            LineNumbers::Relative => true,
+        };
+        let buffer = window.buffer.lock();
+        Some(Gutter {
+            first_line: buffer.line_at(window.start),
+            cursor_line: buffer.line_at(window.cursor.get()),
+            width: buffer.line_count().to_string().len(),
+            relative,
+        })
+    }
+
+    /// The number of columns taken up by the [`Gutter`], including
+    /// the space separating it from the text.
+    ///
+    /// [`Gutter`]: struct.Gutter.html
+    pub fn columns(&self) -> usize {
+        self.width + 1
+    }
+
+    /// The number to draw next to the `line`th line drawn.
+    pub fn number(&self, line: usize) -> usize {
+        let line = self.first_line + line;
+        if self.relative && line != self.cursor_line {
+            line.abs_diff(self.cursor_line)
+        } else {
+            line + 1
+        }
+    }
+}
+
+/// Get the number of columns out of `columns` that are used by
+/// `gutter`.
+///
+/// If the [`Gutter`] doesn't fit it isn't drawn.
+///
+/// [`Gutter`]: struct.Gutter.html
+pub fn gutter_columns(gutter: Option<&Gutter>, columns: usize) -> usize {
+    match gutter {
+        Some(gutter) if gutter.columns() < columns => gutter.columns(),
+        _ => 0,
+    }
+}
+
+/// Where [`draw_window`] puts a character.
+///
+/// In [`WrapMode::Truncate`] the `column` is counted from the start
+/// of the line and the characters before `hscroll` aren't visible.
+///
+/// [`draw_window`]: fn.draw_window.html
+/// [`WrapMode::Truncate`]: ../enum.WrapMode.html#variant.Truncate
+struct Placer {
+    columns: usize,
+    wrap_mode: WrapMode,
+    tab_width: usize,
+    hscroll: usize,
+    row: usize,
+    column: usize,
+    /// Whether characters on this row are cut off on the left.
+    hidden_left: bool,
+    /// Whether characters on this row are cut off on the right.
+    hidden_right: bool,
+}
+
+impl Placer {
+    fn new(columns: usize, options: &DisplayOptions, hscroll: usize) -> Self {
+        Placer {
+            columns,
+            wrap_mode: options.wrap_mode,
+            tab_width: std::cmp::max(options.tab_width, 1),
+            hscroll: match options.wrap_mode {
+                WrapMode::Wrap => 0,
+                WrapMode::Truncate => hscroll,
+            },
+            row: 0,
+            column: 0,
+            hidden_left: false,
+            hidden_right: false,
+        }
+    }
+
+    /// Get the width of `ch`, moving to the next row if it doesn't
+    /// fit on this one.
+    ///
+    /// Afterwards `ch` is drawn at `(self.row, self.column)`.
+    fn fit(&mut self, ch: char) -> usize {
+        let width = match ch {
+            '\n' => 0,
+            '\t' => self.tab_width - self.column % self.tab_width,
+            _ => char_width(ch),
+        };
+        if self.wrap_mode == WrapMode::Wrap && self.column + width > self.columns {
+            if ch == '\t' {
+                // Tabs stop at the edge of the window.
+                return self.columns - self.column;
+            }
+            if self.column != 0 {
+                self.row += 1;
+                self.column = 0;
+            }
+        }
+        width
+    }
+
+    /// Advance past `ch`, which is `width` columns wide.
+    fn advance(&mut self, ch: char, width: usize) {
+        if ch == '\n' {
+            self.row += 1;
+            self.column = 0;
+            self.hidden_left = false;
+            self.hidden_right = false;
+        } else {
+            if width != 0 && self.wrap_mode == WrapMode::Truncate {
+                if self.column < self.hscroll {
+                    self.hidden_left = true;
+                }
+                if !self.is_visible(width) && self.column + width > self.hscroll {
+                    self.hidden_right = true;
+                }
+            }
+            self.column += width;
+            if self.wrap_mode == WrapMode::Wrap && self.column >= self.columns {
+                self.row += 1;
+                self.column = 0;
+            }
+        }
+    }
+
+    /// Whether `width` columns starting at `self.column` are all on
+    /// the screen.
+    fn is_visible(&self, width: usize) -> bool {
+        self.column >= self.hscroll
+            && self.column + width <= self.hscroll.saturating_add(self.columns)
+    }
+
+    /// The column `self.column` is drawn at.
+    fn screen_column(&self) -> usize {
+        self.column - self.hscroll
+    }
+}
+
+/// Draw `$` on the edges of the current row if it is cut off.
+fn draw_continuation_markers(
+    screen: &mut Screen,
+    placer: &Placer,
     y: usize,
     x: usize,
-    mut rows: usize,
-    columns: usize,
-) -> Result<(), String>
+) -> Result<(), String> {
+    if placer.hidden_left {
+        screen.putch(y + placer.row, x, Character::Character('$'))?;
+    }
+    if placer.hidden_right {
+        screen.putch(
+            y + placer.row,
+            x + placer.columns - 1,
+            Character::Character('$'),
+        )?;
+    }
+    Ok(())
+}
+
+/// Get the column `location` is drawn at in [`WrapMode::Truncate`]
+/// before scrolling horizontally.
+///
+/// [`WrapMode::Truncate`]: ../enum.WrapMode.html#variant.Truncate
+pub fn line_column(buffer: &Buffer, options: &DisplayOptions, location: usize) -> usize {
+    let options = DisplayOptions {
+        wrap_mode: WrapMode::Truncate,
+        ..*options
+    };
+    let mut placer = Placer::new(usize::MAX, &options, 0);
+    let begin = buffer.line_start(location);
+    for ch in buffer.display_iter_at(begin).take(location - begin) {
+        let width = placer.fit(ch);
+        placer.advance(ch, width);
+    }
+    placer.column
+}
+
+/// Scroll `window` horizontally so the cursor is visible when it is
+/// drawn with `columns` columns of text.
+///
+/// This does nothing unless `window` truncates lines.  When the
+/// cursor is off the screen, it is put in the middle of the screen.
+pub fn scroll_to_cursor(window: &mut Window, columns: usize) {
+    if window.display_options.wrap_mode != WrapMode::Truncate || columns == 0 {
+        return;
+    }
+    let (column, width, at_end_of_line) = {
+        let buffer = window.buffer.lock();
+        let cursor = window.cursor.get();
+        let column = line_column(&buffer, &window.display_options, cursor);
+        let ch = buffer.display_iter_at(cursor).next();
+        match ch {
+            Some('\n') | None => (column, 1, true),
+            Some(ch) => {
+                let mut placer = Placer::new(columns, &window.display_options, 0);
+                placer.column = column;
+                (column, std::cmp::max(placer.fit(ch), 1), false)
+            }
+        }
+    };
+    // Don't put the cursor on a continuation marker.
+    let first = window.hscroll + if window.hscroll == 0 { 0 } else { 1 };
+    let end = window.hscroll + columns - if at_end_of_line { 0 } else { 1 };
+    if column < first || column + width > end {
+        window.hscroll = column.saturating_sub(columns / 2);
+    }
+}
+
+fn display_char(ch: char, options: &DisplayOptions) -> char {
+    match ch {
+        ' ' if options.show_whitespace => '·',
+        _ => ch,
+    }
+}
+
+fn draw_line_number(
+    screen: &mut Screen,
+    gutter: &Gutter,
+    line: usize,
+    y: usize,
+    x: usize,
+) -> Result<(), String> {
+    let number = format!("{:>width$}", gutter.number(line), width = gutter.width);
+    for (column, ch) in number.chars().enumerate() {
+        screen.putch(y, x + column, Character::Character(ch))?;
+    }
+    Ok(())
+}
+
+/// How [`draw_window`] draws its characters and where.
+///
+/// Locations are offsets into the characters drawn.
+///
+/// [`draw_window`]: fn.draw_window.html
+#[derive(Clone, Copy, Debug)]
+pub struct WindowView<'a> {
+    pub is_selected_window: bool,
+    pub cursor_location: Option<usize>,
+    /// Drawn like the cursor of a [`Window`] that isn't selected.
+    ///
+    /// [`Window`]: ../struct.Window.html
+    pub secondary_cursors: &'a [usize],
+    /// Each character is drawn with the last face whose range
+    /// contains it.
+    pub faces: &'a [(Range<usize>, Attribute)],
+    /// The mode line, drawn on the last row.
+    pub name: Option<&'a str>,
+    pub gutter: Option<&'a Gutter>,
+    pub options: &'a DisplayOptions,
+    pub hscroll: usize,
+    pub y: usize,
+    pub x: usize,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Draw the characters of `iter` as described by `view`.
+pub fn draw_window<I>(screen: &mut Screen, mut iter: I, view: &WindowView) -> Result<(), String>
 where
-    D: DrawableRenderer,
     I: Iterator<Item = char>,
 {
-    let mut column = 0;
-    let mut row = 0;
+    let WindowView {
+        is_selected_window,
+        cursor_location,
+        secondary_cursors,
+        faces,
+        name,
+        gutter,
+        options,
+        hscroll,
+        y,
+        x,
+        mut rows,
+        columns,
+    } = *view;
     let mut location = 0;
+    if rows == 0 || columns == 0 {
+        return Ok(());
+    }
     if name.is_some() {
         rows -= 1;
     }
-    while row < rows {
-        if cursor_location.map(|c| c == location).unwrap_or(false) {
+    let gutter_columns = gutter_columns(gutter, columns);
+    let gutter = gutter.filter(|_| gutter_columns != 0);
+    let text_x = x + gutter_columns;
+    let mut placer = Placer::new(columns - gutter_columns, options, hscroll);
+    let mut line = 0;
+    let face_at = |location: usize| {
+        faces
+            .iter()
+            .rev()
+            .find(|(range, _)| range.contains(&location))
+            .map(|&(_, face)| face)
+    };
+    if let Some(gutter) = gutter.filter(|_| rows > 0) {
+        draw_line_number(screen, gutter, line, y, x)?;
+    }
+    while placer.row < rows {
+        let ch = iter.next();
+        let width = ch.map_or(0, |ch| placer.fit(ch));
+        if placer.row >= rows {
+            break;
+        }
+        if cursor_location.map(|c| c == location).unwrap_or(false) && placer.is_visible(1) {
+            let (row, column) = (y + placer.row, text_x + placer.screen_column());
             if is_selected_window {
-                display.set_attribute(y + row, x + column, Attribute::SelectedCursor)?;
+                screen.set_attribute(row, column, Attribute::SelectedCursor)?;
             } else {
-                display.set_attribute(y + row, x + column, Attribute::UnselectedCursor)?;
+                screen.set_attribute(row, column, Attribute::UnselectedCursor)?;
             }
+        } else if secondary_cursors.contains(&location) && placer.is_visible(1) {
+            let (row, column) = (y + placer.row, text_x + placer.screen_column());
+            screen.set_attribute(row, column, Attribute::UnselectedCursor)?;
         }
-        match iter.next() {
+        match ch {
             Some('\n') => {
-                column = 0;
-                row += 1;
+                draw_continuation_markers(screen, &placer, y, text_x)?;
+                placer.advance('\n', width);
+                line += 1;
+                if let Some(gutter) = gutter.filter(|_| placer.row < rows) {
+                    draw_line_number(screen, gutter, line, y + placer.row, x)?;
+                }
+            }
+            Some('\t') => {
+                // Draw each column of the tab separately since it
+                // may be partially scrolled off the screen.
+                let column = placer.column;
+                for offset in 0..width {
+                    placer.column = column + offset;
+                    if placer.is_visible(1) {
+                        let ch = if offset == 0 && options.show_whitespace {
+                            '→'
+                        } else {
+                            ' '
+                        };
+                        let (row, column) = (y + placer.row, text_x + placer.screen_column());
+                        screen.putch(row, column, Character::Character(ch))?;
+                        if let Some(face) = face_at(location) {
+                            screen.set_attribute(row, column, face)?;
+                        }
+                    }
+                }
+                placer.column = column;
+                placer.advance('\t', width);
             }
             Some(ch) => {
-                display.putch(y + row, x + column, Character::Character(ch))?;
-                column += 1;
-                if column >= columns {
-                    row += 1;
-                    column = 0;
+                // Zero width characters can't be drawn on their own.
+                if width != 0 && placer.is_visible(width) {
+                    let (row, column) = (y + placer.row, text_x + placer.screen_column());
+                    screen.putch(row, column, Character::Character(display_char(ch, options)))?;
+                    if let Some(face) = face_at(location) {
+                        screen.set_attribute(row, column, face)?;
+                    }
                 }
+                placer.advance(ch, width);
+            }
+            None => {
+                draw_continuation_markers(screen, &placer, y, text_x)?;
+                break;
             }
-            None => break,
         }
         location += 1;
     }
@@ -90,12 +575,17 @@ where
             let mut column = 0;
             for ch in name.chars() {
                 if column < columns {
-                    display.putch(y + rows, x + column, Character::Character(ch))?;
+                    screen.putch(y + rows, x + column, Character::Character(ch))?;
                     column += 1;
                 }
             }
+            let face = if is_selected_window {
+                Attribute::SelectedModeLine
+            } else {
+                Attribute::UnselectedModeLine
+            };
             for column in 0..columns {
-                display.set_attribute(y + rows, x + column, Attribute::Inverted)?;
+                screen.set_attribute(y + rows, x + column, face)?;
             }
         }
         None => (),
@@ -103,77 +593,757 @@ where
     Ok(())
 }
 
-fn draw_rect<D>(
-    display: &mut D,
+fn draw_rect(
+    screen: &mut Screen,
     layout: &Layout,
     selected_window: Option<&Arc<Mutex<Window>>>,
-    y: usize,
-    x: usize,
-    rows: usize,
-    columns: usize,
-) -> Result<(), String>
-where
-    D: DrawableRenderer,
-{
+    mode_line: &ModeLine,
+    rect: Rect,
+) -> Result<(), String> {
+    let Rect {
+        y,
+        x,
+        rows,
+        columns,
+    } = rect;
     match layout {
-        Layout::Window(window) => {
+        Layout::Window(window_arc) => {
             let is_selected_window = selected_window
-                .map(|selected_window| Arc::ptr_eq(window, selected_window))
+                .map(|selected_window| Arc::ptr_eq(window_arc, selected_window))
                 .unwrap_or(false);
-            let window = window.lock();
+            let mut window = window_arc.lock();
+            let gutter = Gutter::new(&window);
+            let gutter_columns = gutter_columns(gutter.as_ref(), columns);
+            scroll_to_cursor(&mut window, columns - gutter_columns);
             let buffer = window.buffer.lock();
-            let mut iter = buffer.iter();
+            let start = std::cmp::min(window.start, buffer.len());
+            let cursor = window.cursor.get();
+            let name = mode_line.text(&window, &buffer);
+            let key = |name| {
+                WindowKey::new(
+                    window_arc,
+                    &window,
+                    &buffer,
+                    gutter.as_ref(),
+                    is_selected_window,
+                    name,
+                    rect,
+                )
+            };
+            if screen.restore_window(key(name.clone())) {
+                return Ok(());
+            }
+            let faces: Vec<_> = buffer
+                .overlays
+                .iter()
+                .filter_map(|overlay| {
+                    let range = overlay.range();
+                    let range = range.start.saturating_sub(start)..range.end.saturating_sub(start);
+                    overlay.face.map(|face| (range, face))
+                })
+                .collect();
+            let secondary_cursors: Vec<_> = window
+                .cursors
+                .iter()
+                .map(|secondary| secondary.marker.get())
+                .filter(|&location| location >= start)
+                .map(|location| location - start)
+                .collect();
             draw_window(
-                display,
-                iter,
-                is_selected_window,
-                Some(window.cursor.get()),
-                Some(&buffer.name.name),
-                y,
-                x,
-                rows,
-                columns,
-            )
+                screen,
+                buffer.display_iter_at(start),
+                &WindowView {
+                    is_selected_window,
+                    cursor_location: if cursor >= start {
+                        Some(cursor - start)
+                    } else {
+                        None
+                    },
+                    secondary_cursors: &secondary_cursors,
+                    faces: &faces,
+                    name: Some(&name),
+                    gutter: gutter.as_ref(),
+                    options: &window.display_options,
+                    hscroll: window.hscroll,
+                    y,
+                    x,
+                    rows,
+                    columns,
+                },
+            )?;
+            screen.save_window(key(name));
+            Ok(())
         }
         Layout::VSplit { left, right } => {
-            // 4 columns
-            // __|_
-            // 5 columns
-            // __|__
-            draw_rect(display, left, selected_window, y, x, rows, columns / 2)?;
-            for r in 0..rows {
-                display.putch(y + r, x + columns / 2, Character::VLine)?;
+            let (left_columns, right_columns) = split_columns(columns);
+            draw_rect(
+                screen,
+                left,
+                selected_window,
+                mode_line,
+                Rect {
+                    y,
+                    x,
+                    rows,
+                    columns: left_columns,
+                },
+            )?;
+            if columns > left_columns {
+                for r in 0..rows {
+                    screen.putch(y + r, x + left_columns, Character::VLine)?;
+                }
             }
             draw_rect(
-                display,
+                screen,
                 right,
                 selected_window,
-                y,
-                x + columns / 2 + 1,
-                rows,
-                (columns - 1) / 2,
+                mode_line,
+                Rect {
+                    y,
+                    x: x + left_columns + 1,
+                    rows,
+                    columns: right_columns,
+                },
             )?;
             Ok(())
         }
         Layout::HSplit { top, bottom } => {
-            // 4 rows
-            // __|_
-            // 5 rows
-            // __|__
-            draw_rect(display, top, selected_window, y, x, rows / 2, columns)?;
-            for c in 0..columns {
-                display.putch(y + rows / 2, x + c, Character::HLine)?;
+            let (top_rows, bottom_rows) = split_rows(rows);
+            draw_rect(
+                screen,
+                top,
+                selected_window,
+                mode_line,
+                Rect {
+                    y,
+                    x,
+                    rows: top_rows,
+                    columns,
+                },
+            )?;
+            if rows > top_rows {
+                for c in 0..columns {
+                    screen.putch(y + top_rows, x + c, Character::HLine)?;
+                }
             }
             draw_rect(
-                display,
+                screen,
                 bottom,
                 selected_window,
-                y + rows / 2 + 1,
-                x,
-                (rows - 1) / 2,
-                columns,
+                mode_line,
+                Rect {
+                    y: y + top_rows + 1,
+                    x,
+                    rows: bottom_rows,
+                    columns,
+                },
             )?;
             Ok(())
         }
     }
 }
+
+/// Get the number of columns given to the `left` and `right` sides
+/// of a [`Layout::VSplit`] that is `columns` wide.
+///
+/// The column in between them is used to draw the separator.  If
+/// there is no room for the separator, both sides are empty.
+///
+/// ```text
+/// 4 columns
+/// __|_
+/// 5 columns
+/// __|__
+/// ```
+///
+/// [`Layout::VSplit`]: ../enum.Layout.html#variant.VSplit
+pub fn split_columns(columns: usize) -> (usize, usize) {
+    (columns / 2, columns.saturating_sub(1) / 2)
+}
+
+/// Get the number of rows given to the `top` and `bottom` sides of a
+/// [`Layout::HSplit`] that is `rows` tall.
+///
+/// The row in between them is used to draw the separator.
+///
+/// [`Layout::HSplit`]: ../enum.Layout.html#variant.HSplit
+pub fn split_rows(rows: usize) -> (usize, usize) {
+    (rows / 2, rows.saturating_sub(1) / 2)
+}
+
+/// The area of the screen a [`Window`] is drawn in.
+///
+/// The last row is used by the mode line.
+///
+/// [`Window`]: ../struct.Window.html
+#[derive(Clone)]
+pub struct WindowRect {
+    pub window: Arc<Mutex<Window>>,
+    pub y: usize,
+    pub x: usize,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Collect the area each [`Window`] in `layout` is drawn in.
+///
+/// This uses the same geometry as [`draw`].
+///
+/// [`Window`]: ../struct.Window.html
+/// [`draw`]: fn.draw.html
+pub fn window_rects(
+    layout: &Layout,
+    y: usize,
+    x: usize,
+    rows: usize,
+    columns: usize,
+    rects: &mut Vec<WindowRect>,
+) {
+    match layout {
+        Layout::Window(window) => rects.push(WindowRect {
+            window: window.clone(),
+            y,
+            x,
+            rows,
+            columns,
+        }),
+        Layout::VSplit { left, right } => {
+            let (left_columns, right_columns) = split_columns(columns);
+            window_rects(left, y, x, rows, left_columns, rects);
+            window_rects(right, y, x + left_columns + 1, rows, right_columns, rects);
+        }
+        Layout::HSplit { top, bottom } => {
+            let (top_rows, bottom_rows) = split_rows(rows);
+            window_rects(top, y, x, top_rows, columns, rects);
+            window_rects(bottom, y + top_rows + 1, x, bottom_rows, columns, rects);
+        }
+    }
+}
+
+/// Find the [`Window`] drawn at the screen position `(y, x)` of a
+/// `layout` that is `rows` by `columns` big.
+///
+/// The [`Window`] is returned along with the location in its
+/// [`Buffer`] drawn at that position.  Positions past the end of a
+/// line map to the end of that line.  Positions on the mode line map
+/// to the last line of text.  The separators between [`Window`]s
+/// don't belong to any [`Window`].
+///
+/// [`Window`]: ../struct.Window.html
+/// [`Buffer`]: ../struct.Buffer.html
+pub fn window_at(
+    layout: &Layout,
+    y: usize,
+    x: usize,
+    rows: usize,
+    columns: usize,
+) -> Option<(Arc<Mutex<Window>>, usize)> {
+    let mut rects = Vec::new();
+    window_rects(layout, 0, 0, rows, columns, &mut rects);
+    for rect in rects {
+        if y >= rect.y && y < rect.y + rect.rows && x >= rect.x && x < rect.x + rect.columns {
+            let location = {
+                let window = rect.window.lock();
+                let gutter_columns = gutter_columns(Gutter::new(&window).as_ref(), rect.columns);
+                let buffer = window.buffer.lock();
+                let start = std::cmp::min(window.start, buffer.len());
+                let text_rows = rect.rows.saturating_sub(1);
+                let row = std::cmp::min(y - rect.y, text_rows.saturating_sub(1));
+                let column = (x - rect.x).saturating_sub(gutter_columns);
+                start
+                    + location_at(
+                        buffer.display_iter_at(start),
+                        &window.display_options,
+                        window.hscroll,
+                        rect.columns - gutter_columns,
+                        row,
+                        column,
+                    )
+            };
+            return Some((rect.window, location));
+        }
+    }
+    None
+}
+
+/// Find the offset into `iter` that [`draw_window`] draws at `(y, x)`
+/// when drawing a window with `options` and `columns` columns of text
+/// scrolled `hscroll` columns horizontally.
+///
+/// If `(y, x)` is past the end of a line, the offset of the end of
+/// that line is returned.  If it is past the end of `iter`, the
+/// length of `iter` is returned.
+///
+/// [`draw_window`]: fn.draw_window.html
+pub fn location_at<I>(
+    iter: I,
+    options: &DisplayOptions,
+    hscroll: usize,
+    columns: usize,
+    y: usize,
+    x: usize,
+) -> usize
+where
+    I: Iterator<Item = char>,
+{
+    let mut placer = Placer::new(columns, options, hscroll);
+    let x = x + placer.hscroll;
+    let mut location = 0;
+    for ch in iter {
+        let width = placer.fit(ch);
+        if placer.row > y
+            || (placer.row == y && width != 0 && placer.column + width > x)
+            || (placer.row == y && ch == '\n')
+        {
+            return location;
+        }
+        placer.advance(ch, width);
+        location += 1;
+    }
+    location
+}
+
+/// Find the row and column that [`draw_window`] draws the
+/// `location`th character of `iter` at when drawing a window with
+/// `options` and `columns` columns of text.
+///
+/// This is the inverse of [`location_at`].  The column doesn't
+/// account for horizontal scrolling.
+///
+/// [`draw_window`]: fn.draw_window.html
+/// [`location_at`]: fn.location_at.html
+pub fn position_of<I>(
+    iter: I,
+    options: &DisplayOptions,
+    columns: usize,
+    location: usize,
+) -> (usize, usize)
+where
+    I: Iterator<Item = char>,
+{
+    let mut placer = Placer::new(columns, options, 0);
+    for ch in iter.take(location) {
+        let width = placer.fit(ch);
+        placer.advance(ch, width);
+    }
+    (placer.row, placer.column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use debug_renderer::DebugRenderer;
+    use overlay::Overlay;
+
+    #[test]
+    fn location_at_lines() {
+        let s = "abc\ndefg\n\nh";
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 0, 0),
+            0
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 0, 2),
+            2
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 0, 10),
+            3
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 1, 1),
+            5
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 2, 5),
+            9
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 3, 0),
+            10
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 3, 1),
+            11
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 20, 7, 1),
+            11
+        );
+    }
+
+    #[test]
+    fn location_at_wrapped() {
+        let s = "abcdefghij\nk";
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 4, 0, 3),
+            3
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 4, 1, 0),
+            4
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 4, 2, 1),
+            9
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 4, 2, 3),
+            10
+        );
+        assert_eq!(
+            location_at(s.chars(), &DisplayOptions::default(), 0, 4, 3, 0),
+            11
+        );
+    }
+
+    #[test]
+    fn window_at_vsplit() {
+        let left: Arc<Mutex<Window>> = Arc::default();
+        let right: Arc<Mutex<Window>> = Arc::default();
+        left.lock().insert_str("abc\ndef").unwrap();
+        right.lock().insert_str("ghi").unwrap();
+        let layout = Layout::VSplit {
+            left: left.clone().into(),
+            right: right.clone().into(),
+        };
+        let (window, location) = window_at(&layout, 1, 1, 15, 20).unwrap();
+        assert!(Arc::ptr_eq(&window, &left));
+        assert_eq!(location, 5);
+        assert!(window_at(&layout, 1, 10, 15, 20).is_none());
+        let (window, location) = window_at(&layout, 0, 13, 15, 20).unwrap();
+        assert!(Arc::ptr_eq(&window, &right));
+        assert_eq!(location, 2);
+        let (window, location) = window_at(&layout, 14, 13, 15, 20).unwrap();
+        assert!(Arc::ptr_eq(&window, &right));
+        assert_eq!(location, 3);
+    }
+
+    #[test]
+    fn window_at_scrolled() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc\ndef\nghi").unwrap();
+        window.lock().start = 4;
+        let layout = Layout::Window(window.clone());
+        let (_, location) = window_at(&layout, 1, 2, 15, 20).unwrap();
+        assert_eq!(location, 10);
+    }
+
+    #[test]
+    fn split_tiny() {
+        assert_eq!(split_columns(0), (0, 0));
+        assert_eq!(split_columns(1), (0, 0));
+        assert_eq!(split_rows(0), (0, 0));
+        assert_eq!(split_rows(2), (1, 0));
+    }
+
+    #[test]
+    fn draw_tiny_sizes() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc\ndef").unwrap();
+        // Put the cursor on a character so truncated lines have to
+        // scroll to it.
+        window.lock().set_cursor(0);
+        let layout = Layout::HSplit {
+            top: Layout::VSplit {
+                left: window.clone().into(),
+                right: window.clone().into(),
+            }
+            .into(),
+            bottom: window.clone().into(),
+        };
+        for &wrap_mode in &[WrapMode::Wrap, WrapMode::Truncate] {
+            window.lock().display_options.wrap_mode = wrap_mode;
+            for rows in 0..5 {
+                for columns in 0..5 {
+                    let mut renderer = DebugRenderer::new();
+                    renderer.buffer = vec![vec![' '; columns]; rows];
+                    draw(
+                        &mut renderer,
+                        &layout,
+                        Some(&window),
+                        &ModeLine::default(),
+                        rows,
+                        columns,
+                    )
+                    .unwrap();
+                    window_at(&layout, 0, 0, rows, columns);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn draw_truncates_mode_line() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abcdef").unwrap();
+        let mut renderer = DebugRenderer::new();
+        renderer.buffer = vec![vec![' '; 4]; 2];
+        let layout = Layout::Window(window.clone());
+        draw(
+            &mut renderer,
+            &layout,
+            Some(&window),
+            &ModeLine::default(),
+            2,
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            renderer.buffer,
+            vec![
+                "abcd".chars().collect::<Vec<_>>(),
+                "*scr".chars().collect::<Vec<_>>(),
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_only_changes() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc").unwrap();
+        let other: Arc<Mutex<Window>> = Arc::default();
+        let layout = Layout::VSplit {
+            left: window.clone().into(),
+            right: other.clone().into(),
+        };
+        let mut renderer = DebugRenderer::new();
+        renderer.buffer = vec![vec![' '; 9]; 2];
+        let mode_line = ModeLine::default();
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 2, 9).unwrap();
+        assert_eq!(renderer.buffer, grid(&["abc |    ", "*scr|*scr"]));
+
+        // Nothing changed so nothing is drawn.
+        renderer.buffer[1][0] = 'X';
+        renderer.buffer[0][5] = 'Y';
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 2, 9).unwrap();
+        assert_eq!(renderer.buffer, grid(&["abc |Y   ", "Xscr|*scr"]));
+        assert_eq!(renderer.selected_cursors, vec![(0, 3)]);
+
+        {
+            let mut window = window.lock();
+            window.set_cursor(2);
+            window.delete_1().unwrap();
+        }
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 2, 9).unwrap();
+        assert_eq!(renderer.buffer, grid(&["ab  |Y   ", "Xscr|*scr"]));
+        assert_eq!(renderer.selected_cursors, vec![(0, 2)]);
+
+        renderer.screen.invalidate();
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 2, 9).unwrap();
+        assert_eq!(renderer.buffer, grid(&["ab  |    ", "*scr|*scr"]));
+    }
+
+    fn grid(rows: &[&str]) -> Vec<Vec<char>> {
+        rows.iter().map(|row| row.chars().collect()).collect()
+    }
+
+    fn draw_single(window: &Arc<Mutex<Window>>, rows: usize, columns: usize) -> DebugRenderer {
+        let mut renderer = DebugRenderer::new();
+        renderer.buffer = vec![vec![' '; columns]; rows];
+        let layout = Layout::Window(window.clone());
+        draw(
+            &mut renderer,
+            &layout,
+            Some(window),
+            &ModeLine::default(),
+            rows,
+            columns,
+        )
+        .unwrap();
+        renderer
+    }
+
+    #[test]
+    fn draw_line_numbers_absolute() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window
+            .lock()
+            .insert_str("a\nb\nc\nd\ne\nf\ng\nh\ni\nj")
+            .unwrap();
+        window.lock().start = 14;
+        window.lock().display_options.line_numbers = LineNumbers::Absolute;
+        let renderer = draw_single(&window, 4, 6);
+        assert_eq!(
+            renderer.buffer,
+            grid(&[" 8 h  ", " 9 i  ", "10 j  ", "*scrat"])
+        );
+        assert_eq!(renderer.selected_cursors, vec![(2, 4)]);
+    }
+
+    #[test]
+    fn draw_line_numbers_relative_wrapped() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abcde\nf\ng").unwrap();
+        window.lock().set_cursor(6);
+        window.lock().display_options.line_numbers = LineNumbers::Relative;
+        let renderer = draw_single(&window, 5, 5);
+        assert_eq!(
+            renderer.buffer,
+            grid(&["1 abc", "  de ", "2 f  ", "1 g  ", "*scra"])
+        );
+    }
+
+    #[test]
+    fn draw_truncated_lines() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abcdef\ng h").unwrap();
+        window.lock().display_options.wrap_mode = WrapMode::Truncate;
+        window.lock().display_options.show_whitespace = true;
+        let renderer = draw_single(&window, 3, 4);
+        assert_eq!(renderer.buffer, grid(&["abc$", "g·h ", "*scr"]));
+        assert_eq!(renderer.selected_cursors, vec![(1, 3)]);
+    }
+
+    #[test]
+    fn draw_truncated_scrolls_to_cursor() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window
+            .lock()
+            .insert_str("abcdefghij\nk\nlmnopqrstu")
+            .unwrap();
+        window.lock().display_options.wrap_mode = WrapMode::Truncate;
+        window.lock().set_cursor(7);
+        let renderer = draw_single(&window, 4, 6);
+        assert_eq!(window.lock().hscroll, 4);
+        assert_eq!(
+            renderer.buffer,
+            grid(&["$fghij", "$     ", "$qrstu", "*scrat"])
+        );
+        assert_eq!(renderer.selected_cursors, vec![(0, 3)]);
+
+        let layout = Layout::Window(window.clone());
+        assert_eq!(window_at(&layout, 2, 2, 4, 6).unwrap().1, 19);
+        assert_eq!(window_at(&layout, 1, 2, 4, 6).unwrap().1, 12);
+
+        window.lock().set_cursor(1);
+        draw_single(&window, 4, 6);
+        assert_eq!(window.lock().hscroll, 0);
+    }
+
+    #[test]
+    fn window_at_line_numbers() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc\ndef").unwrap();
+        window.lock().display_options.line_numbers = LineNumbers::Absolute;
+        let layout = Layout::Window(window.clone());
+        assert_eq!(window_at(&layout, 1, 0, 15, 20).unwrap().1, 4);
+        assert_eq!(window_at(&layout, 1, 3, 15, 20).unwrap().1, 5);
+    }
+
+    #[test]
+    fn draw_tabs() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("\tab\tc\n1234567\td").unwrap();
+        window.lock().display_options.tab_width = 4;
+        window.lock().set_cursor(4);
+        let renderer = draw_single(&window, 4, 10);
+        assert_eq!(
+            renderer.buffer,
+            grid(&["    ab  c ", "1234567 d ", "          ", "*scratch* "])
+        );
+        assert_eq!(renderer.selected_cursors, vec![(0, 8)]);
+    }
+
+    #[test]
+    fn draw_wide_and_combining() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("a日本e\u{301}x\nab日").unwrap();
+        window.lock().set_cursor(4);
+        let renderer = draw_single(&window, 5, 4);
+        assert_eq!(
+            renderer.buffer,
+            grid(&["a日  ", "本 ex", "    ", "ab日 ", "*scr"])
+        );
+        assert_eq!(renderer.selected_cursors, vec![(1, 3)]);
+
+        let s = "a日本e\u{301}x";
+        let options = DisplayOptions::default();
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 0, 2), 1);
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 0, 3), 2);
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 1, 1), 2);
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 1, 2), 3);
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 1, 3), 5);
+        assert_eq!(location_at(s.chars(), &options, 0, 4, 2, 0), 6);
+    }
+
+    #[test]
+    fn draw_overlays() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc def\nghi").unwrap();
+        window.lock().set_cursor(5);
+        {
+            let window = window.lock();
+            let mut buffer = window.buffer.lock();
+            let mut hidden = Overlay::new(&buffer, 1..3);
+            hidden.invisible = true;
+            buffer.overlays.push(hidden);
+            let mut face = Overlay::new(&buffer, 4..7);
+            face.face = Some(Attribute::Inverted);
+            buffer.overlays.push(face);
+        }
+        let layout = Layout::Window(window.clone());
+        let mut renderer = DebugRenderer::new();
+        renderer.buffer = vec![vec![' '; 8]; 3];
+        let mode_line = ModeLine::default();
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 3, 8).unwrap();
+        assert_eq!(renderer.buffer, grid(&["a def   ", "ghi     ", "*scratch"]));
+        assert_eq!(renderer.selected_cursors, vec![(0, 3)]);
+        assert_eq!(renderer.attributes[0][1], None);
+        assert_eq!(renderer.attributes[0][2..5], [Some(Attribute::Inverted); 3]);
+        assert_eq!(window_at(&layout, 0, 2, 3, 8).unwrap().1, 4);
+
+        // Removing the overlays draws the window again.
+        window.lock().buffer.lock().overlays.clear();
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 3, 8).unwrap();
+        assert_eq!(renderer.buffer, grid(&["abc def ", "ghi     ", "*scratch"]));
+        assert_eq!(renderer.attributes[0][4], None);
+    }
+
+    #[test]
+    fn draw_secondary_cursors() {
+        let window: Arc<Mutex<Window>> = Arc::default();
+        window.lock().insert_str("abc\ndef").unwrap();
+        window.lock().set_cursor(1);
+        window.lock().add_cursor(5);
+        let layout = Layout::Window(window.clone());
+        let mut renderer = DebugRenderer::new();
+        renderer.buffer = vec![vec![' '; 8]; 3];
+        let mode_line = ModeLine::default();
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 3, 8).unwrap();
+        assert_eq!(renderer.selected_cursors, vec![(1, 1)]);
+        assert_eq!(renderer.unselected_cursors, vec![(0, 1)]);
+
+        // Moving a secondary cursor draws the window again.
+        {
+            let mut window = window.lock();
+            let buffer = window.buffer.clone();
+            buffer
+                .lock()
+                .set_marker(&mut window.cursors[0].marker, 2)
+                .unwrap();
+        }
+        draw(&mut renderer, &layout, Some(&window), &mode_line, 3, 8).unwrap();
+        assert_eq!(renderer.unselected_cursors, vec![(0, 2)]);
+    }
+
+    #[test]
+    fn position_of_wrapped() {
+        let s = "abcdefghij\nk";
+        let options = DisplayOptions::default();
+        for &(y, x) in &[(0, 0), (0, 3), (1, 0), (2, 1), (2, 2), (3, 0)] {
+            let location = location_at(s.chars(), &options, 0, 4, y, x);
+            assert_eq!(position_of(s.chars(), &options, 4, location), (y, x));
+        }
+        assert_eq!(position_of(s.chars(), &options, 4, 12), (3, 1));
+    }
+
+    #[test]
+    fn location_at_tabs() {
+        let s = "a\tb";
+        let options = DisplayOptions::default();
+        assert_eq!(location_at(s.chars(), &options, 0, 20, 0, 1), 1);
+        assert_eq!(location_at(s.chars(), &options, 0, 20, 0, 7), 1);
+        assert_eq!(location_at(s.chars(), &options, 0, 20, 0, 8), 2);
+    }
+}
@@ -1,7 +1,9 @@
 use buffer::Buffer;
-use input::Input;
+use draw::{gutter_columns, window_at, window_rects, Gutter};
+use input::{Input, Key};
 use layout::Layout;
 use messages::Messages;
+use mode_line::ModeLine;
 use parking_lot::Mutex;
 use renderer::Renderer;
 use std::sync::Arc;
@@ -12,6 +14,16 @@ pub struct Frame {
     pub renderer: Box<Renderer>,
     pub selected_window: Arc<Mutex<Window>>,
     pub messages: Messages,
+    pub mode_line: ModeLine,
+    /// The position (row, column) of the mouse if the last [`Input`]
+    /// was a mouse event.
+    ///
+    /// [`Input`]: struct.Input.html
+    pub mouse_position: Option<(usize, usize)>,
+    /// The text of the last paste [`Input`] that hasn't been inserted yet.
+    ///
+    /// [`Input`]: struct.Input.html
+    pub pasted_text: Option<String>,
 }
 
 impl Frame {
@@ -21,6 +33,9 @@ impl Frame {
             renderer,
             selected_window: selected_window,
             messages: Messages::new(),
+            mode_line: ModeLine::default(),
+            mouse_position: None,
+            pasted_text: None,
         }
     }
 
@@ -41,11 +56,110 @@ impl Frame {
         } else {
             None
         };
-        self.renderer
-            .show(&self.layout, selected_window, self.messages.poll())
+        self.renderer.show(
+            &self.layout,
+            selected_window,
+            &self.mode_line,
+            self.messages.poll(),
+        )
     }
 
+    /// Read the next [`Input`] from the [`Renderer`].
+    ///
+    /// Pressing a key dismisses the message in the echo area.
+    ///
+    /// [`Input`]: struct.Input.html
+    /// [`Renderer`]: trait.Renderer.html
     pub fn getch(&mut self) -> Option<Input> {
-        self.renderer.getch()
+        let input = self.renderer.getch();
+        if let Some(input) = input {
+            if input.key != Key::Resize {
+                self.messages.dismiss();
+            }
+            self.mouse_position = if input.is_mouse() {
+                self.renderer.mouse_position()
+            } else {
+                None
+            };
+            if input.key == Key::Paste {
+                self.pasted_text = self.renderer.take_pasted_text();
+            }
+        }
+        input
+    }
+
+    /// Find the [`Window`] displayed at the screen position `(y, x)`
+    /// and the location in its [`Buffer`] displayed there.
+    ///
+    /// See [`draw::window_at`].
+    ///
+    /// [`Window`]: struct.Window.html
+    /// [`Buffer`]: struct.Buffer.html
+    /// [`draw::window_at`]: draw/fn.window_at.html
+    pub fn window_at(&self, y: usize, x: usize) -> Option<(Arc<Mutex<Window>>, usize)> {
+        let (rows, columns) = self.renderer.size();
+        window_at(&self.layout, y, x, rows, columns)
+    }
+
+    /// Find the [`Window`] under the mouse, if the last [`Input`] was
+    /// a mouse event.
+    ///
+    /// [`Window`]: struct.Window.html
+    /// [`Input`]: struct.Input.html
+    pub fn window_at_mouse(&self) -> Option<(Arc<Mutex<Window>>, usize)> {
+        self.mouse_position.and_then(|(y, x)| self.window_at(y, x))
+    }
+
+    /// Get the number of rows and columns of text displayed by `window`.
+    ///
+    /// This excludes the mode line and line numbers.  If `window` isn't in the
+    /// [`Layout`] then `None` is returned.
+    ///
+    /// [`Layout`]: enum.Layout.html
+    pub fn window_size(&self, window: &Arc<Mutex<Window>>) -> Option<(usize, usize)> {
+        let (rows, columns) = self.renderer.size();
+        let mut rects = Vec::new();
+        window_rects(&self.layout, 0, 0, rows, columns, &mut rects);
+        rects
+            .into_iter()
+            .find(|rect| Arc::ptr_eq(&rect.window, window))
+            .map(|rect| {
+                let gutter = Gutter::new(&window.lock());
+                let columns = rect.columns - gutter_columns(gutter.as_ref(), rect.columns);
+                (rect.rows.saturating_sub(1), columns)
+            })
+    }
+
+    /// Select the [`Window`] under the mouse and move its cursor to
+    /// the location under the mouse.
+    ///
+    /// Returns the newly selected [`Window`] or `None` if the mouse
+    /// isn't over a [`Window`].
+    ///
+    /// [`Window`]: struct.Window.html
+    pub fn select_at_mouse(&mut self) -> Option<Arc<Mutex<Window>>> {
+        let (window, location) = self.window_at_mouse()?;
+        window.lock().set_cursor(location);
+        self.selected_window = window.clone();
+        Some(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use debug_renderer::DebugRenderer;
+    use input::kbd;
+
+    #[test]
+    fn getch_records_mouse_position() {
+        let mut renderer = DebugRenderer::from(vec![kbd("Mouse1"), kbd("a")]);
+        renderer.mouse_position = Some((3, 4));
+        let window = Arc::new(Mutex::new(Window::new()));
+        let mut frame = Frame::new(window, Box::new(renderer));
+        frame.getch().unwrap();
+        assert_eq!(frame.mouse_position, Some((3, 4)));
+        frame.getch().unwrap();
+        assert_eq!(frame.mouse_position, None);
     }
 }
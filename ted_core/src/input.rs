@@ -14,12 +14,45 @@ pub struct Input {
 pub enum Key {
     Key(char),
     Function(i8),
+    /// A mouse event.
+    ///
+    /// The position of the mouse is stored in [`Frame::mouse_position`].
+    ///
+    /// [`Frame::mouse_position`]: struct.Frame.html#structfield.mouse_position
+    Mouse(Mouse),
+    /// Text was pasted into the terminal.
+    ///
+    /// The text is stored in [`Frame::pasted_text`].
+    ///
+    /// [`Frame::pasted_text`]: struct.Frame.html#structfield.pasted_text
+    Paste,
+    /// The terminal was resized.
+    Resize,
+}
+
+/// A mouse action
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mouse {
+    /// The button was pressed down.
+    Press(u8),
+    /// The button was released.
+    Release(u8),
+    /// The mouse was moved while the button was held down.
+    Drag(u8),
+    /// The scroll wheel was moved up.
+    WheelUp,
+    /// The scroll wheel was moved down.
+    WheelDown,
 }
 
 impl Input {
     pub fn is_unmodified(&self) -> bool {
         !self.control && !self.alt
     }
+
+    pub fn is_mouse(&self) -> bool {
+        matches!(self.key, Key::Mouse(_))
+    }
 }
 
 pub const BACKSPACE: Key = Key::Key(127 as char);
@@ -42,6 +75,21 @@ impl fmt::Debug for Key {
         match self {
             &Key::Key(c) => write!(f, "{}", c),
             &Key::Function(function) => write!(f, "F{}", function),
+            &Key::Mouse(mouse) => write!(f, "{:?}", mouse),
+            &Key::Paste => write!(f, "Paste"),
+            &Key::Resize => write!(f, "Resize"),
+        }
+    }
+}
+
+impl fmt::Debug for Mouse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mouse::Press(button) => write!(f, "Mouse{}", button),
+            Mouse::Release(button) => write!(f, "MouseRelease{}", button),
+            Mouse::Drag(button) => write!(f, "MouseDrag{}", button),
+            Mouse::WheelUp => write!(f, "WheelUp"),
+            Mouse::WheelDown => write!(f, "WheelDown"),
         }
     }
 }
@@ -51,7 +99,7 @@ impl fmt::Debug for Key {
 /// # Examples
 ///
 /// ```
-/// # use ted_core::{kbd, Key, Input, BACKSPACE};
+/// # use ted_core::{kbd, Key, Input, Mouse, BACKSPACE};
 /// assert_eq!(kbd("a"), Input { key: Key::Key('a'), control: false, alt: false });
 /// assert_eq!(kbd("C-a"), Input { key: Key::Key('a'), control: true, alt: false });
 /// assert_eq!(kbd("A-a"), Input { key: Key::Key('a'), control: false, alt: true });
@@ -64,44 +112,101 @@ impl fmt::Debug for Key {
 /// assert_eq!(kbd("C-Backspace"), Input { key: BACKSPACE, control: true, alt: false });
 /// assert_eq!(kbd("A-Backspace"), Input { key: BACKSPACE, control: false, alt: true });
 /// assert_eq!(kbd("C-A-Backspace"), Input { key: BACKSPACE, control: true, alt: true });
+/// assert_eq!(kbd("Mouse1"), Input { key: Key::Mouse(Mouse::Press(1)), control: false, alt: false });
+/// assert_eq!(kbd("MouseDrag1"), Input { key: Key::Mouse(Mouse::Drag(1)), control: false, alt: false });
+/// assert_eq!(kbd("WheelUp"), Input { key: Key::Mouse(Mouse::WheelUp), control: false, alt: false });
+/// assert_eq!(kbd("Paste"), Input { key: Key::Paste, control: false, alt: false });
+/// assert_eq!(kbd("Resize"), Input { key: Key::Resize, control: false, alt: false });
 /// ```
 pub fn kbd(k: &str) -> Input {
-    if k.starts_with("C-") {
-        let mut i = kbd(&k[2..]);
+    try_kbd(k).unwrap_or_else(|message| panic!("In kbd(): {}", message))
+}
+
+/// Parse an [`Input`] like [`kbd`], returning an error if `k` isn't
+/// valid.
+///
+/// `Return`, `Space` and `Tab` can be used for the keys that are hard
+/// to write out.
+///
+/// # Examples
+///
+/// ```
+/// # use ted_core::{kbd, try_kbd};
+/// assert_eq!(try_kbd("C-x"), Ok(kbd("C-x")));
+/// assert_eq!(try_kbd("C-Return"), Ok(kbd("C-\n")));
+/// assert_eq!(try_kbd("Space"), Ok(kbd(" ")));
+/// assert!(try_kbd("Fx").is_err());
+/// assert!(try_kbd("ab").is_err());
+/// ```
+///
+/// [`Input`]: struct.Input.html
+/// [`kbd`]: fn.kbd.html
+pub fn try_kbd(k: &str) -> Result<Input, String> {
+    let unmodified = |key| Input {
+        control: false,
+        alt: false,
+        key,
+    };
+    if let Some(rest) = k.strip_prefix("C-") {
+        let mut i = try_kbd(rest)?;
         i.control = true;
-        i
-    } else if k.starts_with("A-") {
-        let mut i = kbd(&k[2..]);
+        Ok(i)
+    } else if let Some(rest) = k.strip_prefix("A-") {
+        let mut i = try_kbd(rest)?;
         i.alt = true;
-        i
+        Ok(i)
     } else if k == "Backspace" {
-        Input {
-            control: false,
-            alt: false,
-            key: BACKSPACE,
-        }
-    } else if k.starts_with('F') {
-        Input {
-            control: false,
-            alt: false,
-            key: Key::Function(
-                k[1..]
-                    .parse()
-                    .expect("In kbd(): 'F' encountered without number (as in F1)"),
-            ),
-        }
+        Ok(unmodified(BACKSPACE))
+    } else if k == "Return" {
+        Ok(unmodified(Key::Key('\n')))
+    } else if k == "Space" {
+        Ok(unmodified(Key::Key(' ')))
+    } else if k == "Tab" {
+        Ok(unmodified(Key::Key('\t')))
+    } else if k == "Paste" {
+        Ok(unmodified(Key::Paste))
+    } else if k == "Resize" {
+        Ok(unmodified(Key::Resize))
+    } else if let Some(mouse) = parse_mouse(k)? {
+        Ok(unmodified(Key::Mouse(mouse)))
+    } else if k.starts_with('F') && k.len() > 1 {
+        let function = k[1..]
+            .parse()
+            .map_err(|e| format!("'F' encountered without number (as in F1): {:?}", e))?;
+        Ok(unmodified(Key::Function(function)))
     } else {
         let mut chars = k.chars();
-        let ch = chars.next().unwrap();
-        assert!(chars.next().is_none());
-        Input {
-            control: false,
-            alt: false,
-            key: Key::Key(ch),
+        match (chars.next(), chars.next()) {
+            (Some(ch), None) => Ok(unmodified(Key::Key(ch))),
+            _ => Err(format!("{:?} is not a key", k)),
         }
     }
 }
 
+fn parse_mouse(k: &str) -> Result<Option<Mouse>, String> {
+    fn button(k: &str) -> Result<u8, String> {
+        k.parse().map_err(|e| {
+            format!(
+                "mouse button encountered without number (as in Mouse1): {:?}",
+                e
+            )
+        })
+    }
+    Ok(if k == "WheelUp" {
+        Some(Mouse::WheelUp)
+    } else if k == "WheelDown" {
+        Some(Mouse::WheelDown)
+    } else if let Some(rest) = k.strip_prefix("MouseRelease") {
+        Some(Mouse::Release(button(rest)?))
+    } else if let Some(rest) = k.strip_prefix("MouseDrag") {
+        Some(Mouse::Drag(button(rest)?))
+    } else if let Some(rest) = k.strip_prefix("Mouse") {
+        Some(Mouse::Press(button(rest)?))
+    } else {
+        None
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -297,4 +402,47 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn kbd_mouse() {
+        assert_eq!(
+            kbd("C-Mouse3"),
+            Input {
+                key: Key::Mouse(Mouse::Press(3)),
+                control: true,
+                alt: false
+            }
+        );
+        assert_eq!(
+            kbd("MouseRelease1"),
+            Input {
+                key: Key::Mouse(Mouse::Release(1)),
+                control: false,
+                alt: false
+            }
+        );
+        assert_eq!(
+            kbd("WheelDown"),
+            Input {
+                key: Key::Mouse(Mouse::WheelDown),
+                control: false,
+                alt: false
+            }
+        );
+        assert_eq!("A-MouseDrag1", format!("{:?}", kbd("A-MouseDrag1")));
+        assert_eq!("WheelUp", format!("{:?}", kbd("WheelUp")));
+    }
+
+    #[test]
+    fn kbd_paste() {
+        assert_eq!(
+            kbd("Paste"),
+            Input {
+                key: Key::Paste,
+                control: false,
+                alt: false
+            }
+        );
+        assert_eq!("Paste", format!("{:?}", kbd("Paste")));
+    }
 }
@@ -13,7 +13,32 @@ impl Command for InsertCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let mut selected_window = selected_window.lock();
-        selected_window.insert(self.0)
+        selected_window.at_each_cursor(|window| window.insert(self.0))
+    }
+}
+
+#[derive(Debug)]
+struct InsertPasteCommand;
+pub fn insert_paste_command() -> Arc<dyn Command> {
+    Arc::new(InsertPasteCommand)
+}
+
+impl Command for InsertPasteCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let (selected_window, pasted_text) = {
+            let selected_frame = state.lock().display.selected_frame.clone();
+            let mut selected_frame = selected_frame.lock();
+            (
+                selected_frame.selected_window.clone(),
+                selected_frame.pasted_text.take(),
+            )
+        };
+        match pasted_text {
+            Some(pasted_text) => selected_window
+                .lock()
+                .at_each_cursor(|window| window.insert_str(&pasted_text)),
+            None => Ok(()),
+        }
     }
 }
 
@@ -60,4 +85,29 @@ mod tests {
         assert_eq!(buffer.lock().to_string(), "x ");
         assert_eq!(window.lock().cursor.get(), 1);
     }
+
+    #[test]
+    fn insert_paste_command_is_one_undo() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        let buffer = window.lock().buffer.clone();
+
+        window.lock().insert_str("ab").unwrap();
+        window.lock().set_cursor(1);
+        state.lock().display.selected_frame.lock().pasted_text = Some("x\ny".to_string());
+
+        insert_paste_command().execute(state.clone()).unwrap();
+        assert_eq!(buffer.lock().to_string(), "ax\nyb");
+        assert_eq!(window.lock().cursor.get(), 4);
+        assert!(state
+            .lock()
+            .display
+            .selected_frame
+            .lock()
+            .pasted_text
+            .is_none());
+
+        buffer.lock().undo().unwrap();
+        assert_eq!(buffer.lock().to_string(), "ab");
+    }
 }
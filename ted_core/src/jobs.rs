@@ -0,0 +1,398 @@
+use buffer::Buffer;
+use mode_line::ModeLineSegment;
+use parking_lot::{Condvar, Mutex};
+use state::State;
+use std::slice;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
+use window::Window;
+
+/// Shared between a [`Job`] and the thread doing its work.
+///
+/// [`Job`]: struct.Job.html
+#[derive(Debug)]
+pub struct JobHandle {
+    id: usize,
+    cancelled: AtomicBool,
+    /// The percentage done plus one, or 0 if unknown.
+    progress: AtomicUsize,
+    finished: Mutex<bool>,
+    finished_condvar: Condvar,
+}
+
+impl JobHandle {
+    fn new(id: usize) -> Self {
+        JobHandle {
+            id,
+            cancelled: AtomicBool::new(false),
+            progress: AtomicUsize::new(0),
+            finished: Mutex::new(false),
+            finished_condvar: Condvar::new(),
+        }
+    }
+
+    /// Get the id of the [`Job`].
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Check if the [`Job`] was cancelled.  Work should stop as soon
+    /// as possible once it is.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Report how far along the [`Job`] is, out of 100.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn set_progress(&self, percent: usize) {
+        let percent = std::cmp::min(percent, 100);
+        self.progress.store(percent + 1, Ordering::SeqCst);
+    }
+
+    fn progress(&self) -> Option<usize> {
+        match self.progress.load(Ordering::SeqCst) {
+            0 => None,
+            progress => Some(progress - 1),
+        }
+    }
+
+    /// Block until the [`Job`] has finished and its result has been
+    /// applied or thrown away.
+    ///
+    /// This must not be called from the [`Job`]'s own work.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn join(&self) {
+        let mut finished = self.finished.lock();
+        while !*finished {
+            self.finished_condvar.wait(&mut finished);
+        }
+    }
+
+    fn finish(&self) {
+        *self.finished.lock() = true;
+        self.finished_condvar.notify_all();
+    }
+}
+
+/// Work running in the background.
+///
+/// See [`spawn_job`].
+///
+/// [`spawn_job`]: fn.spawn_job.html
+#[derive(Debug)]
+pub struct Job {
+    pub id: usize,
+    pub name: String,
+    pub started: Instant,
+    handle: Arc<JobHandle>,
+}
+
+impl Job {
+    /// Get the percentage done, if the [`Job`] reports it.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn progress(&self) -> Option<usize> {
+        self.handle.progress()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.handle.is_cancelled()
+    }
+
+    /// Ask the [`Job`] to stop.  Its result won't be applied.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn cancel(&self) {
+        self.handle.cancelled.store(true, Ordering::SeqCst);
+    }
+}
+
+/// The [`Job`]s that are running.
+///
+/// [`Job`]: struct.Job.html
+#[derive(Debug, Default)]
+pub struct Jobs {
+    jobs: Vec<Job>,
+    next_id: usize,
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        Jobs::default()
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Job> {
+        self.jobs.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Cancel the [`Job`] with the id `id`.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub fn cancel(&self, id: usize) -> Result<(), String> {
+        match self.jobs.iter().find(|job| job.id == id) {
+            Some(job) => {
+                job.cancel();
+                Ok(())
+            }
+            None => Err(format!("Error: No job with id {}", id)),
+        }
+    }
+
+    fn add(&mut self, name: String) -> (usize, Arc<JobHandle>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let handle = Arc::new(JobHandle::new(id));
+        self.jobs.push(Job {
+            id,
+            name,
+            started: Instant::now(),
+            handle: handle.clone(),
+        });
+        (id, handle)
+    }
+
+    fn remove(&mut self, id: usize) {
+        self.jobs.retain(|job| job.id != id);
+    }
+}
+
+/// Run `work` on a background thread and then `apply` its result.
+///
+/// The [`Job`] is added to [`State::jobs`] until it finishes.  If it
+/// is cancelled then `apply` isn't called.  If either `work` or
+/// `apply` fail, the error is shown in the selected [`Frame`]'s
+/// messages.
+///
+/// Returns the [`JobHandle`] of the [`Job`], which can be used to
+/// wait for it to finish.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate parking_lot;
+/// # extern crate ted_core;
+/// # use parking_lot::Mutex;
+/// # use std::sync::Arc;
+/// # use ted_core::*;
+/// # fn main() {
+/// let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+/// let buffer = state.lock().display.selected_window_buffer();
+/// let handle = spawn_job(
+///     &state,
+///     "count",
+///     |_| Ok((1..4).map(|i| i.to_string()).collect::<String>()),
+///     move |_, text| replace_buffer_contents(&buffer, &text),
+/// )
+/// .unwrap();
+/// handle.join();
+/// let buffer = state.lock().display.selected_window_buffer();
+/// assert_eq!(buffer.lock().to_string(), "123");
+/// # }
+/// ```
+///
+/// [`Job`]: struct.Job.html
+/// [`JobHandle`]: struct.JobHandle.html
+/// [`State::jobs`]: struct.State.html#structfield.jobs
+/// [`Frame`]: struct.Frame.html
+pub fn spawn_job<S, W, A, T>(
+    state: &Arc<Mutex<State>>,
+    name: S,
+    work: W,
+    apply: A,
+) -> Result<Arc<JobHandle>, String>
+where
+    S: ToString,
+    W: FnOnce(&JobHandle) -> Result<T, String> + Send + 'static,
+    A: FnOnce(&Arc<Mutex<State>>, T) -> Result<(), String> + Send + 'static,
+    T: Send + 'static,
+{
+    let name = name.to_string();
+    let jobs = state.lock().jobs.clone();
+    let (id, handle) = jobs.lock().add(name.clone());
+    let result = {
+        let state = state.clone();
+        let jobs = jobs.clone();
+        let handle = handle.clone();
+        thread::Builder::new()
+            .name(format!("ted job {}", id))
+            .spawn(move || {
+                let result = work(&handle);
+                // Work that was cancelled is thrown away.
+                let result = if handle.is_cancelled() {
+                    Ok(())
+                } else {
+                    result.and_then(|result| apply(&state, result))
+                };
+                jobs.lock().remove(id);
+                if let Err(message) = result {
+                    let state = state.lock();
+                    let mut selected_frame = state.display.selected_frame.lock();
+                    selected_frame
+                        .messages
+                        .add_error(format!("{}: {}", name, message));
+                }
+                handle.finish();
+            })
+    };
+    match result {
+        Ok(_) => Ok(handle),
+        Err(e) => {
+            jobs.lock().remove(id);
+            Err(e.to_string())
+        }
+    }
+}
+
+/// Replace the contents of `buffer`, even if it is read only.
+///
+/// This is useful for applying the result of a [`Job`].  See
+/// [`Buffer::replace_contents`].
+///
+/// [`Job`]: struct.Job.html
+/// [`Buffer::replace_contents`]: struct.Buffer.html#method.replace_contents
+pub fn replace_buffer_contents(buffer: &Mutex<Buffer>, contents: &str) -> Result<(), String> {
+    let mut buffer = buffer.lock();
+    let read_only = buffer.read_only;
+    buffer.read_only = false;
+    let result = buffer.replace_contents(contents);
+    buffer.read_only = read_only;
+    result
+}
+
+const SPINNER: [char; 4] = ['|', '/', '-', '\\'];
+
+/// Show a spinner and the progress of the running [`Job`]s.
+///
+/// The segment is left out if there are no [`Job`]s.
+///
+/// [`Job`]: struct.Job.html
+#[derive(Debug)]
+pub struct JobsSegment {
+    jobs: Arc<Mutex<Jobs>>,
+}
+
+/// Construct a [`JobsSegment`] showing `jobs`.
+///
+/// [`JobsSegment`]: struct.JobsSegment.html
+pub fn jobs_segment(jobs: &Arc<Mutex<Jobs>>) -> Arc<JobsSegment> {
+    Arc::new(JobsSegment { jobs: jobs.clone() })
+}
+
+impl ModeLineSegment for JobsSegment {
+    fn text(&self, _: &Window, _: &Buffer) -> Option<String> {
+        let jobs = self.jobs.lock();
+        let job = jobs.iter().next()?;
+        let frame = job.started.elapsed().as_millis() / 100;
+        let mut text = format!("{} {}", SPINNER[frame as usize % SPINNER.len()], job.name);
+        if let Some(progress) = job.progress() {
+            text.push_str(&format!(" {}%", progress));
+        }
+        if jobs.len() > 1 {
+            text.push_str(&format!(" +{}", jobs.len() - 1));
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use debug_renderer::DebugRenderer;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn job_applies_result() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window.lock().insert_str("a\nb\nc").unwrap();
+        window.lock().set_cursor(4);
+        let buffer = window.lock().buffer.clone();
+        buffer.lock().read_only = true;
+        spawn_job(
+            &state,
+            "refresh",
+            |_| Ok("a\nB\nX\nc".to_string()),
+            move |_, text| replace_buffer_contents(&buffer, &text),
+        )
+        .unwrap()
+        .join();
+        let mut window = window.lock();
+        window.update_cursor();
+        assert_eq!(window.buffer.lock().to_string(), "a\nB\nX\nc");
+        assert!(window.buffer.lock().read_only);
+        assert_eq!(window.cursor.get(), 6);
+    }
+
+    #[test]
+    fn job_reports_errors() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        spawn_job(
+            &state,
+            "fail",
+            |_| Err::<(), _>("Error: Failed".to_string()),
+            |_, ()| Ok(()),
+        )
+        .unwrap()
+        .join();
+        let state = state.lock();
+        let mut selected_frame = state.display.selected_frame.lock();
+        assert_eq!(selected_frame.messages.poll(), Some("fail: Error: Failed"));
+    }
+
+    #[test]
+    fn job_cancel_and_progress() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let (send_started, receive_started) = channel();
+        let (send_cancelled, receive_cancelled) = channel();
+        let applied = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let applied = applied.clone();
+            spawn_job(
+                &state,
+                "slow",
+                move |handle| {
+                    handle.set_progress(40);
+                    send_started.send(()).unwrap();
+                    receive_cancelled.recv().unwrap();
+                    assert!(handle.is_cancelled());
+                    Ok(())
+                },
+                move |_, ()| {
+                    applied.store(true, Ordering::SeqCst);
+                    Ok(())
+                },
+            )
+            .unwrap()
+        };
+        receive_started.recv().unwrap();
+        let jobs = state.lock().jobs.clone();
+        {
+            let window = Window::new();
+            let buffer = window.buffer.lock();
+            let text = jobs_segment(&jobs).text(&window, &buffer).unwrap();
+            assert!(text.ends_with(" slow 40%"), "{}", text);
+        }
+        assert!(jobs.lock().cancel(handle.id() + 1).is_err());
+        jobs.lock().cancel(handle.id()).unwrap();
+        send_cancelled.send(()).unwrap();
+        handle.join();
+        assert!(jobs.lock().is_empty());
+        assert!(!applied.load(Ordering::SeqCst));
+    }
+}
@@ -13,6 +13,7 @@
 #[macro_use]
 extern crate lazy_static;
 extern crate by_address;
+extern crate libc;
 extern crate parking_lot;
 
 #[macro_use]
@@ -20,17 +21,25 @@ mod input;
 pub use input::*;
 mod display;
 pub use display::*;
+mod display_coordinator;
+pub use display_coordinator::*;
+mod display_options;
+pub use display_options::*;
 mod frame;
 pub use frame::*;
 mod renderer;
 pub use renderer::*;
 mod debug_renderer;
 pub mod draw;
+mod screen;
+pub use screen::Screen;
 pub use debug_renderer::*;
 mod command;
 pub use command::*;
 mod insert_command;
 pub use insert_command::*;
+mod jobs;
+pub use jobs::*;
 mod key_map;
 pub use key_map::*;
 mod layout;
@@ -42,13 +51,22 @@ pub use window::*;
 mod logger;
 pub use logger::*;
 mod buffer;
-pub use buffer::{Buffer, BufferName};
+pub use buffer::{Buffer, BufferName, LineEnding};
+mod char_width;
+pub use char_width::*;
 mod cursor;
 pub use cursor::*;
+mod marker;
+pub use marker::{Gravity, Marker};
 mod mode;
 pub use mode::*;
+mod overlay;
+pub use overlay::*;
+mod mode_line;
+pub use mode_line::*;
 mod messages;
 pub use messages::*;
+pub mod testing;
 
 // private modules
 mod buffer_contents;
@@ -1,45 +1,312 @@
+use buffer::Buffer;
 use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 lazy_static! {
-    static ref LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref LOG: Mutex<Log> = Mutex::new(Log::new(1000));
+}
+
+/// How important a logged message is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Error,
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LogLevel::Debug => write!(f, "DEBUG"),
+            LogLevel::Info => write!(f, "INFO"),
+            LogLevel::Error => write!(f, "ERROR"),
+        }
+    }
+}
+
+/// The `*Messages*` [`Buffer`] along with how many lines it may
+/// hold.
+///
+/// [`Buffer`]: struct.Buffer.html
+struct Log {
+    buffer: Arc<Mutex<Buffer>>,
+    lines: usize,
+    max_lines: usize,
+}
+
+impl Log {
+    fn new(max_lines: usize) -> Self {
+        let mut buffer = Buffer::new("*Messages*".into());
+        buffer.read_only = true;
+        Log {
+            buffer: Arc::new(Mutex::new(buffer)),
+            lines: 0,
+            max_lines,
+        }
+    }
+
+    fn push(&mut self, time: SystemTime, level: LogLevel, message: &str) {
+        let buffer = self.buffer.clone();
+        let mut buffer = buffer.lock();
+        self.push_to(&mut buffer, time, level, message);
+    }
+
+    /// Like [`push`] but gives up if the [`Buffer`] is locked.
+    ///
+    /// Returns whether the message was logged.
+    ///
+    /// [`push`]: #method.push
+    /// [`Buffer`]: struct.Buffer.html
+    fn try_push(&mut self, time: SystemTime, level: LogLevel, message: &str) -> bool {
+        let buffer = self.buffer.clone();
+        let mut buffer = match buffer.try_lock() {
+            Some(buffer) => buffer,
+            None => return false,
+        };
+        self.push_to(&mut buffer, time, level, message);
+        true
+    }
+
+    fn push_to(&mut self, buffer: &mut Buffer, time: SystemTime, level: LogLevel, message: &str) {
+        let entry = format!("{} {} {}\n", format_time(time), level, message);
+        self.lines += entry.matches('\n').count();
+        // Logging must never fail so errors are ignored.  The log
+        // isn't something to undo.
+        let _ = buffer.append_without_history(&entry);
+        self.trim(buffer);
+    }
+
+    /// Delete the oldest lines until there are at most `max_lines`.
+    fn trim(&mut self, buffer: &mut Buffer) {
+        if self.lines <= self.max_lines {
+            return;
+        }
+        let mut end = 0;
+        while self.lines > self.max_lines {
+            while let Ok(c) = buffer.get(end) {
+                end += 1;
+                if c == '\n' {
+                    break;
+                }
+            }
+            self.lines -= 1;
+        }
+        let _ = buffer.delete_without_history(0, end);
+    }
+}
+
+/// Format the time of day of `time` as `HH:MM:SS.mmm` in UTC.
+fn format_time(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % (24 * 60 * 60);
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / (60 * 60),
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Log a message at the [`LogLevel`] `level`.
+///
+/// The message is appended to the [`messages_buffer`] with a
+/// timestamp and its level.
+///
+/// [`LogLevel`]: enum.LogLevel.html
+/// [`messages_buffer`]: fn.messages_buffer.html
+pub fn log_with_level<S>(level: LogLevel, s: S)
+where
+    S: Into<String>,
+{
+    LOG.lock().push(SystemTime::now(), level, &s.into());
+}
+
+/// Log an error without waiting for the log to be free.
+///
+/// This is for places such as a panic hook, where the thread may
+/// already hold the log.  Returns whether the message was logged so
+/// it can be reported some other way.
+pub fn try_log_error<S>(s: S) -> bool
+where
+    S: Into<String>,
+{
+    match LOG.try_lock() {
+        Some(mut log) => log.try_push(SystemTime::now(), LogLevel::Error, &s.into()),
+        None => false,
+    }
 }
 
 /// Log a message
 ///
-/// This is similar to `println!`.  The message is placed into the log
-/// and is visible to the user at a later point.
+/// This is similar to `println!`.  The message is placed into the
+/// [`messages_buffer`] where the user can view it.
+///
+/// [`messages_buffer`]: fn.messages_buffer.html
 pub fn log<S>(s: S)
 where
     S: Into<String>,
 {
-    let mut log = LOG.lock();
-    log.push(s.into());
+    log_with_level(LogLevel::Info, s)
 }
 
 /// Log a message
 ///
-/// This is similar to `println!`.  The message is placed into the log
-/// and is visible to the user at a later point.
+/// This is similar to `println!`.  The message is placed into the
+/// [`messages_buffer`] where the user can view it.
 ///
 /// If the program is compiled in debug mode, this message will be
 /// logged.
+///
+/// [`messages_buffer`]: fn.messages_buffer.html
 pub fn log_debug<S>(s: S)
 where
     S: Into<String>,
 {
     if cfg!(debug_assertions) {
-        let mut log = LOG.lock();
-        log.push(s.into());
+        log_with_level(LogLevel::Debug, s)
     }
 }
 
+/// Log an error.
+///
+/// Errors shown to the user with [`Messages::add_error`] are logged
+/// this way.
+///
+/// [`Messages::add_error`]: struct.Messages.html#method.add_error
+pub fn log_error<S>(s: S)
+where
+    S: Into<String>,
+{
+    log_with_level(LogLevel::Error, s)
+}
+
+/// Get the read only `*Messages*` [`Buffer`] that the log is put in.
+///
+/// # Examples
+///
+/// ```
+/// # use ted_core::*;
+/// log("Hello from the log");
+/// let buffer = messages_buffer();
+/// let buffer = buffer.lock();
+/// assert!(buffer.read_only);
+/// assert!(buffer.to_string().contains(" INFO Hello from the log\n"));
+/// ```
+///
+/// [`Buffer`]: struct.Buffer.html
+pub fn messages_buffer() -> Arc<Mutex<Buffer>> {
+    LOG.lock().buffer.clone()
+}
+
+/// Set the number of lines kept in the [`messages_buffer`].
+///
+/// The oldest lines are deleted first.  The default is 1000.
+///
+/// [`messages_buffer`]: fn.messages_buffer.html
+pub fn set_messages_limit(max_lines: usize) {
+    let mut log = LOG.lock();
+    log.max_lines = max_lines;
+    let buffer = log.buffer.clone();
+    let mut buffer = buffer.lock();
+    log.trim(&mut buffer);
+}
+
 /// Print the log
 ///
-/// This will probably be replaced soon with a buffer that stores the
-/// log.
+/// This prints the contents of the [`messages_buffer`] to stdout.
+/// It is used when exiting so that the log can be read after the
+/// terminal is restored.
+///
+/// [`messages_buffer`]: fn.messages_buffer.html
 pub fn print_log() {
-    let log = LOG.lock();
-    for l in log.iter() {
-        println!("{}", l);
+    let buffer = messages_buffer();
+    print!("{}", *buffer.lock());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(seconds * 1000 + 7)
+    }
+
+    #[test]
+    fn format_time_of_day() {
+        assert_eq!(format_time(at(0)), "00:00:00.007");
+        assert_eq!(
+            format_time(at(2 * 24 * 60 * 60 + 13 * 60 * 60 + 62)),
+            "13:01:02.007"
+        );
+    }
+
+    #[test]
+    fn log_push_levels() {
+        let mut log = Log::new(10);
+        log.push(at(1), LogLevel::Info, "first");
+        log.push(at(2), LogLevel::Error, "Error: second");
+        let buffer = log.buffer.lock();
+        assert_eq!(
+            buffer.to_string(),
+            "00:00:01.007 INFO first\n00:00:02.007 ERROR Error: second\n"
+        );
+        assert!(buffer.read_only);
+        assert!(!buffer.is_modified());
+    }
+
+    #[test]
+    fn log_try_push_gives_up_when_locked() {
+        let mut log = Log::new(10);
+        let buffer = log.buffer.clone();
+        {
+            let _locked = buffer.lock();
+            assert!(!log.try_push(at(1), LogLevel::Error, "a"));
+        }
+        assert!(log.try_push(at(2), LogLevel::Error, "b"));
+        assert_eq!(buffer.lock().to_string(), "00:00:02.007 ERROR b\n");
+    }
+
+    #[test]
+    fn log_push_trims_oldest_lines() {
+        let mut log = Log::new(3);
+        log.push(at(1), LogLevel::Info, "a");
+        log.push(at(2), LogLevel::Debug, "b\nc");
+        log.push(at(3), LogLevel::Info, "d");
+        assert_eq!(log.lines, 3);
+        assert_eq!(
+            log.buffer.lock().to_string(),
+            "00:00:02.007 DEBUG b\nc\n00:00:03.007 INFO d\n"
+        );
+    }
+
+    #[test]
+    fn log_leaves_read_only_and_history_alone() {
+        let mut log = Log::new(2);
+        log.push(at(1), LogLevel::Info, "a");
+        log.push(at(2), LogLevel::Info, "b");
+        let buffer = log.buffer.clone();
+        {
+            let mut buffer = buffer.lock();
+            buffer.read_only = false;
+            // Edit the second line, which is kept when the first is
+            // trimmed.
+            buffer.insert(20, '>').unwrap();
+        }
+        log.push(at(3), LogLevel::Info, "c");
+        let mut buffer = buffer.lock();
+        assert!(!buffer.read_only);
+        assert_eq!(
+            buffer.to_string(),
+            ">00:00:02.007 INFO b\n00:00:03.007 INFO c\n"
+        );
+        assert!(buffer.undo().unwrap());
+        assert_eq!(
+            buffer.to_string(),
+            "00:00:02.007 INFO b\n00:00:03.007 INFO c\n"
+        );
     }
 }
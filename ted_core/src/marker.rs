@@ -0,0 +1,520 @@
+use parking_lot::Mutex;
+use std::fmt;
+use std::sync::Arc;
+
+/// Which way a [`Marker`] goes when text is inserted at its location.
+///
+/// [`Marker`]: struct.Marker.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gravity {
+    /// Stay before the inserted text.
+    Left,
+    /// Move after the inserted text.  This is how a cursor behaves
+    /// while typing.
+    Right,
+}
+
+/// A location in a [`Buffer`] that moves with the text around it.
+///
+/// Unlike a [`Cursor`], a `Marker` is updated by the [`Buffer`] as
+/// soon as it changes, so it never has to walk the undo history.
+/// Text inserted before the `Marker` moves it forward and deleting
+/// text around it moves it to the start of the deletion.  Text
+/// inserted exactly at the `Marker` moves it depending on its
+/// [`Gravity`].  Each update takes `O(log n)` time in the number of
+/// `Marker`s.
+///
+/// A `Marker` is created by [`Buffer::add_marker`] and is removed
+/// from the [`Buffer`] when it is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use ted_core::*;
+/// let mut buffer = Buffer::new_with_contents("*scratch*".into(), "ab");
+/// let left = buffer.add_marker(1, Gravity::Left);
+/// let right = buffer.add_marker(1, Gravity::Right);
+///
+/// buffer.insert_str(1, "xyz").unwrap();
+/// assert_eq!(left.get(), 1);
+/// assert_eq!(right.get(), 4);
+///
+/// buffer.delete_region(0, 2).unwrap();
+/// assert_eq!(left.get(), 0);
+/// assert_eq!(right.get(), 2);
+/// ```
+///
+/// [`Buffer`]: struct.Buffer.html
+/// [`Buffer::add_marker`]: struct.Buffer.html#method.add_marker
+/// [`Cursor`]: struct.Cursor.html
+/// [`Gravity`]: enum.Gravity.html
+pub struct Marker {
+    markers: Arc<Mutex<MarkerSet>>,
+    gravity: Gravity,
+    node: usize,
+}
+
+impl Marker {
+    /// Get the location of the `Marker`.
+    pub fn get(&self) -> usize {
+        self.markers
+            .lock()
+            .tree_mut(self.gravity)
+            .location(self.node)
+    }
+
+    /// Get the [`Gravity`] of the `Marker`.
+    ///
+    /// [`Gravity`]: enum.Gravity.html
+    pub fn gravity(&self) -> Gravity {
+        self.gravity
+    }
+}
+
+impl Clone for Marker {
+    /// Create another `Marker` at the same location.
+    fn clone(&self) -> Self {
+        let location = self.get();
+        MarkerSet::add(&self.markers, location, self.gravity)
+    }
+}
+
+impl Drop for Marker {
+    fn drop(&mut self) {
+        self.markers.lock().tree_mut(self.gravity).remove(self.node);
+    }
+}
+
+impl fmt::Debug for Marker {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.get())
+    }
+}
+
+/// The [`Marker`]s of a [`Buffer`].
+///
+/// [`Marker`]: struct.Marker.html
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Default)]
+pub struct MarkerSet {
+    left: MarkerTree,
+    right: MarkerTree,
+}
+
+impl MarkerSet {
+    pub fn add(markers: &Arc<Mutex<MarkerSet>>, location: usize, gravity: Gravity) -> Marker {
+        let node = markers.lock().tree_mut(gravity).add(location);
+        Marker {
+            markers: markers.clone(),
+            gravity,
+            node,
+        }
+    }
+
+    pub fn contains(markers: &Arc<Mutex<MarkerSet>>, marker: &Marker) -> bool {
+        Arc::ptr_eq(markers, &marker.markers)
+    }
+
+    pub fn set(&mut self, marker: &Marker, location: usize) {
+        let tree = self.tree_mut(marker.gravity);
+        tree.remove_node(marker.node);
+        tree.insert_node(marker.node, location);
+    }
+
+    /// Update the `Marker`s after `len` characters are inserted at
+    /// `location`.
+    pub fn insert(&mut self, location: usize, len: usize) {
+        if len != 0 {
+            self.left.shift(location, true, len as isize);
+            self.right.shift(location, false, len as isize);
+        }
+    }
+
+    /// Update the `Marker`s after the `len` characters at `location`
+    /// are deleted.
+    pub fn delete(&mut self, location: usize, len: usize) {
+        if len != 0 {
+            self.left.collapse(location, len);
+            self.right.collapse(location, len);
+        }
+    }
+
+    fn tree_mut(&mut self, gravity: Gravity) -> &mut MarkerTree {
+        match gravity {
+            Gravity::Left => &mut self.left,
+            Gravity::Right => &mut self.right,
+        }
+    }
+}
+
+const NIL: usize = usize::MAX;
+
+/// A change to the locations of a subtree of a [`MarkerTree`].
+///
+/// Each location is first set to `set`, if there is one, and then
+/// `add` is added.
+///
+/// [`MarkerTree`]: struct.MarkerTree.html
+#[derive(Clone, Copy)]
+struct Update {
+    set: Option<usize>,
+    add: isize,
+}
+
+const NO_UPDATE: Update = Update { set: None, add: 0 };
+
+impl Update {
+    fn then(self, next: Update) -> Update {
+        match next.set {
+            Some(_) => next,
+            None => Update {
+                set: self.set,
+                add: self.add + next.add,
+            },
+        }
+    }
+
+    fn apply(self, location: usize) -> usize {
+        (self.set.unwrap_or(location) as isize + self.add) as usize
+    }
+}
+
+struct Node {
+    location: usize,
+    priority: u32,
+    parent: usize,
+    left: usize,
+    right: usize,
+    /// An update that has been applied to this node but not yet to
+    /// its children.
+    pending: Update,
+}
+
+/// A treap of locations ordered by location.
+///
+/// Edits to a [`Buffer`] never change the order of its [`Marker`]s,
+/// so whole subtrees can be moved at once by lazily applying an
+/// [`Update`] to them.  Nodes are stored in a `Vec` and identified by
+/// their index so a [`Marker`] can find its node again.
+///
+/// [`Buffer`]: struct.Buffer.html
+/// [`Marker`]: struct.Marker.html
+/// [`Update`]: struct.Update.html
+struct MarkerTree {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: usize,
+    seed: u32,
+}
+
+impl Default for MarkerTree {
+    fn default() -> Self {
+        MarkerTree {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: NIL,
+            seed: 0x2545_f491,
+        }
+    }
+}
+
+impl MarkerTree {
+    fn add(&mut self, location: usize) -> usize {
+        // xorshift
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        let node = Node {
+            location,
+            priority: self.seed,
+            parent: NIL,
+            left: NIL,
+            right: NIL,
+            pending: NO_UPDATE,
+        };
+        let index = match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        self.insert_node(index, location);
+        index
+    }
+
+    fn remove(&mut self, node: usize) {
+        self.remove_node(node);
+        self.free.push(node);
+    }
+
+    fn location(&mut self, node: usize) -> usize {
+        self.push_path(node);
+        self.nodes[node].location
+    }
+
+    /// Add `add` to every location after `location`, including those
+    /// at `location` unless `stay` is set.
+    fn shift(&mut self, location: usize, stay: bool, add: isize) {
+        let (before, after) = self.split(self.root, location, stay);
+        self.update(after, Update { set: None, add });
+        self.root = self.merge(before, after);
+        self.set_parent(self.root, NIL);
+    }
+
+    /// Move the locations inside the `len` characters after
+    /// `location` to `location` and the ones after them back by
+    /// `len`.
+    fn collapse(&mut self, location: usize, len: usize) {
+        let (before, rest) = self.split(self.root, location, true);
+        let (inside, after) = self.split(rest, location + len, true);
+        self.update(
+            inside,
+            Update {
+                set: Some(location),
+                add: 0,
+            },
+        );
+        self.update(
+            after,
+            Update {
+                set: None,
+                add: -(len as isize),
+            },
+        );
+        let before = self.merge(before, inside);
+        self.root = self.merge(before, after);
+        self.set_parent(self.root, NIL);
+    }
+
+    fn insert_node(&mut self, node: usize, location: usize) {
+        {
+            let node = &mut self.nodes[node];
+            node.location = location;
+            node.left = NIL;
+            node.right = NIL;
+            node.pending = NO_UPDATE;
+        }
+        let (before, after) = self.split(self.root, location, true);
+        let before = self.merge(before, node);
+        self.root = self.merge(before, after);
+        self.set_parent(self.root, NIL);
+    }
+
+    fn remove_node(&mut self, node: usize) {
+        self.push_path(node);
+        let (left, right, parent) = {
+            let node = &self.nodes[node];
+            (node.left, node.right, node.parent)
+        };
+        let merged = self.merge(left, right);
+        if parent == NIL {
+            self.root = merged;
+            self.set_parent(merged, NIL);
+        } else if self.nodes[parent].left == node {
+            self.set_left(parent, merged);
+        } else {
+            self.set_right(parent, merged);
+        }
+    }
+
+    /// Apply the pending updates from the root down to `node`,
+    /// including those of `node` itself.
+    fn push_path(&mut self, node: usize) {
+        let mut path = Vec::new();
+        let mut n = node;
+        while n != NIL {
+            path.push(n);
+            n = self.nodes[n].parent;
+        }
+        for &n in path.iter().rev() {
+            self.push(n);
+        }
+    }
+
+    fn push(&mut self, node: usize) {
+        let (pending, left, right) = {
+            let node = &mut self.nodes[node];
+            let pending = node.pending;
+            node.pending = NO_UPDATE;
+            (pending, node.left, node.right)
+        };
+        self.update(left, pending);
+        self.update(right, pending);
+    }
+
+    fn update(&mut self, node: usize, update: Update) {
+        if node != NIL {
+            let node = &mut self.nodes[node];
+            node.location = update.apply(node.location);
+            node.pending = node.pending.then(update);
+        }
+    }
+
+    /// Split the subtree at `node` into the locations before
+    /// `location` and the rest.  If `inclusive` is set, the locations
+    /// equal to `location` are put in the first part.
+    fn split(&mut self, node: usize, location: usize, inclusive: bool) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+        self.push(node);
+        let node_location = self.nodes[node].location;
+        if node_location < location || (inclusive && node_location == location) {
+            let right = self.nodes[node].right;
+            let (middle, after) = self.split(right, location, inclusive);
+            self.set_right(node, middle);
+            self.set_parent(after, NIL);
+            (node, after)
+        } else {
+            let left = self.nodes[node].left;
+            let (before, middle) = self.split(left, location, inclusive);
+            self.set_left(node, middle);
+            self.set_parent(before, NIL);
+            (before, node)
+        }
+    }
+
+    /// Merge two subtrees where every location in `before` is at or
+    /// before every location in `after`.
+    fn merge(&mut self, before: usize, after: usize) -> usize {
+        if before == NIL {
+            return after;
+        }
+        if after == NIL {
+            return before;
+        }
+        if self.nodes[before].priority > self.nodes[after].priority {
+            self.push(before);
+            let right = self.nodes[before].right;
+            let merged = self.merge(right, after);
+            self.set_right(before, merged);
+            before
+        } else {
+            self.push(after);
+            let left = self.nodes[after].left;
+            let merged = self.merge(before, left);
+            self.set_left(after, merged);
+            after
+        }
+    }
+
+    fn set_left(&mut self, node: usize, left: usize) {
+        self.nodes[node].left = left;
+        self.set_parent(left, node);
+    }
+
+    fn set_right(&mut self, node: usize, right: usize) {
+        self.nodes[node].right = right;
+        self.set_parent(right, node);
+    }
+
+    fn set_parent(&mut self, node: usize, parent: usize) {
+        if node != NIL {
+            self.nodes[node].parent = parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use buffer::Buffer;
+
+    #[test]
+    fn markers_follow_edits() {
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "abcdef");
+        let markers: Vec<Marker> = (0..7)
+            .flat_map(|i| {
+                vec![
+                    buffer.add_marker(i, Gravity::Left),
+                    buffer.add_marker(i, Gravity::Right),
+                ]
+            })
+            .collect();
+        buffer.insert_str(3, "xy").unwrap();
+        assert_eq!(
+            markers.iter().map(Marker::get).collect::<Vec<_>>(),
+            vec![0, 0, 1, 1, 2, 2, 3, 5, 6, 6, 7, 7, 8, 8]
+        );
+        buffer.delete_region(1, 6).unwrap();
+        assert_eq!(
+            markers.iter().map(Marker::get).collect::<Vec<_>>(),
+            vec![0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 3, 3]
+        );
+        buffer.undo().unwrap();
+        assert_eq!(
+            markers.iter().map(Marker::get).collect::<Vec<_>>(),
+            vec![0, 0, 1, 6, 1, 6, 1, 6, 1, 6, 7, 7, 8, 8]
+        );
+    }
+
+    #[test]
+    fn marker_set_clone_and_drop() {
+        let mut buffer = Buffer::new_with_contents("*scratch*".into(), "abc");
+        let mut marker = buffer.add_marker(10, Gravity::Right);
+        assert_eq!(marker.get(), 3);
+        buffer.set_marker(&mut marker, 1).unwrap();
+        let clone = marker.clone();
+        drop(marker);
+        buffer.insert(0, 'x').unwrap();
+        assert_eq!(clone.get(), 2);
+
+        let other = Buffer::new("*other*".into());
+        let mut other_marker = other.add_marker(0, Gravity::Left);
+        assert!(buffer.set_marker(&mut other_marker, 0).is_err());
+    }
+
+    #[test]
+    fn many_markers_match_model() {
+        let mut tree = MarkerTree::default();
+        let mut model = Vec::new();
+        let mut x = 7u32;
+        let mut random = |n: usize| {
+            x ^= x << 13;
+            x ^= x >> 17;
+            x ^= x << 5;
+            x as usize % n
+        };
+        for i in 0..2000 {
+            match random(5) {
+                0 | 1 => {
+                    let location = random(100);
+                    model.push(Some((tree.add(location), location)));
+                }
+                2 if !model.is_empty() => {
+                    let i = random(model.len());
+                    if let Some((node, _)) = model[i].take() {
+                        tree.remove(node);
+                    }
+                }
+                3 => {
+                    let (location, add) = (random(100), random(10));
+                    tree.shift(location, i % 2 == 0, add as isize);
+                    for &mut (_, ref mut l) in model.iter_mut().flatten() {
+                        if *l > location || (i % 2 != 0 && *l == location) {
+                            *l += add;
+                        }
+                    }
+                }
+                _ => {
+                    let (location, len) = (random(100), random(10));
+                    tree.collapse(location, len);
+                    for &mut (_, ref mut l) in model.iter_mut().flatten() {
+                        if *l > location + len {
+                            *l -= len;
+                        } else if *l > location {
+                            *l = location;
+                        }
+                    }
+                }
+            }
+            for &(node, location) in model.iter().flatten() {
+                assert_eq!(tree.location(node), location);
+            }
+        }
+    }
+}
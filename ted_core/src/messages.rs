@@ -1,35 +1,240 @@
+use logger::{log_with_level, LogLevel};
+use std::collections::vec_deque;
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
+struct Message {
+    text: String,
+    shown: Instant,
+    /// If the message is being shown again from the history.
+    is_from_history: bool,
+}
+
+/// The messages shown in the echo area of a [`Frame`].
+///
+/// A message is shown until the next one is added, the user presses
+/// a key, or the `timeout` runs out.  Old messages are kept in a
+/// history that can be browsed with [`show_previous`] and
+/// [`show_next`].
+///
+/// [`Frame`]: struct.Frame.html
+/// [`show_previous`]: #method.show_previous
+/// [`show_next`]: #method.show_next
 pub struct Messages {
-    messages: VecDeque<String>,
-    message_display_time: Option<Instant>,
+    current: Option<Message>,
+    history: VecDeque<String>,
+    /// The index into `history` of the message being browsed.
+    history_index: Option<usize>,
+    /// How long a message is shown.  If `None`, it is shown until
+    /// the next key press.
+    pub timeout: Option<Duration>,
+    /// The number of messages kept in the history.
+    pub history_limit: usize,
 }
 
 impl Messages {
     pub fn new() -> Self {
         Messages {
-            messages: VecDeque::new(),
-            message_display_time: None,
+            current: None,
+            history: VecDeque::new(),
+            history_index: None,
+            timeout: Some(Duration::from_secs(10)),
+            history_limit: 100,
         }
     }
 
+    /// Show `message` to the user, replacing the current one.
+    ///
+    /// It is also logged to the `*Messages*` buffer as information.
+    /// Use [`add_error`] for errors.
+    ///
+    /// [`add_error`]: #method.add_error
     pub fn add<S: ToString>(&mut self, message: S) {
-        self.messages.push_back(message.to_string());
+        self.add_with_level(LogLevel::Info, message)
     }
 
-    pub fn poll(&mut self) -> Option<&str> {
-        if let Some(time) = self.message_display_time {
-            if time.elapsed() > Duration::from_secs(10) {
-                self.messages.pop_front();
-                self.message_display_time = None;
+    /// Show the error `message` to the user, replacing the current
+    /// one.
+    ///
+    /// It is also logged to the `*Messages*` buffer as an error.
+    pub fn add_error<S: ToString>(&mut self, message: S) {
+        self.add_with_level(LogLevel::Error, message)
+    }
+
+    /// Show `message` to the user, replacing the current one, and log
+    /// it at `level`.
+    pub fn add_with_level<S: ToString>(&mut self, level: LogLevel, message: S) {
+        let message = message.to_string();
+        log_with_level(level, message.clone());
+        self.history.push_back(message.clone());
+        while self.history.len() > self.history_limit {
+            self.history.pop_front();
+        }
+        self.history_index = None;
+        self.show(message, false);
+    }
+
+    fn show(&mut self, text: String, is_from_history: bool) {
+        self.current = Some(Message {
+            text,
+            shown: Instant::now(),
+            is_from_history,
+        });
+    }
+
+    /// Stop showing the current message.
+    ///
+    /// This is called when a key is pressed.  Browsing the history
+    /// starts over from the most recent message unless the dismissed
+    /// message came from the history.
+    pub fn dismiss(&mut self) {
+        if let Some(message) = self.current.take() {
+            if !message.is_from_history {
+                self.history_index = None;
             }
         }
-        if self.message_display_time.is_none() {
-            if !self.messages.is_empty() {
-                self.message_display_time = Some(Instant::now());
+    }
+
+    /// Get the message to show, if there is one.
+    pub fn poll(&mut self) -> Option<&str> {
+        let timed_out = match (&self.current, self.timeout) {
+            (Some(message), Some(timeout)) => message.shown.elapsed() > timeout,
+            _ => false,
+        };
+        if timed_out {
+            self.current = None;
+        }
+        self.current.as_ref().map(|message| message.text.as_ref())
+    }
+
+    /// Iterate over the previous messages, oldest first.
+    pub fn history(&self) -> vec_deque::Iter<'_, String> {
+        self.history.iter()
+    }
+
+    /// Show the message before the one being browsed.
+    ///
+    /// If no message is being browsed, the most recent one is shown.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::Messages;
+    /// let mut messages = Messages::new();
+    /// messages.add("first");
+    /// messages.add("second");
+    /// messages.dismiss();
+    /// assert_eq!(messages.poll(), None);
+    ///
+    /// messages.show_previous();
+    /// assert_eq!(messages.poll(), Some("second"));
+    /// messages.dismiss();
+    /// messages.show_previous();
+    /// assert_eq!(messages.poll(), Some("first"));
+    /// messages.show_next();
+    /// assert_eq!(messages.poll(), Some("second"));
+    /// ```
+    pub fn show_previous(&mut self) {
+        let index = match self.history_index {
+            Some(index) => index.saturating_sub(1),
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.show_history(index);
+    }
+
+    /// Show the message after the one being browsed.
+    ///
+    /// After the most recent message, nothing is shown.
+    pub fn show_next(&mut self) {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => self.show_history(index + 1),
+            _ => {
+                self.current = None;
+                self.history_index = None;
             }
         }
-        self.messages.front().map(|x| x.as_ref())
+    }
+
+    fn show_history(&mut self, index: usize) {
+        self.history_index = Some(index);
+        let text = self.history[index].clone();
+        self.show(text, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logger::messages_buffer;
+
+    #[test]
+    fn add_replaces_message() {
+        let mut messages = Messages::new();
+        messages.add("a");
+        messages.add("b");
+        assert_eq!(messages.poll(), Some("b"));
+        messages.dismiss();
+        assert_eq!(messages.poll(), None);
+        assert_eq!(messages.history().collect::<Vec<_>>(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn add_logs_at_level() {
+        let mut messages = Messages::new();
+        messages.add("Saved the file for add_logs_at_level");
+        messages.add_error("Error: Failed in add_logs_at_level");
+        let log = messages_buffer().lock().to_string();
+        assert!(log.contains(" INFO Saved the file for add_logs_at_level\n"));
+        assert!(log.contains(" ERROR Error: Failed in add_logs_at_level\n"));
+    }
+
+    #[test]
+    fn messages_time_out() {
+        let mut messages = Messages::new();
+        messages.timeout = Some(Duration::from_millis(0));
+        messages.add("a");
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(messages.poll(), None);
+
+        messages.timeout = None;
+        messages.add("b");
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(messages.poll(), Some("b"));
+    }
+
+    #[test]
+    fn history_is_limited() {
+        let mut messages = Messages::new();
+        messages.history_limit = 2;
+        messages.add("a");
+        messages.add("b");
+        messages.add("c");
+        assert_eq!(messages.history().collect::<Vec<_>>(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn browse_history() {
+        let mut messages = Messages::new();
+        messages.show_previous();
+        assert_eq!(messages.poll(), None);
+
+        messages.add("a");
+        messages.add("b");
+        messages.show_previous();
+        messages.show_previous();
+        messages.show_previous();
+        assert_eq!(messages.poll(), Some("a"));
+        messages.show_next();
+        messages.show_next();
+        assert_eq!(messages.poll(), None);
+
+        // Dismissing a normal message starts over from the newest.
+        messages.show_previous();
+        messages.show_previous();
+        messages.add("c");
+        messages.dismiss();
+        messages.show_previous();
+        assert_eq!(messages.poll(), Some("c"));
     }
 }
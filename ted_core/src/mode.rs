@@ -12,6 +12,9 @@ use std::sync::Arc;
 /// [`Window::window_modes`]: struct.Window.html#structfield.window_modes
 /// [`Buffer::buffer_modes`]: struct.Buffer.html#structfield.buffer_modes
 pub struct Mode {
+    /// The name shown in the mode line.  Modes with empty names
+    /// aren't shown.
+    pub name: String,
     /// The mapping
     pub key_map: Arc<Mutex<KeyMap>>,
 }
@@ -30,6 +33,9 @@ impl Default for Mode {
 
 impl From<Arc<Mutex<KeyMap>>> for Mode {
     fn from(key_map: Arc<Mutex<KeyMap>>) -> Self {
-        Mode { key_map }
+        Mode {
+            name: String::new(),
+            key_map,
+        }
     }
 }
@@ -0,0 +1,294 @@
+use buffer::Buffer;
+use draw::line_column;
+use std::fmt;
+use std::sync::Arc;
+use window::Window;
+
+/// A piece of information shown in the [`ModeLine`].
+///
+/// [`ModeLine`]: struct.ModeLine.html
+pub trait ModeLineSegment: fmt::Debug + Send + Sync {
+    /// Get the text of the segment for `window`, which is showing
+    /// `buffer`, or `None` to leave it out.
+    fn text(&self, window: &Window, buffer: &Buffer) -> Option<String>;
+}
+
+impl<T> ModeLineSegment for Arc<T>
+where
+    T: ModeLineSegment,
+{
+    fn text(&self, window: &Window, buffer: &Buffer) -> Option<String> {
+        let segment: &T = self;
+        segment.text(window, buffer)
+    }
+}
+
+/// The line drawn below each [`Window`] describing it.
+///
+/// The mode line is built by joining the text of each of the
+/// `segments` with `separator`.  By default only the name of the
+/// [`Buffer`] is shown.
+///
+/// [`Window`]: struct.Window.html
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Clone, Debug)]
+pub struct ModeLine {
+    pub segments: Vec<Arc<dyn ModeLineSegment>>,
+    pub separator: String,
+}
+
+impl ModeLine {
+    pub fn new() -> Self {
+        ModeLine::default()
+    }
+
+    /// Build the text of the mode line for `window`, which is
+    /// showing `buffer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate parking_lot;
+    /// # extern crate ted_core;
+    /// # use ted_core::*;
+    /// # fn main() {
+    /// let window = Window::new();
+    /// let mode_line = ModeLine {
+    ///     segments: vec![buffer_flags_segment(), buffer_name_segment(), line_column_segment()],
+    ///     separator: " ".to_string(),
+    /// };
+    /// let buffer = window.buffer.lock();
+    /// assert_eq!(mode_line.text(&window, &buffer), "-- *scratch* 1:0");
+    /// # }
+    /// ```
+    pub fn text(&self, window: &Window, buffer: &Buffer) -> String {
+        let mut text = String::new();
+        for segment in &self.segments {
+            if let Some(segment) = segment.text(window, buffer) {
+                if !text.is_empty() {
+                    text.push_str(&self.separator);
+                }
+                text.push_str(&segment);
+            }
+        }
+        text
+    }
+}
+
+impl Default for ModeLine {
+    fn default() -> Self {
+        ModeLine {
+            segments: vec![buffer_name_segment()],
+            separator: "  ".to_string(),
+        }
+    }
+}
+
+/// Show the name of the [`Buffer`].
+///
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Debug)]
+pub struct BufferNameSegment;
+
+/// Construct a [`BufferNameSegment`].
+///
+/// [`BufferNameSegment`]: struct.BufferNameSegment.html
+pub fn buffer_name_segment() -> Arc<BufferNameSegment> {
+    Arc::new(BufferNameSegment)
+}
+
+impl ModeLineSegment for BufferNameSegment {
+    fn text(&self, _: &Window, buffer: &Buffer) -> Option<String> {
+        Some(buffer.name.name.clone())
+    }
+}
+
+/// Show whether the [`Buffer`] is read only and whether it has been
+/// modified.
+///
+/// The first character is `%` if the [`Buffer`] is read only.  The
+/// second is `*` if the [`Buffer`] has been modified.  Otherwise they
+/// are `-`.
+///
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Debug)]
+pub struct BufferFlagsSegment;
+
+/// Construct a [`BufferFlagsSegment`].
+///
+/// [`BufferFlagsSegment`]: struct.BufferFlagsSegment.html
+pub fn buffer_flags_segment() -> Arc<BufferFlagsSegment> {
+    Arc::new(BufferFlagsSegment)
+}
+
+impl ModeLineSegment for BufferFlagsSegment {
+    fn text(&self, _: &Window, buffer: &Buffer) -> Option<String> {
+        Some(format!(
+            "{}{}",
+            if buffer.read_only { '%' } else { '-' },
+            if buffer.is_modified() { '*' } else { '-' }
+        ))
+    }
+}
+
+/// Show the line and column of the cursor.
+///
+/// Lines start at 1 and columns start at 0.  Lines are counted as
+/// they are drawn, like in the [`Gutter`].
+///
+/// [`Gutter`]: draw/struct.Gutter.html
+#[derive(Debug)]
+pub struct LineColumnSegment;
+
+/// Construct a [`LineColumnSegment`].
+///
+/// [`LineColumnSegment`]: struct.LineColumnSegment.html
+pub fn line_column_segment() -> Arc<LineColumnSegment> {
+    Arc::new(LineColumnSegment)
+}
+
+impl ModeLineSegment for LineColumnSegment {
+    fn text(&self, window: &Window, buffer: &Buffer) -> Option<String> {
+        let cursor = window.cursor.get();
+        let line = buffer.line_at(cursor);
+        let column = line_column(buffer, &window.display_options, cursor);
+        Some(format!("{}:{}", line + 1, column))
+    }
+}
+
+/// Show how far through the [`Buffer`] the cursor is.
+///
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Debug)]
+pub struct PercentageSegment;
+
+/// Construct a [`PercentageSegment`].
+///
+/// [`PercentageSegment`]: struct.PercentageSegment.html
+pub fn percentage_segment() -> Arc<PercentageSegment> {
+    Arc::new(PercentageSegment)
+}
+
+impl ModeLineSegment for PercentageSegment {
+    fn text(&self, window: &Window, buffer: &Buffer) -> Option<String> {
+        let cursor = window.cursor.get();
+        Some(if cursor == 0 {
+            "Top".to_string()
+        } else if cursor >= buffer.len() {
+            "Bot".to_string()
+        } else {
+            format!("{}%", cursor * 100 / buffer.len())
+        })
+    }
+}
+
+/// Show the names of the [`Mode`]s of the [`Buffer`] and [`Window`].
+///
+/// [`Mode`]s without names aren't shown.  If there are no named
+/// [`Mode`]s then the segment is left out.
+///
+/// [`Mode`]: struct.Mode.html
+/// [`Buffer`]: struct.Buffer.html
+/// [`Window`]: struct.Window.html
+#[derive(Debug)]
+pub struct ModesSegment;
+
+/// Construct a [`ModesSegment`].
+///
+/// [`ModesSegment`]: struct.ModesSegment.html
+pub fn modes_segment() -> Arc<ModesSegment> {
+    Arc::new(ModesSegment)
+}
+
+impl ModeLineSegment for ModesSegment {
+    fn text(&self, window: &Window, buffer: &Buffer) -> Option<String> {
+        let names: Vec<String> = buffer
+            .buffer_modes
+            .iter()
+            .chain(&window.window_modes)
+            .map(|mode| mode.lock().name.clone())
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.is_empty() {
+            None
+        } else {
+            Some(format!("({})", names.join(" ")))
+        }
+    }
+}
+
+/// Show the encoding and [`line_ending`] of the [`Buffer`].
+///
+/// [`line_ending`]: struct.Buffer.html#structfield.line_ending
+/// [`Buffer`]: struct.Buffer.html
+#[derive(Debug)]
+pub struct EncodingSegment;
+
+/// Construct an [`EncodingSegment`].
+///
+/// [`EncodingSegment`]: struct.EncodingSegment.html
+pub fn encoding_segment() -> Arc<EncodingSegment> {
+    Arc::new(EncodingSegment)
+}
+
+impl ModeLineSegment for EncodingSegment {
+    fn text(&self, _: &Window, buffer: &Buffer) -> Option<String> {
+        // Files that aren't UTF-8 can't be opened.
+        Some(format!("utf-8 {}", buffer.line_ending))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mode::Mode;
+    use parking_lot::Mutex;
+
+    fn standard_mode_line() -> ModeLine {
+        ModeLine {
+            segments: vec![
+                buffer_flags_segment(),
+                buffer_name_segment(),
+                line_column_segment(),
+                percentage_segment(),
+                modes_segment(),
+                encoding_segment(),
+            ],
+            separator: " ".to_string(),
+        }
+    }
+
+    #[test]
+    fn mode_line_text() {
+        let mut window = Window::new();
+        window.insert_str("ab\r\ncd\nef").unwrap();
+        window.set_cursor(5);
+        window.buffer.lock().detect_line_ending();
+        let mode_line = standard_mode_line();
+        assert_eq!(
+            mode_line.text(&window, &window.buffer.lock()),
+            "-* *scratch* 2:1 55% utf-8 CRLF"
+        );
+    }
+
+    #[test]
+    fn mode_line_modes_and_flags() {
+        let mut window = Window::new();
+        {
+            let mut buffer = window.buffer.lock();
+            buffer.insert_str(0, "ab").unwrap();
+            buffer.erase_history();
+            buffer.read_only = true;
+            let mut mode = Mode::new();
+            mode.name = "Git".to_string();
+            buffer.buffer_modes.push(Arc::new(Mutex::new(mode)));
+            buffer.buffer_modes.push(Arc::new(Mutex::new(Mode::new())));
+        }
+        window.set_cursor(2);
+        let mode_line = standard_mode_line();
+        assert_eq!(
+            mode_line.text(&window, &window.buffer.lock()),
+            "%- *scratch* 1:2 Bot (Git) utf-8 LF"
+        );
+    }
+}
@@ -0,0 +1,160 @@
+use buffer::Buffer;
+use command::Command;
+use draw::Attribute;
+use key_map::KeyMap;
+use marker::{Gravity, Marker};
+use parking_lot::Mutex;
+use state::State;
+use std::any::Any;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use window::Window;
+
+/// A range of a [`Buffer`] with properties attached to it.
+///
+/// The ends of an `Overlay` are [`Marker`]s so it follows the text
+/// as the [`Buffer`] is edited.  Text inserted at either end is
+/// outside of the `Overlay`.
+///
+/// `Overlay`s are stored in [`Buffer::overlays`].  When they
+/// overlap, the properties of the later ones take precedence.
+///
+/// # Examples
+///
+/// ```
+/// # use ted_core::*;
+/// # use ted_core::draw::Attribute;
+/// let mut buffer = Buffer::new_with_contents("*scratch*".into(), "abc def");
+/// let mut overlay = Overlay::new(&buffer, 4..7);
+/// overlay.face = Some(Attribute::Inverted);
+/// overlay.read_only = true;
+/// buffer.overlays.push(overlay);
+///
+/// buffer.insert_str(0, "xy").unwrap();
+/// assert_eq!(buffer.overlays[0].range(), 6..9);
+/// assert!(buffer.delete(7).is_err());
+/// buffer.insert_str(9, "!").unwrap();
+/// assert_eq!(buffer.to_string(), "xyabc def!");
+/// ```
+///
+/// [`Buffer`]: struct.Buffer.html
+/// [`Buffer::overlays`]: struct.Buffer.html#structfield.overlays
+/// [`Marker`]: struct.Marker.html
+#[derive(Clone)]
+pub struct Overlay {
+    start: Marker,
+    end: Marker,
+    /// The [`Attribute`] the text is drawn with.
+    ///
+    /// [`Attribute`]: draw/enum.Attribute.html
+    pub face: Option<Attribute>,
+    /// Whether the text can't be changed.  Text can still be
+    /// inserted at either end.
+    pub read_only: bool,
+    /// Keys that are bound while the cursor is in the `Overlay`.
+    ///
+    /// These are looked up by [`State::lookup`] before the buffer
+    /// modes.
+    ///
+    /// [`State::lookup`]: struct.State.html#method.lookup
+    pub key_map: Option<Arc<Mutex<KeyMap>>>,
+    /// The [`Command`] run when the text is clicked.
+    ///
+    /// [`Command`]: trait.Command.html
+    pub action: Option<Arc<dyn Command>>,
+    /// Whether the text is hidden.  Invisible text isn't drawn and
+    /// takes up no space on the screen.
+    pub invisible: bool,
+    /// Anything else to remember about the text.
+    pub data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Overlay {
+    /// Create an `Overlay` over `range` of `buffer` without any
+    /// properties.
+    ///
+    /// The `range` is kept inside the [`Buffer`].
+    ///
+    /// [`Buffer`]: struct.Buffer.html
+    pub fn new(buffer: &Buffer, range: Range<usize>) -> Self {
+        Overlay {
+            start: buffer.add_marker(range.start, Gravity::Right),
+            end: buffer.add_marker(std::cmp::max(range.start, range.end), Gravity::Left),
+            face: None,
+            read_only: false,
+            key_map: None,
+            action: None,
+            invisible: false,
+            data: None,
+        }
+    }
+
+    /// Get the location of the first character in the `Overlay`.
+    pub fn start(&self) -> usize {
+        self.start.get()
+    }
+
+    /// Get the location after the last character in the `Overlay`.
+    pub fn end(&self) -> usize {
+        std::cmp::max(self.start.get(), self.end.get())
+    }
+
+    /// Get the range of locations in the `Overlay`.
+    ///
+    /// Once all the text in the `Overlay` is deleted the range is
+    /// empty.
+    pub fn range(&self) -> Range<usize> {
+        self.start()..self.end()
+    }
+
+    /// Check if the character at `location` is in the `Overlay`.
+    pub fn contains(&self, location: usize) -> bool {
+        self.start() <= location && location < self.end()
+    }
+
+    /// Get the [`data`] if it is a `T`.
+    ///
+    /// [`data`]: #structfield.data
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_ref().and_then(|data| data.downcast_ref())
+    }
+}
+
+impl fmt::Debug for Overlay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Overlay")
+            .field("range", &self.range())
+            .field("face", &self.face)
+            .field("read_only", &self.read_only)
+            .field("key_map", &self.key_map.is_some())
+            .field("action", &self.action)
+            .field("invisible", &self.invisible)
+            .field("data", &self.data.is_some())
+            .finish()
+    }
+}
+
+/// Run the [`action`] of the [`Overlay`] at the cursor of `window`.
+///
+/// Nothing happens if there isn't one.  This is what clicking on
+/// text does.
+///
+/// [`action`]: struct.Overlay.html#structfield.action
+/// [`Overlay`]: struct.Overlay.html
+pub fn run_overlay_action(state: &Arc<Mutex<State>>, window: &Mutex<Window>) -> Result<(), String> {
+    let action = {
+        let window = window.lock();
+        let buffer = window.buffer.lock();
+        let cursor = window.cursor.clone().updated(&buffer);
+        let action = buffer
+            .overlays_at(cursor.get())
+            .filter_map(|overlay| overlay.action.clone())
+            .next();
+        action
+    };
+    match action {
+        Some(action) => action.execute(state.clone()),
+        None => Ok(()),
+    }
+}
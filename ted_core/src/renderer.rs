@@ -1,6 +1,9 @@
 use input::Input;
 use layout::Layout;
+use mode_line::ModeLine;
 use parking_lot::Mutex;
+use std::any::Any;
+use std::os::unix::io::RawFd;
 use std::sync::Arc;
 use window::Window;
 
@@ -10,14 +13,18 @@ use window::Window;
 ///
 /// [`DebugFrame`]: struct.DebugFrame.html
 /// [`CursesFrame`]: struct.CursesFrame.html
-pub trait Renderer: Send {
-    /// Show the [`Layout`] on the `Frame`.
+pub trait Renderer: Send + AsAny {
+    /// Show the [`Layout`] on the `Frame`, drawing each [`Window`]'s
+    /// [`ModeLine`] below it.
     ///
     /// [`Layout`]: enum.Layout.html
+    /// [`Window`]: struct.Window.html
+    /// [`ModeLine`]: struct.ModeLine.html
     fn show(
         &mut self,
         layout: &Layout,
         selected_window: Option<&Arc<Mutex<Window>>>,
+        mode_line: &ModeLine,
         message: Option<&str>,
     ) -> Result<(), String>;
 
@@ -25,4 +32,76 @@ pub trait Renderer: Send {
     ///
     /// [`Input`]: enum.Input.html
     fn getch(&mut self) -> Option<Input>;
+
+    /// Get the number of rows and columns the `Renderer` draws to.
+    fn size(&self) -> (usize, usize);
+
+    /// Get the position (row, column) of the last mouse [`Input`]
+    /// returned by [`getch`].
+    ///
+    /// [`Input`]: enum.Input.html
+    /// [`getch`]: #tymethod.getch
+    fn mouse_position(&self) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Take the text of the last paste [`Input`] returned by [`getch`].
+    ///
+    /// [`Input`]: enum.Input.html
+    /// [`getch`]: #tymethod.getch
+    fn take_pasted_text(&mut self) -> Option<String> {
+        None
+    }
+
+    /// Get a file descriptor that becomes readable when [`getch`] may
+    /// have more input.
+    ///
+    /// The UI loop sleeps until one of these is readable instead of
+    /// polling [`getch`].  `Renderer`s without one are polled.
+    ///
+    /// [`getch`]: #tymethod.getch
+    fn input_fd(&self) -> Option<RawFd> {
+        None
+    }
+}
+
+impl<'a> dyn Renderer + 'a {
+    /// Get this `Renderer` as a `T`, or `None` if it is a different
+    /// kind of `Renderer`.
+    pub fn downcast_ref<T: Renderer + 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Get this `Renderer` as a mutable `T`, or `None` if it is a
+    /// different kind of `Renderer`.
+    pub fn downcast_mut<T: Renderer + 'static>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+}
+
+/// Convert a reference to [`Any`] so a [`Renderer`] can be downcast
+/// to its concrete type.
+///
+/// This is implemented for every `'static` type.  Use
+/// [`Renderer::downcast_ref`] and [`Renderer::downcast_mut`] rather
+/// than calling it directly, since calling it on a `Box<Renderer>`
+/// gives the `Box` instead of the `Renderer`.
+///
+/// [`Any`]: https://doc.rust-lang.org/std/any/trait.Any.html
+/// [`Renderer`]: trait.Renderer.html
+/// [`Renderer::downcast_ref`]: trait.Renderer.html#method.downcast_ref
+/// [`Renderer::downcast_mut`]: trait.Renderer.html#method.downcast_mut
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
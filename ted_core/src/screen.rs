@@ -0,0 +1,460 @@
+use buffer::{current_state, Buffer};
+use change::StateNode;
+use char_width::char_width;
+use display_options::DisplayOptions;
+use draw::{Attribute, Character, DrawableRenderer, Gutter};
+use parking_lot::Mutex;
+use std::ops::Range;
+use std::sync::{Arc, Weak};
+use window::Window;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Cell {
+    character: Character,
+    face: Option<Attribute>,
+    cursor: Option<Attribute>,
+    /// Whether this cell is covered by a wide character in the cell
+    /// before it.
+    is_continuation: bool,
+}
+
+const BLANK: Cell = Cell {
+    character: Character::Character(' '),
+    face: None,
+    cursor: None,
+    is_continuation: false,
+};
+
+/// The area of the screen a [`Window`] is drawn in.
+///
+/// [`Window`]: ../struct.Window.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub y: usize,
+    pub x: usize,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// Everything that affects how a [`Window`] is drawn.
+///
+/// If the key of a [`Window`] is the same as in the last frame then
+/// it doesn't need to be drawn again.
+///
+/// [`Window`]: ../struct.Window.html
+pub struct WindowKey {
+    window: Weak<Mutex<Window>>,
+    buffer: Weak<Mutex<Buffer>>,
+    state: Weak<Mutex<StateNode>>,
+    start: usize,
+    cursor: usize,
+    secondary_cursors: Vec<usize>,
+    hscroll: usize,
+    options: DisplayOptions,
+    gutter: Option<Gutter>,
+    /// The range, face and whether each [`Overlay`] is invisible.
+    ///
+    /// [`Overlay`]: ../struct.Overlay.html
+    overlays: Vec<(Range<usize>, Option<Attribute>, bool)>,
+    is_selected_window: bool,
+    mode_line: String,
+    rect: Rect,
+}
+
+impl WindowKey {
+    pub fn new(
+        window_arc: &Arc<Mutex<Window>>,
+        window: &Window,
+        buffer: &Buffer,
+        gutter: Option<&Gutter>,
+        is_selected_window: bool,
+        mode_line: String,
+        rect: Rect,
+    ) -> Self {
+        WindowKey {
+            window: Arc::downgrade(window_arc),
+            buffer: Arc::downgrade(&window.buffer),
+            state: current_state(buffer),
+            start: window.start,
+            cursor: window.cursor.get(),
+            secondary_cursors: window
+                .cursors
+                .iter()
+                .map(|secondary| secondary.marker.get())
+                .collect(),
+            hscroll: window.hscroll,
+            options: window.display_options,
+            gutter: gutter.cloned(),
+            overlays: buffer
+                .overlays
+                .iter()
+                .map(|overlay| (overlay.range(), overlay.face, overlay.invisible))
+                .collect(),
+            is_selected_window,
+            mode_line,
+            rect,
+        }
+    }
+
+    /// Check if the [`Window`] would be drawn the same way for both
+    /// keys.
+    ///
+    /// The weak pointers keep their allocations alive so they can't
+    /// be reused by a different [`Window`], [`Buffer`] or state.
+    ///
+    /// [`Window`]: ../struct.Window.html
+    /// [`Buffer`]: ../struct.Buffer.html
+    fn is_unchanged(&self, other: &WindowKey) -> bool {
+        self.window.ptr_eq(&other.window)
+            && self.buffer.ptr_eq(&other.buffer)
+            && self.state.ptr_eq(&other.state)
+            && self.start == other.start
+            && self.cursor == other.cursor
+            && self.secondary_cursors == other.secondary_cursors
+            && self.hscroll == other.hscroll
+            && self.options == other.options
+            && self.gutter == other.gutter
+            && self.overlays == other.overlays
+            && self.is_selected_window == other.is_selected_window
+            && self.mode_line == other.mode_line
+            && self.rect == other.rect
+    }
+}
+
+struct CachedWindow {
+    key: WindowKey,
+    cells: Vec<Cell>,
+}
+
+/// A grid of characters and attributes that is drawn to a
+/// [`DrawableRenderer`].
+///
+/// Each frame is drawn into the `Screen` and then [`flush`]ed.  Only
+/// the cells that differ from the last frame are sent to the
+/// [`DrawableRenderer`].  The `Screen` also remembers what each
+/// [`Window`] looked like so that [`Window`]s that haven't changed
+/// don't have to be drawn again.
+///
+/// [`DrawableRenderer`]: trait.DrawableRenderer.html
+/// [`flush`]: #method.flush
+/// [`Window`]: ../struct.Window.html
+#[derive(Default)]
+pub struct Screen {
+    rows: usize,
+    columns: usize,
+    cells: Vec<Cell>,
+    /// The cells on the [`DrawableRenderer`] or `None` if they are
+    /// unknown.
+    shown: Option<Vec<Cell>>,
+    windows: Vec<CachedWindow>,
+    next_windows: Vec<CachedWindow>,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen::default()
+    }
+
+    pub fn size(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    /// Change the size of the `Screen`.
+    ///
+    /// If the size changes, the next [`flush`] redraws everything.
+    ///
+    /// [`flush`]: #method.flush
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        if (rows, columns) != (self.rows, self.columns) {
+            self.rows = rows;
+            self.columns = columns;
+            self.cells = vec![BLANK; rows * columns];
+            self.invalidate();
+        }
+    }
+
+    /// Forget what is on the [`DrawableRenderer`] so that the next
+    /// [`flush`] redraws everything.
+    ///
+    /// This should be called when the contents of the
+    /// [`DrawableRenderer`] are lost, such as when the terminal is
+    /// resized.
+    ///
+    /// [`DrawableRenderer`]: trait.DrawableRenderer.html
+    /// [`flush`]: #method.flush
+    pub fn invalidate(&mut self) {
+        self.shown = None;
+        self.windows.clear();
+        self.next_windows.clear();
+    }
+
+    /// Start drawing a new frame.
+    pub fn erase(&mut self) {
+        for cell in &mut self.cells {
+            *cell = BLANK;
+        }
+        self.next_windows.clear();
+    }
+
+    fn index(&self, y: usize, x: usize, function: &str) -> Result<usize, String> {
+        if y >= self.rows {
+            Err(format!("Error: Screen::{}(): y out of bounds", function))
+        } else if x >= self.columns {
+            Err(format!("Error: Screen::{}(): x out of bounds", function))
+        } else {
+            Ok(y * self.columns + x)
+        }
+    }
+
+    pub fn putch(&mut self, y: usize, x: usize, ch: Character) -> Result<(), String> {
+        let index = self.index(y, x, "putch")?;
+        self.cells[index].character = ch;
+        self.cells[index].is_continuation = false;
+        if let Character::Character(ch) = ch {
+            if char_width(ch) == 2 && x + 1 < self.columns {
+                self.cells[index + 1].character = Character::Character(' ');
+                self.cells[index + 1].is_continuation = true;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_attribute(&mut self, y: usize, x: usize, at: Attribute) -> Result<(), String> {
+        let index = self.index(y, x, "set_attribute")?;
+        match at {
+            Attribute::SelectedCursor | Attribute::UnselectedCursor => {
+                self.cells[index].cursor = Some(at)
+            }
+            _ => self.cells[index].face = Some(at),
+        }
+        Ok(())
+    }
+
+    /// Copy the cells of a [`Window`] from the last frame if its
+    /// `key` hasn't changed.
+    ///
+    /// Returns `true` if the [`Window`] was restored.
+    ///
+    /// [`Window`]: ../struct.Window.html
+    pub fn restore_window(&mut self, key: WindowKey) -> bool {
+        let position = self
+            .windows
+            .iter()
+            .position(|cached| cached.key.is_unchanged(&key));
+        match position {
+            Some(position) => {
+                let cached = self.windows.swap_remove(position);
+                let rect = key.rect;
+                for row in (0..rect.rows).filter(|_| rect.columns != 0) {
+                    let start = (rect.y + row) * self.columns + rect.x;
+                    let cached_start = row * rect.columns;
+                    self.cells[start..start + rect.columns]
+                        .copy_from_slice(&cached.cells[cached_start..cached_start + rect.columns]);
+                }
+                self.next_windows.push(cached);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remember the cells of a [`Window`] that was just drawn.
+    ///
+    /// [`Window`]: ../struct.Window.html
+    pub fn save_window(&mut self, key: WindowKey) {
+        let rect = key.rect;
+        let mut cells = Vec::with_capacity(rect.rows * rect.columns);
+        for row in (0..rect.rows).filter(|_| rect.columns != 0) {
+            let start = (rect.y + row) * self.columns + rect.x;
+            cells.extend_from_slice(&self.cells[start..start + rect.columns]);
+        }
+        self.next_windows.push(CachedWindow { key, cells });
+    }
+
+    /// Send the cells that changed since the last `flush` to
+    /// `display`.
+    pub fn flush<D>(&mut self, display: &mut D) -> Result<(), String>
+    where
+        D: DrawableRenderer,
+    {
+        std::mem::swap(&mut self.windows, &mut self.next_windows);
+        self.next_windows.clear();
+        if self.shown.is_none() {
+            display.erase()?;
+        }
+        display.clear_cursors()?;
+        for (index, cell) in self.cells.iter().enumerate() {
+            let (y, x) = (index / self.columns, index % self.columns);
+            let changed = match self.shown {
+                Some(ref shown) => shown[index] != *cell,
+                None => *cell != BLANK,
+            };
+            if changed && !cell.is_continuation {
+                display.putch(y, x, cell.character)?;
+                if let Some(face) = cell.face {
+                    display.set_attribute(y, x, face)?;
+                }
+            }
+            if let Some(cursor) = cell.cursor {
+                display.set_attribute(y, x, cursor)?;
+            }
+        }
+        self.shown = Some(self.cells.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use debug_renderer::DebugRenderer;
+    use input::Input;
+    use layout::Layout;
+    use mode_line::ModeLine;
+    use renderer::Renderer;
+    use state::State;
+
+    /// Records the calls made to it.
+    struct RecordingRenderer {
+        erases: usize,
+        putches: Vec<(usize, usize, Character)>,
+        attributes: Vec<(usize, usize, Attribute)>,
+        screen: Screen,
+    }
+
+    impl RecordingRenderer {
+        fn new() -> Self {
+            RecordingRenderer {
+                erases: 0,
+                putches: Vec::new(),
+                attributes: Vec::new(),
+                screen: Screen::new(),
+            }
+        }
+    }
+
+    impl Renderer for RecordingRenderer {
+        fn show(
+            &mut self,
+            _: &Layout,
+            _: Option<&Arc<Mutex<Window>>>,
+            _: &ModeLine,
+            _: Option<&str>,
+        ) -> Result<(), String> {
+            Ok(())
+        }
+        fn getch(&mut self) -> Option<Input> {
+            None
+        }
+        fn size(&self) -> (usize, usize) {
+            (2, 4)
+        }
+    }
+
+    impl DrawableRenderer for RecordingRenderer {
+        fn screen(&mut self) -> &mut Screen {
+            &mut self.screen
+        }
+        fn erase(&mut self) -> Result<(), String> {
+            self.erases += 1;
+            Ok(())
+        }
+        fn putch(&mut self, y: usize, x: usize, ch: Character) -> Result<(), String> {
+            self.putches.push((y, x, ch));
+            Ok(())
+        }
+        fn set_attribute(&mut self, y: usize, x: usize, at: Attribute) -> Result<(), String> {
+            self.attributes.push((y, x, at));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn flush_only_changes() {
+        let mut renderer = RecordingRenderer::new();
+        let mut screen = Screen::new();
+        screen.resize(2, 4);
+        screen.putch(0, 1, Character::Character('a')).unwrap();
+        screen
+            .set_attribute(0, 1, Attribute::SelectedCursor)
+            .unwrap();
+        screen.flush(&mut renderer).unwrap();
+        assert_eq!(renderer.erases, 1);
+        assert_eq!(renderer.putches, vec![(0, 1, Character::Character('a'))]);
+        assert_eq!(renderer.attributes, vec![(0, 1, Attribute::SelectedCursor)]);
+
+        renderer.putches.clear();
+        renderer.attributes.clear();
+        screen.erase();
+        screen.putch(0, 1, Character::Character('a')).unwrap();
+        screen.putch(1, 0, Character::Character('b')).unwrap();
+        screen
+            .set_attribute(0, 1, Attribute::SelectedCursor)
+            .unwrap();
+        screen.flush(&mut renderer).unwrap();
+        assert_eq!(renderer.erases, 1);
+        assert_eq!(renderer.putches, vec![(1, 0, Character::Character('b'))]);
+        assert_eq!(renderer.attributes, vec![(0, 1, Attribute::SelectedCursor)]);
+
+        renderer.putches.clear();
+        renderer.attributes.clear();
+        screen.erase();
+        screen.flush(&mut renderer).unwrap();
+        assert_eq!(
+            renderer.putches,
+            vec![
+                (0, 1, Character::Character(' ')),
+                (1, 0, Character::Character(' ')),
+            ]
+        );
+        assert_eq!(renderer.attributes, vec![]);
+    }
+
+    #[test]
+    fn flush_wide_characters() {
+        let mut renderer = RecordingRenderer::new();
+        let mut screen = Screen::new();
+        screen.resize(1, 4);
+        screen.putch(0, 0, Character::Character('a')).unwrap();
+        screen.putch(0, 1, Character::Character('b')).unwrap();
+        screen.flush(&mut renderer).unwrap();
+
+        renderer.putches.clear();
+        screen.erase();
+        screen.putch(0, 0, Character::Character('中')).unwrap();
+        screen.flush(&mut renderer).unwrap();
+        // The second column is covered by the wide character.
+        assert_eq!(renderer.putches, vec![(0, 0, Character::Character('中'))]);
+    }
+
+    #[test]
+    fn flush_after_invalidate() {
+        let mut renderer = RecordingRenderer::new();
+        let mut screen = Screen::new();
+        screen.resize(1, 4);
+        screen.putch(0, 2, Character::VLine).unwrap();
+        screen.flush(&mut renderer).unwrap();
+        screen.flush(&mut renderer).unwrap();
+        assert_eq!(renderer.erases, 1);
+        assert_eq!(renderer.putches.len(), 1);
+
+        screen.invalidate();
+        screen.flush(&mut renderer).unwrap();
+        assert_eq!(renderer.erases, 2);
+        assert_eq!(renderer.putches.len(), 2);
+    }
+
+    #[test]
+    fn downcast_renderer() {
+        let mut renderer: Box<dyn Renderer> = Box::new(RecordingRenderer::new());
+        assert!(renderer.downcast_ref::<DebugRenderer>().is_none());
+        renderer.downcast_mut::<RecordingRenderer>().unwrap().erases = 3;
+        assert_eq!(
+            renderer.downcast_ref::<RecordingRenderer>().unwrap().erases,
+            3
+        );
+
+        let state = State::new(RecordingRenderer::new());
+        assert!(state.display.debug_renderer().is_err());
+    }
+}
@@ -1,13 +1,14 @@
 use command::*;
 use display::Display;
 use input::*;
-use insert_command::insert_command;
+use insert_command::{insert_command, insert_paste_command};
+use jobs::Jobs;
 use key_map::*;
 use logger::log;
 use mode::*;
 use parking_lot::Mutex;
 use renderer::Renderer;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::Arc;
 use window::Window;
 
@@ -16,6 +17,15 @@ pub struct State {
     pub default_key_map: Arc<Mutex<KeyMap>>,
     pub global_modes: Vec<Arc<Mutex<Mode>>>,
     pub display: Display,
+    /// The [`Job`]s running in the background.
+    ///
+    /// [`Job`]: struct.Job.html
+    pub jobs: Arc<Mutex<Jobs>>,
+    /// The [`Command`]s that can be run by name, such as from a batch
+    /// script.
+    ///
+    /// [`Command`]: trait.Command.html
+    pub commands: BTreeMap<String, Arc<dyn Command>>,
 }
 
 impl State {
@@ -27,14 +37,17 @@ impl State {
             default_key_map: Arc::new(Mutex::new(KeyMap::default())),
             global_modes: Vec::new(),
             display: Display::new(Arc::new(Mutex::new(Window::new())), Box::new(renderer)),
+            jobs: Arc::new(Mutex::new(Jobs::new())),
+            commands: BTreeMap::new(),
         }
     }
 
     /// This function looks up what [`Command`] an input is bound to.
     ///
-    /// First this looks up key bindings on each [`Mode`].  If one is
-    /// found, it immediately returns it.  If a mapping is not found
-    /// in a [`Mode`], the [`FallthroughBehavior`] is checked.
+    /// First this looks up key bindings in the [`Overlay`]s at the
+    /// cursor and then on each [`Mode`].  If one is found, it
+    /// immediately returns it.  If a mapping is not found in a
+    /// [`Mode`], the [`FallthroughBehavior`] is checked.
     ///
     /// For more information on how this function works, look at
     /// [`KeyMap::lookup`].
@@ -42,7 +55,27 @@ impl State {
     /// [`Command`]: type.Command.html
     /// [`KeyMap::lookup`]: struct.KeyMap.html#method.lookup
     /// [`Mode`]: struct.Mode.html
+    /// [`Overlay`]: struct.Overlay.html
     pub fn lookup(&self, inputs: &mut VecDeque<Input>) -> Result<Arc<Command>, Result<(), ()>> {
+        let overlay_key_maps: Vec<_> = {
+            let selected_window = self.display.selected_window();
+            let selected_window = selected_window.lock();
+            let buffer = selected_window.buffer.lock();
+            let cursor = selected_window.cursor.clone().updated(&buffer);
+            let key_maps = buffer
+                .overlays_at(cursor.get())
+                .filter_map(|overlay| overlay.key_map.clone())
+                .collect();
+            key_maps
+        };
+        for key_map in &overlay_key_maps {
+            match KeyMap::lookup(key_map, inputs, true) {
+                Ok(command) => return Ok(command),
+                Err(LookupError::NotEnoughInput) => return Err(Ok(())),
+                Err(LookupError::UnboundInput(_)) => (),
+                Err(LookupError::InputWasMapped) => return self.lookup(inputs),
+            }
+        }
         {
             let buffer = self.display.selected_window_buffer();
             let buffer = buffer.lock();
@@ -94,6 +127,13 @@ fn insert_key_behavior(input: Input) -> Result<Arc<Command>, Result<(), ()>> {
             Key::Key(c) if is_displayable(c) => {
                 return Ok(insert_command(c));
             }
+            Key::Paste => {
+                return Ok(insert_paste_command());
+            }
+            // The screen is redrawn after every command.
+            Key::Resize => {
+                return Ok(blank_command());
+            }
             _ => (),
         }
     }
@@ -109,6 +149,7 @@ fn is_displayable(c: char) -> bool {
 mod tests {
     use super::*;
     use debug_renderer::DebugRenderer;
+    use overlay::Overlay;
 
     #[test]
     fn lookup_basic() {
@@ -206,6 +247,44 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn lookup_overlay_key_map() {
+        let state = State::new(DebugRenderer::new());
+        let command_mode = blank_command();
+        let command_overlay = blank_command();
+        let mode = Mode::new();
+        mode.key_map
+            .lock()
+            .bind(vec![kbd("a")], command_mode.clone());
+        let key_map = Arc::new(Mutex::new(KeyMap::default()));
+        key_map.lock().bind(vec![kbd("a")], command_overlay.clone());
+        let window = state.display.selected_window();
+        {
+            let buffer = state.display.selected_window_buffer();
+            let mut buffer = buffer.lock();
+            buffer.insert_str(0, "abcd").unwrap();
+            buffer.buffer_modes.push(Arc::new(Mutex::new(mode)));
+            let mut overlay = Overlay::new(&buffer, 1..3);
+            overlay.key_map = Some(key_map);
+            buffer.overlays.push(overlay);
+        }
+        window.lock().set_cursor(0);
+        assert!(Arc::ptr_eq(
+            &state.lookup(&mut vec![kbd("a")].into()).unwrap(),
+            &command_mode
+        ));
+        window.lock().set_cursor(2);
+        assert!(Arc::ptr_eq(
+            &state.lookup(&mut vec![kbd("a")].into()).unwrap(),
+            &command_overlay
+        ));
+        window.lock().set_cursor(3);
+        assert!(Arc::ptr_eq(
+            &state.lookup(&mut vec![kbd("a")].into()).unwrap(),
+            &command_mode
+        ));
+    }
+
     #[test]
     fn lookup_get_insert() {
         let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
@@ -215,6 +294,24 @@ mod tests {
         assert_eq!(buffer.lock().to_string(), "a");
     }
 
+    #[test]
+    fn lookup_get_paste() {
+        let mut renderer = DebugRenderer::from(vec![kbd("Paste")]);
+        renderer.pasted_text = Some("fn main() {\n}".to_string());
+        let state = Arc::new(Mutex::new(State::new(renderer)));
+        let input = state.lock().display.getch().unwrap();
+        let paste = state.lock().lookup(&mut vec![input].into()).unwrap();
+        paste.execute(state.clone()).unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().to_string(), "fn main() {\n}");
+    }
+
+    #[test]
+    fn lookup_get_resize() {
+        let state = State::new(DebugRenderer::new());
+        assert!(state.lookup(&mut vec![kbd("Resize")].into()).is_ok());
+    }
+
     #[test]
     fn is_displayable_newline() {
         assert!(is_displayable('\n'));
@@ -0,0 +1,540 @@
+//! Helpers for testing ted with a [`DebugRenderer`].
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate parking_lot;
+//! # extern crate ted_core;
+//! # use parking_lot::Mutex;
+//! # use std::sync::Arc;
+//! # use ted_core::testing::*;
+//! # use ted_core::*;
+//! # fn main() {
+//! let state = Arc::new(Mutex::new(State::new(DebugRenderer::with_size(4, 10))));
+//! type_keys(&state, "abc Space x").unwrap();
+//! assert_eq!(
+//!     render_screen(&state).unwrap(),
+//!     "|abc x     |\n\
+//!      :     ^    :\n\
+//!      |          |\n\
+//!      |*scratch* |\n\
+//!      :==========:\n\
+//!      |          |\n"
+//! );
+//! # }
+//! ```
+//!
+//! [`DebugRenderer`]: ../struct.DebugRenderer.html
+
+use buffer::Buffer;
+use cursor::Cursor;
+use display_coordinator::{handle_input_event, InputEvent};
+use input::{try_kbd, Input, Key};
+use marker::Gravity;
+use parking_lot::Mutex;
+use state::State;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Parse a space separated sequence of keys.
+///
+/// Each word is parsed with [`try_kbd`].  Words that aren't keys are
+/// typed one character at a time.  So `"C-x 2 abc"` is `C-x`, `2`,
+/// `a`, `b` and `c`.
+///
+/// [`try_kbd`]: ../fn.try_kbd.html
+pub fn parse_keys(keys: &str) -> Vec<Input> {
+    let mut inputs = Vec::new();
+    for word in keys.split_whitespace() {
+        match try_kbd(word) {
+            Ok(input) => inputs.push(input),
+            Err(_) => inputs.extend(word.chars().map(|c| Input {
+                control: false,
+                alt: false,
+                key: Key::Key(c),
+            })),
+        }
+    }
+    inputs
+}
+
+/// Type the `keys` into the selected [`Frame`] and run the
+/// [`Command`]s they are bound to.
+///
+/// The `keys` are written as for [`parse_keys`].  It is an error if
+/// a [`Command`] fails or the last key sequence isn't finished.
+///
+/// [`Frame`]: ../struct.Frame.html
+/// [`Command`]: ../trait.Command.html
+/// [`parse_keys`]: fn.parse_keys.html
+pub fn type_keys(state: &Arc<Mutex<State>>, keys: &str) -> Result<(), String> {
+    let mut inputs = VecDeque::new();
+    for input in parse_keys(keys) {
+        let frame = state.lock().display.selected_frame.clone();
+        let event = InputEvent {
+            frame,
+            input,
+            mouse_position: None,
+            pasted_text: None,
+        };
+        handle_input_event(state, &mut inputs, event)?;
+    }
+    if inputs.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Error: {:?} is not a complete key sequence",
+            inputs
+        ))
+    }
+}
+
+/// Show the selected [`Frame`] and render it as text.
+///
+/// The [`Frame`] must be drawn by a [`DebugRenderer`].  See
+/// [`DebugRenderer::render`].
+///
+/// [`Frame`]: ../struct.Frame.html
+/// [`DebugRenderer`]: ../struct.DebugRenderer.html
+/// [`DebugRenderer::render`]: ../struct.DebugRenderer.html#method.render
+pub fn render_screen(state: &Mutex<State>) -> Result<String, String> {
+    let state = state.lock();
+    state.display.show()?;
+    let rendered = state.display.debug_renderer()?.render();
+    Ok(rendered)
+}
+
+/// The environment variable that makes [`assert_snapshot`] update
+/// the snapshots instead of comparing against them.
+///
+/// [`assert_snapshot`]: fn.assert_snapshot.html
+pub const UPDATE_SNAPSHOTS: &str = "TED_UPDATE_SNAPSHOTS";
+
+/// Assert that `actual` matches the snapshot called `name`.
+///
+/// Snapshots are stored in `snapshots/{name}.txt` in the crate being
+/// tested.  Run the tests with `TED_UPDATE_SNAPSHOTS=1` to write
+/// `actual` as the new snapshot instead.
+///
+/// # Panics
+///
+/// If the snapshot doesn't match or doesn't exist.
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from("snapshots").join(format!("{}.txt", name));
+    if std::env::var_os(UPDATE_SNAPSHOTS).is_some_and(|update| update != "0") {
+        fs::create_dir_all("snapshots").unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "Couldn't read snapshot {}: {}\nRun with {}=1 to create it.",
+            path.display(),
+            e,
+            UPDATE_SNAPSHOTS
+        )
+    });
+    assert!(
+        expected == actual,
+        "Snapshot {} doesn't match.  Run with {}=1 to update it.\nExpected:\n{}\nActual:\n{}",
+        path.display(),
+        UPDATE_SNAPSHOTS,
+        expected,
+        actual
+    );
+}
+
+/// Apply the edits encoded in `data` to a [`Buffer`] and to a simple
+/// model of it, panicking as soon as they disagree.
+///
+/// The bytes of `data` are read as a sequence of operations:
+/// inserting and deleting text (sometimes out of bounds), undoing,
+/// redoing, grouping changes and moving [`Cursor`]s.  After each operation the
+/// contents, [`len`] and a [`substring`] of the [`Buffer`] are
+/// checked, as is the location of two [`Cursor`]s and two
+/// [`Marker`]s.  One [`Cursor`] is updated after every operation and
+/// the other only when `data` asks for it, so it has to be moved
+/// across several changes at once.
+///
+/// This is used by the property tests and the fuzz target in
+/// `ted_core/fuzz`.  Both use the tiny chunks of text of the
+/// `small-chunks` feature.
+///
+/// [`Buffer`]: ../struct.Buffer.html
+/// [`Cursor`]: ../struct.Cursor.html
+/// [`Marker`]: ../struct.Marker.html
+/// [`len`]: ../struct.Buffer.html#method.len
+/// [`substring`]: ../struct.Buffer.html#method.substring
+pub fn check_buffer_edits(data: &[u8]) {
+    const CHARS: [char; 6] = ['a', 'b', ' ', '\n', 'β', '😀'];
+    let mut bytes = data.iter().map(|&b| b as usize);
+    let mut buffer = Buffer::new("*scratch*".into());
+    let mut model = BufferModel::default();
+    let mut cursors = [Cursor::new(), Cursor::new()];
+    let mut markers = [
+        buffer.add_marker(0, Gravity::Left),
+        buffer.add_marker(0, Gravity::Right),
+    ];
+    for cursor in &mut cursors {
+        cursor.update(&buffer);
+    }
+    // The state and location each cursor was last updated to.
+    let mut expected = [(0, 0), (0, 0)];
+    while let Some(op) = bytes.next() {
+        let len = model.contents.len();
+        match op % 10 {
+            0 => {
+                let loc = next(&mut bytes) % (len + 1);
+                let c = CHARS[next(&mut bytes) % CHARS.len()];
+                buffer.insert(loc, c).unwrap();
+                model.change(loc, vec![c], true);
+            }
+            1 => {
+                let loc = next(&mut bytes) % (len + 1);
+                let text: Vec<char> = (0..next(&mut bytes) % 16)
+                    .map(|_| CHARS[next(&mut bytes) % CHARS.len()])
+                    .collect();
+                let s: String = text.iter().collect();
+                buffer.insert_str(loc, &s).unwrap();
+                model.change(loc, text, true);
+            }
+            2 if len > 0 => {
+                let loc = next(&mut bytes) % len;
+                buffer.delete(loc).unwrap();
+                model.change(loc, vec![model.contents[loc]], false);
+            }
+            3 => {
+                let begin = next(&mut bytes) % (len + 1);
+                let end = begin + next(&mut bytes) % (len - begin + 1);
+                buffer.delete_region(begin, end).unwrap();
+                let text = model.contents[begin..end].to_vec();
+                model.change(begin, text, false);
+            }
+            4 => assert_eq!(buffer.undo().unwrap(), model.undo(), "undo"),
+            5 => assert_eq!(buffer.redo().unwrap(), model.redo(), "redo"),
+            6 => {
+                let loc = len + 1 + next(&mut bytes) % 4;
+                assert!(buffer.insert(loc, 'a').is_err());
+                assert!(buffer.insert_str(loc, "ab").is_err());
+                assert!(buffer.delete(len + next(&mut bytes) % 4).is_err());
+                assert!(buffer.delete_region(0, loc).is_err());
+                assert!(buffer.substring(loc, loc + 1).is_err());
+            }
+            7 => {
+                let cursor = next(&mut bytes) % 2;
+                let loc = next(&mut bytes) % (len + 2);
+                cursors[cursor].set(&buffer, loc);
+                expected[cursor] = (model.current, loc.min(len));
+                buffer.set_marker(&mut markers[cursor], loc).unwrap();
+                model.markers[cursor].1 = loc.min(len);
+            }
+            8 => {
+                cursors[1].update(&buffer);
+                expected[1] = model.moved(expected[1], model.current);
+            }
+            _ => {
+                if model.group.is_some() {
+                    buffer.end_undo_group();
+                    model.group = None;
+                } else {
+                    buffer.begin_undo_group();
+                    model.group = Some(false);
+                }
+            }
+        }
+
+        cursors[0].update(&buffer);
+        expected[0] = model.moved(expected[0], model.current);
+
+        let contents: String = model.contents.iter().collect();
+        assert_eq!(buffer.to_string(), contents);
+        assert_eq!(buffer.len(), model.contents.len());
+        let len = model.contents.len();
+        let begin = next(&mut bytes) % (len + 1);
+        let end = begin + next(&mut bytes) % (len - begin + 1);
+        let substring: String = model.contents[begin..end].iter().collect();
+        assert_eq!(buffer.substring(begin, end).unwrap(), substring);
+        assert_eq!(cursors[0].get(), expected[0].1, "updated cursor");
+        if cursors[1].is_updated(&buffer) {
+            assert_eq!(cursors[1].get(), expected[1].1, "lazy cursor");
+        }
+        for (marker, &(_, location)) in markers.iter().zip(&model.markers) {
+            assert_eq!(marker.get(), location, "{:?} marker", marker.gravity());
+        }
+    }
+}
+
+fn next<I: Iterator<Item = usize>>(bytes: &mut I) -> usize {
+    bytes.next().unwrap_or(0)
+}
+
+/// A change in a [`BufferModel`].
+struct ModelChange {
+    loc: usize,
+    text: Vec<char>,
+    is_insert: bool,
+}
+
+impl ModelChange {
+    /// Move `location` as if this change was done or undone.
+    fn move_location(&self, location: usize, is_insert: bool) -> usize {
+        let len = self.text.len();
+        if is_insert {
+            if location >= self.loc {
+                location + len
+            } else {
+                location
+            }
+        } else if location > self.loc + len {
+            location - len
+        } else if location > self.loc {
+            self.loc
+        } else {
+            location
+        }
+    }
+}
+
+/// Insert or delete the text of `change` in `contents` and move the
+/// `markers` along with it.
+fn apply_change(
+    contents: &mut Vec<char>,
+    markers: &mut [(Gravity, usize)],
+    change: &ModelChange,
+    is_insert: bool,
+) {
+    let len = change.text.len();
+    let end = change.loc + len;
+    if is_insert {
+        let tail = contents.split_off(change.loc);
+        contents.extend(&change.text);
+        contents.extend(tail);
+        for &mut (gravity, ref mut location) in markers {
+            if *location > change.loc || (gravity == Gravity::Right && *location == change.loc) {
+                *location += len;
+            }
+        }
+    } else {
+        assert_eq!(&contents[change.loc..end], &change.text[..]);
+        contents.drain(change.loc..end);
+        for &mut (_, ref mut location) in markers {
+            if *location > end {
+                *location -= len;
+            } else if *location > change.loc {
+                *location = change.loc;
+            }
+        }
+    }
+}
+
+/// A node in the undo tree of a [`BufferModel`].
+struct ModelNode {
+    pred: Option<usize>,
+    succ: Vec<usize>,
+    change: ModelChange,
+    /// Whether the change was made in the same undo group as `pred`.
+    joined: bool,
+}
+
+/// The contents and undo tree of a [`Buffer`], stored as simply as
+/// possible.
+///
+/// [`Buffer`]: ../struct.Buffer.html
+struct BufferModel {
+    contents: Vec<char>,
+    markers: Vec<(Gravity, usize)>,
+    nodes: Vec<ModelNode>,
+    current: usize,
+    /// Whether an undo group is open and has a change in it.
+    group: Option<bool>,
+}
+
+impl Default for BufferModel {
+    fn default() -> Self {
+        BufferModel {
+            contents: Vec::new(),
+            markers: vec![(Gravity::Left, 0), (Gravity::Right, 0)],
+            nodes: vec![ModelNode {
+                pred: None,
+                succ: Vec::new(),
+                change: ModelChange {
+                    loc: 0,
+                    text: Vec::new(),
+                    is_insert: true,
+                },
+                joined: false,
+            }],
+            current: 0,
+            group: None,
+        }
+    }
+}
+
+impl BufferModel {
+    fn change(&mut self, loc: usize, text: Vec<char>, is_insert: bool) {
+        let change = ModelChange {
+            loc,
+            text,
+            is_insert,
+        };
+        apply_change(&mut self.contents, &mut self.markers, &change, is_insert);
+        let node = self.nodes.len();
+        self.nodes.push(ModelNode {
+            pred: Some(self.current),
+            succ: Vec::new(),
+            change,
+            joined: self.group == Some(true),
+        });
+        if self.group.is_some() {
+            self.group = Some(true);
+        }
+        self.nodes[self.current].succ.push(node);
+        self.current = node;
+    }
+
+    fn undo(&mut self) -> bool {
+        let joined = self.nodes[self.current].joined;
+        let undone = self.undo_change();
+        if joined {
+            self.undo();
+        }
+        undone
+    }
+
+    fn undo_change(&mut self) -> bool {
+        let node = &self.nodes[self.current];
+        match node.pred {
+            Some(pred) => {
+                apply_change(
+                    &mut self.contents,
+                    &mut self.markers,
+                    &node.change,
+                    !node.change.is_insert,
+                );
+                self.current = pred;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn redo(&mut self) -> bool {
+        let redone = self.redo_change();
+        let is_next_joined = |model: &BufferModel| match model.nodes[model.current].succ.last() {
+            Some(&succ) => model.nodes[succ].joined,
+            None => false,
+        };
+        while redone && is_next_joined(self) {
+            self.redo_change();
+        }
+        redone
+    }
+
+    fn redo_change(&mut self) -> bool {
+        match self.nodes[self.current].succ.last() {
+            Some(&succ) => {
+                let change = &self.nodes[succ].change;
+                apply_change(
+                    &mut self.contents,
+                    &mut self.markers,
+                    change,
+                    change.is_insert,
+                );
+                self.current = succ;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Move a location from the state `from` to the state `to` along
+    /// the path between them in the undo tree.
+    fn moved(&self, (from, location): (usize, usize), to: usize) -> (usize, usize) {
+        let ancestors = |mut node: usize| {
+            let mut ancestors = vec![node];
+            while let Some(pred) = self.nodes[node].pred {
+                ancestors.push(pred);
+                node = pred;
+            }
+            ancestors
+        };
+        let up = ancestors(from);
+        let mut down = ancestors(to);
+        while down.len() > 1 && up.contains(&down[down.len() - 2]) {
+            down.pop();
+        }
+        let common = *down.last().unwrap();
+        let mut location = location;
+        for &node in up.iter().take_while(|&&node| node != common) {
+            let change = &self.nodes[node].change;
+            location = change.move_location(location, !change.is_insert);
+        }
+        for &node in down.iter().rev().skip(1) {
+            let change = &self.nodes[node].change;
+            location = change.move_location(location, change.is_insert);
+        }
+        (to, location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use debug_renderer::DebugRenderer;
+    use input::kbd;
+
+    #[test]
+    fn parse_keys_types_words() {
+        assert_eq!(
+            parse_keys("C-x 2  abc F1 Return"),
+            vec![
+                kbd("C-x"),
+                kbd("2"),
+                kbd("a"),
+                kbd("b"),
+                kbd("c"),
+                kbd("F1"),
+                kbd("\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn type_keys_incomplete_sequence() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let quit: fn(Arc<Mutex<State>>) -> Result<(), String> = |_| Err("quit".to_string());
+        state.lock().default_key_map.lock().bind(
+            vec![kbd("C-x"), kbd("C-c")],
+            ::command::function_command(quit),
+        );
+        assert!(type_keys(&state, "a C-x").is_err());
+        assert_eq!(type_keys(&state, "C-x C-c"), Err("quit".to_string()));
+    }
+
+    #[test]
+    fn buffer_edits_match_model() {
+        // A xorshift generator so failures can be reproduced from the
+        // seed.
+        for seed in 1..500u64 {
+            let mut x = seed;
+            let data: Vec<u8> = (0..300)
+                .map(|_| {
+                    x ^= x << 13;
+                    x ^= x >> 7;
+                    x ^= x << 17;
+                    x as u8
+                })
+                .collect();
+            check_buffer_edits(&data);
+        }
+    }
+
+    #[test]
+    fn snapshot_typed_lines() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::with_size(6, 16))));
+        type_keys(&state, "first Return second Return Tab third").unwrap();
+        assert_snapshot("typed_lines", &render_screen(&state).unwrap());
+    }
+}
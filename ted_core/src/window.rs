@@ -1,11 +1,42 @@
 use buffer::*;
 use cursor::Cursor;
+use display_options::DisplayOptions;
 use key_map::KeyMap;
 use layout::Layout;
+use marker::{Gravity, Marker};
 use mode::Mode;
 use parking_lot::Mutex;
 use std::sync::Arc;
 
+/// The column that moving up and down tries to keep the cursor at.
+///
+/// Moving through a short line moves the cursor left, but the next
+/// vertical movement should go back to the original column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GoalColumn {
+    pub column: usize,
+    /// The cursor location after the last vertical movement.  The
+    /// goal column is forgotten once the cursor moves anywhere else.
+    pub location: usize,
+}
+
+/// A cursor in a [`Window`] other than its main [`cursor`].
+///
+/// Its location is a [`Marker`] so it follows edits made at the other
+/// cursors without walking the undo history.
+///
+/// See [`Window::at_each_cursor`].
+///
+/// [`Window`]: struct.Window.html
+/// [`cursor`]: struct.Window.html#structfield.cursor
+/// [`Marker`]: struct.Marker.html
+/// [`Window::at_each_cursor`]: struct.Window.html#method.at_each_cursor
+#[derive(Clone, Debug)]
+pub struct SecondaryCursor {
+    pub marker: Marker,
+    pub goal_column: Option<GoalColumn>,
+}
+
 /// A view into a specific [`Buffer`]
 ///
 /// [`Buffer`]: struct.Buffer.html
@@ -13,7 +44,15 @@ pub struct Window {
     pub buffer: Arc<Mutex<Buffer>>,
     pub buffer_key_map: Arc<Mutex<KeyMap>>,
     pub cursor: Cursor,
+    /// The other places that commands act at.
+    pub cursors: Vec<SecondaryCursor>,
+    /// The location of the first character displayed in the `Window`.
+    pub start: usize,
     pub window_modes: Vec<Arc<Mutex<Mode>>>,
+    pub display_options: DisplayOptions,
+    /// The number of columns scrolled horizontally when truncating lines.
+    pub hscroll: usize,
+    pub goal_column: Option<GoalColumn>,
 }
 
 impl Window {
@@ -42,6 +81,131 @@ impl Window {
         self.cursor.update(&*buffer);
     }
 
+    /// Leave a [`SecondaryCursor`] where the cursor is.
+    ///
+    /// [`SecondaryCursor`]: struct.SecondaryCursor.html
+    pub fn push_cursor(&mut self) {
+        self.update_cursor();
+        let marker = self
+            .buffer
+            .lock()
+            .add_marker(self.cursor.get(), Gravity::Right);
+        self.cursors.push(SecondaryCursor {
+            marker,
+            goal_column: self.goal_column,
+        });
+    }
+
+    /// Add a [`SecondaryCursor`] where the cursor is and then move
+    /// the cursor to `location`.
+    ///
+    /// [`SecondaryCursor`]: struct.SecondaryCursor.html
+    pub fn add_cursor(&mut self, location: usize) {
+        self.push_cursor();
+        self.set_cursor(location);
+        self.merge_cursors();
+    }
+
+    /// Run `f` at each cursor of the `Window`.
+    ///
+    /// `f` is run at each of the [`cursors`] in turn by moving the main
+    /// [`cursor`] there, and then at the main [`cursor`].  The
+    /// changes `f` makes to the [`Buffer`] are undone together.
+    /// Afterwards cursors that ended up at the same location are
+    /// merged.
+    ///
+    /// Stops at the first error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ted_core::*;
+    /// let mut window = Window::new();
+    /// window.insert_str("ab\ncd").unwrap();
+    /// window.set_cursor(0);
+    /// window.add_cursor(3);
+    /// window.at_each_cursor(|window| window.insert('>')).unwrap();
+    /// assert_eq!(window.buffer.lock().to_string(), ">ab\n>cd");
+    /// assert_eq!(window.cursor.get(), 5);
+    /// assert_eq!(window.cursors[0].marker.get(), 1);
+    ///
+    /// window.buffer.lock().undo().unwrap();
+    /// assert_eq!(window.buffer.lock().to_string(), "ab\ncd");
+    /// ```
+    ///
+    /// [`cursors`]: #structfield.cursors
+    /// [`cursor`]: #structfield.cursor
+    /// [`Buffer`]: struct.Buffer.html
+    pub fn at_each_cursor<F>(&mut self, mut f: F) -> Result<(), String>
+    where
+        F: FnMut(&mut Window) -> Result<(), String>,
+    {
+        if self.cursors.is_empty() {
+            return f(self);
+        }
+        self.buffer.lock().begin_undo_group();
+        self.update_cursor();
+        let main = SecondaryCursor {
+            marker: self
+                .buffer
+                .lock()
+                .add_marker(self.cursor.get(), Gravity::Right),
+            goal_column: self.goal_column,
+        };
+        let mut cursors = std::mem::take(&mut self.cursors);
+        let mut result = Ok(());
+        for secondary in &mut cursors {
+            self.enter_cursor(secondary);
+            result = f(self);
+            self.leave_cursor(secondary);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.enter_cursor(&main);
+        if result.is_ok() {
+            result = f(self);
+        }
+        self.cursors = cursors;
+        self.buffer.lock().end_undo_group();
+        self.update_cursor();
+        self.merge_cursors();
+        result
+    }
+
+    /// Move the main cursor to `secondary`.
+    fn enter_cursor(&mut self, secondary: &SecondaryCursor) {
+        self.set_cursor(secondary.marker.get());
+        self.goal_column = secondary.goal_column;
+    }
+
+    /// Move `secondary` to the main cursor.
+    fn leave_cursor(&mut self, secondary: &mut SecondaryCursor) {
+        self.update_cursor();
+        let buffer = self.buffer.lock();
+        buffer
+            .set_marker(&mut secondary.marker, self.cursor.get())
+            .unwrap();
+        secondary.goal_column = self.goal_column;
+    }
+
+    /// Remove [`SecondaryCursor`]s that are at the same location as
+    /// another cursor.
+    ///
+    /// [`SecondaryCursor`]: struct.SecondaryCursor.html
+    pub fn merge_cursors(&mut self) {
+        let mut locations = vec![self.cursor.get()];
+        self.cursors.retain(|secondary| {
+            let location = secondary.marker.get();
+            if locations.contains(&location) {
+                false
+            } else {
+                locations.push(location);
+                true
+            }
+        });
+    }
+
     /// Insert a char `c` at the cursor.
     ///
     /// This will automatically invoke [`update_cursor`].
@@ -113,7 +277,12 @@ impl From<Arc<Mutex<Buffer>>> for Window {
             buffer: buffer,
             buffer_key_map: Arc::default(),
             cursor: Cursor::new(),
+            cursors: Vec::new(),
+            start: 0,
             window_modes: Vec::new(),
+            display_options: DisplayOptions::default(),
+            hscroll: 0,
+            goal_column: None,
         }
     }
 }
@@ -146,7 +315,12 @@ pub fn clone_window(window: &Arc<Mutex<Window>>) -> Arc<Mutex<Window>> {
         buffer: window.buffer.clone(),
         buffer_key_map: window.buffer_key_map.clone(),
         cursor: window.cursor.clone(),
+        cursors: window.cursors.clone(),
+        start: window.start,
         window_modes: Vec::new(),
+        display_options: window.display_options,
+        hscroll: window.hscroll,
+        goal_column: window.goal_column,
     }));
     cloned
 }
@@ -196,4 +370,28 @@ mod tests {
         window.set_cursor(100);
         assert_eq!(window.cursor.get(), 3);
     }
+
+    #[test]
+    fn secondary_cursors_follow_edits() {
+        let mut window = Window::new();
+        window.insert_str("ab\ncd").unwrap();
+        window.set_cursor(4);
+        window.add_cursor(0);
+        window.insert_str("xy").unwrap();
+        assert_eq!(window.cursors[0].marker.get(), 6);
+        window.buffer.lock().undo().unwrap();
+        assert_eq!(window.cursors[0].marker.get(), 4);
+        window.buffer.lock().delete_region(3, 5).unwrap();
+        assert_eq!(window.cursors[0].marker.get(), 3);
+    }
+
+    #[test]
+    fn window_replace_contents_keeps_cursor() {
+        let mut window = Window::new();
+        window.insert_str("a\nb\nc").unwrap();
+        window.set_cursor(4);
+        window.buffer.lock().replace_contents("a\nB\nX\nc").unwrap();
+        window.update_cursor();
+        assert_eq!(window.cursor.get(), 6);
+    }
 }
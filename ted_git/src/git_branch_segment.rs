@@ -0,0 +1,100 @@
+use git2::Repository;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use ted_core::*;
+
+/// How long a cached branch is shown before it is looked up again.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Show the branch of the git repository containing the [`Buffer`]'s
+/// file.
+///
+/// The segment is left out if the [`Buffer`] has no file or the file
+/// isn't in a git repository.
+///
+/// Looking up the branch touches the file system, so it is done on a
+/// background thread and cached per path.  Drawing only reads the
+/// cache, and each entry is refreshed when it is two seconds old.
+///
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+#[derive(Debug, Default)]
+pub struct GitBranchSegment {
+    branches: Arc<Mutex<HashMap<PathBuf, CachedBranch>>>,
+}
+
+#[derive(Debug)]
+struct CachedBranch {
+    branch: Option<String>,
+    /// When the branch was last looked up, or `None` if it hasn't been.
+    updated: Option<Instant>,
+    is_refreshing: bool,
+}
+
+/// Construct a [`GitBranchSegment`].
+///
+/// [`GitBranchSegment`]: struct.GitBranchSegment.html
+pub fn git_branch_segment() -> Arc<GitBranchSegment> {
+    Arc::new(GitBranchSegment::default())
+}
+
+fn find_branch(path: &Path) -> Option<String> {
+    let repository = Repository::discover(path).ok()?;
+    let head = repository.head().ok()?;
+    head.shorthand().map(|branch| branch.to_string())
+}
+
+impl GitBranchSegment {
+    fn refresh(&self, path: &Path) {
+        let branches = self.branches.clone();
+        let path_buf = path.to_path_buf();
+        let result = thread::Builder::new()
+            .name("ted git branch".to_string())
+            .spawn(move || {
+                let branch = find_branch(&path_buf);
+                branches.lock().insert(
+                    path_buf,
+                    CachedBranch {
+                        branch,
+                        updated: Some(Instant::now()),
+                        is_refreshing: false,
+                    },
+                );
+            });
+        // Try again next time if the thread couldn't be started.
+        if result.is_err() {
+            if let Some(cached) = self.branches.lock().get_mut(path) {
+                cached.is_refreshing = false;
+            }
+        }
+    }
+}
+
+impl ModeLineSegment for GitBranchSegment {
+    fn text(&self, _: &Window, buffer: &Buffer) -> Option<String> {
+        let path = buffer.name.path.as_ref()?;
+        let (branch, is_stale) = {
+            let mut branches = self.branches.lock();
+            let cached = branches.entry(path.clone()).or_insert(CachedBranch {
+                branch: None,
+                updated: None,
+                is_refreshing: false,
+            });
+            let is_stale = !cached.is_refreshing
+                && cached
+                    .updated
+                    .map_or(true, |updated| updated.elapsed() >= REFRESH_INTERVAL);
+            if is_stale {
+                cached.is_refreshing = true;
+            }
+            (cached.branch.clone(), is_stale)
+        };
+        if is_stale {
+            self.refresh(path);
+        }
+        branch.map(|branch| format!("git:{}", branch))
+    }
+}
@@ -1,41 +1,22 @@
 use git_mode::check_if_in_git_mode;
+use git_repository::GitFile;
 use std::path::*;
-use ted_common_commands::*;
 use ted_core::*;
 
 pub fn check<O, E: ToString>(r: Result<O, E>) -> Result<O, String> {
     r.map_err(|e| e.to_string())
 }
 
-pub fn get_highlighted_file(buffer: &Buffer, mut cursor: usize) -> Result<(PathBuf, bool), String> {
-    let initial_cursor = cursor;
+/// Get the path of the file at `cursor` in a status buffer and
+/// whether it is staged.
+pub fn get_highlighted_file(buffer: &Buffer, cursor: usize) -> Result<(PathBuf, bool), String> {
     check_if_in_git_mode(&buffer)?;
-    cursor = begin_of_line(&buffer, cursor);
-    {
-        let prev_line = forward_line(&buffer, cursor, -1);
-        if prev_line == cursor {
-            Err(ERROR_NO_FILE_HIGHLIGHTED)?;
-        }
-        cursor = prev_line;
-    }
-    let is_staged = loop {
-        let substring = buffer.substring(cursor, end_of_line(&buffer, cursor))?;
-        if substring == "Staged files:" {
-            break true;
-        }
-        if substring == "Unstaged files:" {
-            break false;
-        }
-        if cursor == 0 {
-            Err(ERROR_NO_FILE_HIGHLIGHTED)?;
-        }
-        cursor = forward_line(&buffer, cursor, -1);
-    };
-    let path = PathBuf::from(buffer.substring(
-        begin_of_line(&buffer, initial_cursor) + 2,
-        end_of_line(&buffer, initial_cursor),
-    )?);
-    Ok((path, is_staged))
+    let file = buffer
+        .overlays_at(cursor)
+        .filter_map(|overlay| overlay.data::<GitFile>())
+        .next()
+        .ok_or(ERROR_NO_FILE_HIGHLIGHTED)?;
+    Ok((file.path.clone(), file.is_staged))
 }
 
 pub fn get_highlighted_file_staged(buffer: &Buffer, cursor: usize) -> Result<PathBuf, String> {
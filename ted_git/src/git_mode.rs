@@ -53,7 +53,8 @@ pub fn toggle_git_mode(buffer: &mut Buffer) {
 }
 
 fn create_git_mode() -> Arc<Mutex<Mode>> {
-    let mode = Mode::new();
+    let mut mode = Mode::new();
+    mode.name = "Git".to_string();
     {
         let mut key_map = mode.key_map.lock();
         key_map.bind(vec![kbd("g")], git_refresh_repository_command());
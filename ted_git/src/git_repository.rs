@@ -1,7 +1,9 @@
 use git2::*;
 use git_common::*;
+use git_diff::git_diff_command;
 use git_mode::*;
 use parking_lot::Mutex;
+use std::ops::Range;
 use std::path::*;
 use std::sync::Arc;
 use ted_core::*;
@@ -53,20 +55,81 @@ pub fn git_refresh_repository_command() -> Arc<GitRefreshRepositoryCommand> {
 impl Command for GitRefreshRepositoryCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let buffer = state.lock().display.selected_window_buffer();
-        let mut buffer = buffer.lock();
-        let repository_path = buffer.name.path.clone().ok_or(ERROR_FILE_PATH_NONE)?;
-        git_refresh_repository(&repository_path, &mut buffer)?;
+        let repository_path = buffer
+            .lock()
+            .name
+            .path
+            .clone()
+            .ok_or(ERROR_FILE_PATH_NONE)?;
+        // Reading the status of a large repository is slow so do it
+        // in the background.
+        spawn_job(
+            &state,
+            "git status",
+            move |_| git_repository_status(&repository_path),
+            move |_, (name, contents, files)| {
+                replace_buffer_contents(&buffer, &contents)?;
+                let mut buffer = buffer.lock();
+                buffer.name = name;
+                buffer.read_only = true;
+                add_file_overlays(&mut buffer, files);
+                Ok(())
+            },
+        )?;
         Ok(())
     }
 }
 
 pub fn git_refresh_repository(repository_path: &Path, buffer: &mut Buffer) -> Result<(), String> {
+    let (name, contents, files) = git_repository_status(repository_path)?;
     buffer.read_only = false;
-    buffer.buffer_modes.push(git_mode());
+    turn_on_git_mode(buffer);
+    buffer.name = name;
+    buffer.clear()?;
+    buffer.insert_str(0, &contents)?;
+    buffer.erase_history();
+    buffer.read_only = true;
+    add_file_overlays(buffer, files);
+    Ok(())
+}
+
+/// A file listed in the status buffer of a repository.
+///
+/// This is the [`data`] of the [`Overlay`] over the line of the file.
+///
+/// [`data`]: ../ted_core/struct.Overlay.html#structfield.data
+/// [`Overlay`]: ../ted_core/struct.Overlay.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct GitFile {
+    pub path: PathBuf,
+    pub is_staged: bool,
+}
+
+/// Replace the [`Overlay`]s of a status buffer with one for the line
+/// of each of the `files`.  Clicking on a file shows its diff.
+///
+/// [`Overlay`]: ../ted_core/struct.Overlay.html
+fn add_file_overlays(buffer: &mut Buffer, files: Vec<(Range<usize>, GitFile)>) {
+    buffer.overlays.clear();
+    for (range, file) in files {
+        let mut overlay = Overlay::new(buffer, range);
+        overlay.action = Some(git_diff_command());
+        overlay.data = Some(Arc::new(file));
+        buffer.overlays.push(overlay);
+    }
+}
+
+/// Get the name and contents of the status buffer of the repository
+/// containing `repository_path`.
+///
+/// The files listed are returned along with the range of their lines.
+pub fn git_repository_status(
+    repository_path: &Path,
+) -> Result<(BufferName, String, Vec<(Range<usize>, GitFile)>), String> {
     let mut buf = String::new();
     let repo = check(Repository::discover(repository_path))?;
     let workdir = repo.workdir().ok_or(ERROR_REPOSITORY_WORKDIR_NONE)?;
-    buffer.name = BufferName {
+    let name = BufferName {
         name: format!(
             "*git* {}",
             workdir
@@ -81,6 +144,7 @@ pub fn git_refresh_repository(repository_path: &Path, buffer: &mut Buffer) -> Re
     buf.push_str(": ");
     buf.push_str(&format!("{:?}", repo.state()));
     buf.push('\n');
+    let mut files = Vec::new();
     let statuses = check(repo.statuses(None))?;
     if !statuses.is_empty() {
         let mut staged = Vec::new();
@@ -112,6 +176,7 @@ pub fn git_refresh_repository(repository_path: &Path, buffer: &mut Buffer) -> Re
         if !staged.is_empty() {
             buf.push_str("\nStaged files:\n");
         }
+        let mut location = buf.chars().count();
         for (file, stat) in staged {
             if stat.is_index_new() {
                 buf.push('N');
@@ -129,11 +194,22 @@ pub fn git_refresh_repository(repository_path: &Path, buffer: &mut Buffer) -> Re
             buf.push(' ');
             buf.push_str(&file);
             buf.push('\n');
+            let start = location;
+            // The status, a space, the path and the newline.
+            location += file.chars().count() + 3;
+            files.push((
+                start..location,
+                GitFile {
+                    path: PathBuf::from(&*file),
+                    is_staged: true,
+                },
+            ));
         }
 
         if !unstaged.is_empty() {
             buf.push_str("\nUnstaged files:\n");
         }
+        let mut location = buf.chars().count();
         for (file, stat) in unstaged {
             if stat.is_wt_new() {
                 buf.push('N');
@@ -151,11 +227,16 @@ pub fn git_refresh_repository(repository_path: &Path, buffer: &mut Buffer) -> Re
             buf.push(' ');
             buf.push_str(&file);
             buf.push('\n');
+            let start = location;
+            location += file.chars().count() + 3;
+            files.push((
+                start..location,
+                GitFile {
+                    path: PathBuf::from(&*file),
+                    is_staged: false,
+                },
+            ));
         }
     }
-    buffer.clear()?;
-    buffer.insert_str(0, &buf)?;
-    buffer.erase_history();
-    buffer.read_only = true;
-    Ok(())
+    Ok((name, buf, files))
 }
@@ -5,6 +5,9 @@ extern crate parking_lot;
 extern crate ted_common_commands;
 extern crate ted_core;
 
+mod git_branch_segment;
+pub use git_branch_segment::*;
+
 mod git_common;
 
 mod git_diff;
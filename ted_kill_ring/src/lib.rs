@@ -57,7 +57,7 @@ pub fn kill_region(window: &Window) -> Result<(), String> {
     let substring = {
         let mut buffer = window.buffer.lock();
         let (region, substring) = substring_region(window, &buffer);
-        buffer.delete_region(region.start.get(), region.end.get())?;
+        buffer.delete_region(region.start, region.end)?;
         substring
     };
     insert_kill(window, substring);
@@ -77,6 +77,17 @@ pub fn paste(window: &Window) -> Result<(), String> {
     Ok(())
 }
 
+/// Get the text that [`paste`] would insert in `window`.
+///
+/// [`paste`]: fn.paste.html
+pub fn current_kill(window: &Window) -> Option<String> {
+    let kills = KILLS.lock();
+    kills
+        .get(&W(window))
+        .and_then(|kill_ring| kill_ring.ring.get(kill_ring.pos))
+        .cloned()
+}
+
 pub fn paste_pop(window: &Window, times: isize) -> Result<(), String> {
     fn modulus(a: isize, b: isize) -> isize {
         ((a % b) + b) % b
@@ -170,6 +181,32 @@ impl Command for PastePopCommand {
     }
 }
 
+/// Insert the text that [`PasteCommand`] would insert on every line
+/// of the rectangle in the selected
+/// [`Window`](../ted_core/struct.Window.html).
+///
+/// [`PasteCommand`]: struct.PasteCommand.html
+#[derive(Debug)]
+pub struct StringInsertRectangleCommand;
+
+/// Construct a [`StringInsertRectangleCommand`].
+///
+/// [`StringInsertRectangleCommand`]: struct.StringInsertRectangleCommand.html
+pub fn string_insert_rectangle_command() -> Arc<StringInsertRectangleCommand> {
+    Arc::new(StringInsertRectangleCommand)
+}
+
+impl Command for StringInsertRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let string = current_kill(&selected_window).ok_or("Error: The kill ring is empty")?;
+        string_insert_rectangle(&selected_window, &string)?;
+        selected_window.update_cursor();
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +247,28 @@ mod tests {
         assert_eq!(window.cursor.get(), 3);
     }
 
+    #[test]
+    fn string_insert_rectangle_from_kill_ring() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let selected_window = state.lock().display.selected_window();
+        {
+            let mut selected_window = selected_window.lock();
+            selected_window.insert_str("ab\ncd").unwrap();
+            set_mark(&selected_window, 1);
+        }
+        assert_eq!(
+            string_insert_rectangle_command().execute(state.clone()),
+            Err("Error: The kill ring is empty".to_string())
+        );
+        insert_kill(&selected_window.lock(), "|".to_string());
+        string_insert_rectangle_command()
+            .execute(state.clone())
+            .unwrap();
+        let selected_window = selected_window.lock();
+        assert_eq!(selected_window.buffer.lock().to_string(), "a|b\nc|d");
+        remove_mark(&selected_window);
+    }
+
     #[test]
     fn paste_empty_string() {
         let mut window = Window::new();
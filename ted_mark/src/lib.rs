@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate lazy_static;
 extern crate parking_lot;
+extern crate ted_common_commands;
 extern crate ted_core;
 
 use parking_lot::Mutex;
@@ -9,13 +10,16 @@ use std::ops::Range;
 use std::sync::Arc;
 use ted_core::*;
 
+mod rectangle;
+pub use rectangle::*;
+
 #[derive(PartialEq, Eq, Hash)]
 struct W(*const Window);
 unsafe impl Send for W {}
 unsafe impl Sync for W {}
 
 lazy_static! {
-    static ref MARKS: Mutex<HashMap<W, Cursor>> = Mutex::new(HashMap::new());
+    static ref MARKS: Mutex<HashMap<W, Marker>> = Mutex::new(HashMap::new());
 }
 
 pub fn is_mark_active(window: &Window) -> bool {
@@ -23,39 +27,48 @@ pub fn is_mark_active(window: &Window) -> bool {
     marks.contains_key(&W(window))
 }
 
-pub fn get_mark(window: &Window) -> Option<Cursor> {
+pub fn get_mark(window: &Window) -> Option<usize> {
     let marks = MARKS.lock();
-    marks.get(&W(window)).cloned()
+    marks.get(&W(window)).map(Marker::get)
 }
 
-pub fn get_region(window: &Window) -> Range<Cursor> {
+/// Get the region between the mark and the cursor of `window`.
+///
+/// `buffer` is the locked [`Buffer`] of `window`.
+///
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn get_region(window: &Window, buffer: &Buffer) -> Range<usize> {
     debug_assert!(is_mark_active(window));
+    let cursor = window.cursor.clone().updated(buffer).get();
     match get_mark(window) {
         Some(mark) => {
-            let cursor = window.cursor.clone();
-            if mark.get() < cursor.get() {
+            if mark < cursor {
                 mark..cursor
             } else {
                 cursor..mark
             }
         }
-        None => window.cursor.clone()..window.cursor.clone(),
+        None => cursor..cursor,
     }
 }
 
-pub fn substring_region(window: &Window, buffer: &Buffer) -> (Range<Cursor>, String) {
-    let mut region = get_region(window);
-    region.start.update(&buffer);
-    region.end.update(&buffer);
-    let substring = buffer
-        .substring(region.start.get(), region.end.get())
-        .unwrap();
+pub fn substring_region(window: &Window, buffer: &Buffer) -> (Range<usize>, String) {
+    let region = get_region(window, buffer);
+    let substring = buffer.substring(region.start, region.end).unwrap();
     (region, substring)
 }
 
-pub fn set_mark(window: &Window, cursor: Cursor) {
+/// Set the mark of `window` at `location`.
+///
+/// The mark is a [`Marker`] so it moves with edits to the [`Buffer`].
+/// Text inserted at the mark goes after it.
+///
+/// [`Marker`]: ../ted_core/struct.Marker.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn set_mark(window: &Window, location: usize) {
+    let marker = window.buffer.lock().add_marker(location, Gravity::Left);
     let mut marks = MARKS.lock();
-    marks.insert(W(window), cursor);
+    marks.insert(W(window), marker);
 }
 
 pub fn remove_mark(window: &Window) {
@@ -77,7 +90,7 @@ impl Command for SetMarkCommand {
     fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
         let selected_window = state.lock().display.selected_window();
         let selected_window = selected_window.lock();
-        set_mark(&selected_window, selected_window.cursor.clone());
+        set_mark(&selected_window, selected_window.cursor.get());
         Ok(())
     }
 }
@@ -101,16 +114,81 @@ impl Command for RemoveMarkCommand {
     }
 }
 
+/// Remove the mark, then select the [`Window`] under the mouse and
+/// move its cursor to the mouse.
+///
+/// If the text under the mouse is in an [`Overlay`] with an action,
+/// it is run.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Overlay`]: ../ted_core/struct.Overlay.html
+#[derive(Debug)]
+pub struct MousePressCommand;
+
+/// Construct a [`MousePressCommand`].
+///
+/// [`MousePressCommand`]: struct.MousePressCommand.html
+pub fn mouse_press_command() -> Arc<MousePressCommand> {
+    Arc::new(MousePressCommand)
+}
+
+impl Command for MousePressCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let window = selected_frame.lock().select_at_mouse();
+        match window {
+            Some(window) => {
+                remove_mark(&window.lock());
+                run_overlay_action(&state, &window)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Extend the region to the mouse.
+///
+/// If the mark isn't active, it is set at the cursor (where the drag
+/// started).  Dragging outside the selected [`Window`] does nothing.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct MouseDragCommand;
+
+/// Construct a [`MouseDragCommand`].
+///
+/// [`MouseDragCommand`]: struct.MouseDragCommand.html
+pub fn mouse_drag_command() -> Arc<MouseDragCommand> {
+    Arc::new(MouseDragCommand)
+}
+
+impl Command for MouseDragCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let selected_frame = selected_frame.lock();
+        if let Some((window, location)) = selected_frame.window_at_mouse() {
+            if Arc::ptr_eq(&window, &selected_frame.selected_window) {
+                let mut window = window.lock();
+                if !is_mark_active(&window) {
+                    set_mark(&window, window.cursor.get());
+                }
+                window.set_cursor(location);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ted_common_commands::vertical_split_command;
 
     #[test]
     fn get_mark_with_set_is_correct() {
         let window = Window::new();
-        let cursor = Cursor::new();
-        set_mark(&window, cursor);
-        assert_eq!(get_mark(&window).unwrap().get(), 0);
+        set_mark(&window, 0);
+        assert_eq!(get_mark(&window), Some(0));
         assert!(is_mark_active(&window));
     }
 
@@ -124,12 +202,97 @@ mod tests {
     #[test]
     fn remove_mark_works() {
         let window = Window::new();
-        let cursor = Cursor::new();
-        set_mark(&window, cursor);
+        set_mark(&window, 0);
         assert!(get_mark(&window).is_some());
         assert!(is_mark_active(&window));
         remove_mark(&window);
         assert!(get_mark(&window).is_none());
         assert!(!is_mark_active(&window));
     }
+
+    #[test]
+    fn mark_moves_with_edits() {
+        let mut window = Window::new();
+        window.insert_str("abc").unwrap();
+        set_mark(&window, 1);
+        window.set_cursor(0);
+        window.insert_str("xy").unwrap();
+        assert_eq!(get_mark(&window), Some(3));
+        window.set_cursor(3);
+        window.insert('z').unwrap();
+        assert_eq!(get_mark(&window), Some(3));
+        window.buffer.lock().delete_region(2, 5).unwrap();
+        assert_eq!(get_mark(&window), Some(2));
+        remove_mark(&window);
+    }
+
+    #[test]
+    fn mouse_drag_sets_region() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let window = state.lock().display.selected_window();
+        window.lock().insert_str("abc\ndef").unwrap();
+        let selected_frame = state.lock().display.selected_frame.clone();
+
+        selected_frame.lock().mouse_position = Some((0, 1));
+        mouse_press_command().execute(state.clone()).unwrap();
+        assert!(!is_mark_active(&window.lock()));
+        assert_eq!(window.lock().cursor.get(), 1);
+
+        selected_frame.lock().mouse_position = Some((1, 2));
+        mouse_drag_command().execute(state.clone()).unwrap();
+        selected_frame.lock().mouse_position = Some((1, 1));
+        mouse_drag_command().execute(state.clone()).unwrap();
+        let window = window.lock();
+        assert_eq!(get_region(&window, &window.buffer.lock()), 1..5);
+        remove_mark(&window);
+    }
+
+    #[test]
+    fn mouse_press_selects_other_window() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let left = state.lock().display.selected_window();
+        left.lock().insert_str("abc\ndef").unwrap();
+        vertical_split_command().execute(state.clone()).unwrap();
+
+        state.lock().display.selected_frame.lock().mouse_position = Some((1, 12));
+        mouse_press_command().execute(state.clone()).unwrap();
+
+        let right = state.lock().display.selected_window();
+        assert!(!Arc::ptr_eq(&left, &right));
+        assert_eq!(right.lock().cursor.get(), 5);
+        assert_eq!(left.lock().cursor.get(), 7);
+    }
+
+    #[test]
+    fn mouse_press_on_separator_does_nothing() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let left = state.lock().display.selected_window();
+        vertical_split_command().execute(state.clone()).unwrap();
+
+        state.lock().display.selected_frame.lock().mouse_position = Some((1, 10));
+        mouse_press_command().execute(state.clone()).unwrap();
+
+        assert!(Arc::ptr_eq(&left, &state.lock().display.selected_window()));
+    }
+
+    #[test]
+    fn mouse_press_runs_overlay_action() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let buffer = state.lock().display.selected_window_buffer();
+        {
+            let mut buffer = buffer.lock();
+            buffer.insert_str(0, "abc").unwrap();
+            let mut overlay = Overlay::new(&buffer, 1..3);
+            overlay.action = Some(insert_command('x'));
+            buffer.overlays.push(overlay);
+        }
+
+        state.lock().display.selected_frame.lock().mouse_position = Some((0, 0));
+        mouse_press_command().execute(state.clone()).unwrap();
+        assert_eq!(buffer.lock().to_string(), "abc");
+
+        state.lock().display.selected_frame.lock().mouse_position = Some((0, 2));
+        mouse_press_command().execute(state.clone()).unwrap();
+        assert_eq!(buffer.lock().to_string(), "abxc");
+    }
 }
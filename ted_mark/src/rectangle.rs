@@ -0,0 +1,539 @@
+use parking_lot::Mutex;
+use std::ops::Range;
+use std::sync::Arc;
+use ted_common_commands::{begin_of_line, end_of_line};
+use ted_core::draw::{line_column, location_at};
+use ted_core::*;
+use {get_region, is_mark_active};
+
+pub const ERROR_MARK_NOT_SET: &str = "Error: The mark is not set";
+
+lazy_static! {
+    static ref KILLED_RECTANGLE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+}
+
+/// The rectangle with the mark and the cursor at opposite corners.
+///
+/// Columns are counted as the [`Window`] draws them, so tabs and wide
+/// characters line up with what is on the screen.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug, PartialEq)]
+pub struct Rectangle {
+    /// The location of the beginning of each line of the rectangle.
+    pub lines: Vec<usize>,
+    pub columns: Range<usize>,
+    /// The options of the [`Window`] the columns are counted in.
+    ///
+    /// [`Window`]: ../ted_core/struct.Window.html
+    pub options: DisplayOptions,
+}
+
+impl Rectangle {
+    /// Get the part of the line beginning at `begin` that is inside
+    /// the rectangle.  It is shorter than the rectangle if the line
+    /// ends before the last column.
+    pub fn line_range(&self, buffer: &Buffer, begin: usize) -> Range<usize> {
+        column_location(buffer, &self.options, begin, self.columns.start)
+            ..column_location(buffer, &self.options, begin, self.columns.end)
+    }
+
+    pub fn width(&self) -> usize {
+        self.columns.end - self.columns.start
+    }
+}
+
+/// Get the location of the character drawn at `column` of the line
+/// beginning at `begin`, or the end of the line if it is shorter.
+fn column_location(
+    buffer: &Buffer,
+    options: &DisplayOptions,
+    begin: usize,
+    column: usize,
+) -> usize {
+    let options = DisplayOptions {
+        wrap_mode: WrapMode::Truncate,
+        ..*options
+    };
+    begin
+        + location_at(
+            buffer.display_iter_at(begin),
+            &options,
+            0,
+            usize::MAX,
+            0,
+            column,
+        )
+}
+
+/// Get the [`Rectangle`] between the mark and the cursor of `window`.
+///
+/// [`Rectangle`]: struct.Rectangle.html
+pub fn get_rectangle(window: &Window, buffer: &Buffer) -> Result<Rectangle, String> {
+    if !is_mark_active(window) {
+        Err(ERROR_MARK_NOT_SET)?
+    }
+    let Range { start, end } = get_region(window, buffer);
+    let mut begin = begin_of_line(buffer, start);
+    let last_begin = begin_of_line(buffer, end);
+    let start_column = line_column(buffer, &window.display_options, start);
+    let end_column = line_column(buffer, &window.display_options, end);
+    let mut lines = vec![begin];
+    while begin != last_begin {
+        begin = end_of_line(buffer, begin) + 1;
+        lines.push(begin);
+    }
+    Ok(Rectangle {
+        lines,
+        columns: std::cmp::min(start_column, end_column)..std::cmp::max(start_column, end_column),
+        options: window.display_options,
+    })
+}
+
+/// Get the text of each line in the rectangle.  Short lines are
+/// padded with spaces.
+pub fn extract_rectangle(window: &Window) -> Result<Vec<String>, String> {
+    let buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    let mut strings = Vec::new();
+    for &begin in &rectangle.lines {
+        let range = rectangle.line_range(&buffer, begin);
+        let mut string = buffer.substring(range.start, range.end)?;
+        let width = range_width(&buffer, &rectangle.options, &range);
+        pad(&mut string, rectangle.width().saturating_sub(width));
+        strings.push(string);
+    }
+    Ok(strings)
+}
+
+fn pad(string: &mut String, spaces: usize) {
+    string.extend(std::iter::repeat_n(' ', spaces));
+}
+
+/// The number of columns `range` takes up on its line.
+fn range_width(buffer: &Buffer, options: &DisplayOptions, range: &Range<usize>) -> usize {
+    line_column(buffer, options, range.end) - line_column(buffer, options, range.start)
+}
+
+/// Run `f` on `buffer` so that its changes are undone together.
+fn with_undo_group<F>(buffer: &mut Buffer, f: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Buffer) -> Result<(), String>,
+{
+    buffer.begin_undo_group();
+    let result = f(buffer);
+    buffer.end_undo_group();
+    result
+}
+
+/// Insert `s` at `column` of the line beginning at `begin`, padding
+/// the line with spaces if it is too short.
+///
+/// Returns the location after the inserted text.
+fn insert_at_column(
+    buffer: &mut Buffer,
+    options: &DisplayOptions,
+    begin: usize,
+    column: usize,
+    s: &str,
+) -> Result<usize, String> {
+    let end = end_of_line(buffer, begin);
+    let end_column = line_column(buffer, options, end);
+    let (location, s) = if end_column < column {
+        let mut padded = String::new();
+        pad(&mut padded, column - end_column);
+        padded.push_str(s);
+        (end, padded)
+    } else {
+        (
+            column_location(buffer, options, begin, column),
+            s.to_string(),
+        )
+    };
+    buffer.insert_str(location, &s)?;
+    Ok(location + s.chars().count())
+}
+
+/// Remember the rectangle so that it can be yanked by
+/// [`yank_rectangle`].
+///
+/// [`yank_rectangle`]: fn.yank_rectangle.html
+pub fn copy_rectangle(window: &Window) -> Result<(), String> {
+    *KILLED_RECTANGLE.lock() = extract_rectangle(window)?;
+    Ok(())
+}
+
+/// Delete the rectangle and remember it so that it can be yanked by
+/// [`yank_rectangle`].
+///
+/// [`yank_rectangle`]: fn.yank_rectangle.html
+pub fn kill_rectangle(window: &Window) -> Result<(), String> {
+    let strings = extract_rectangle(window)?;
+    delete_rectangle(window)?;
+    *KILLED_RECTANGLE.lock() = strings;
+    Ok(())
+}
+
+/// Delete the rectangle without remembering it.
+pub fn delete_rectangle(window: &Window) -> Result<(), String> {
+    let mut buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    with_undo_group(&mut buffer, |buffer| {
+        for &begin in rectangle.lines.iter().rev() {
+            let range = rectangle.line_range(buffer, begin);
+            buffer.delete_region(range.start, range.end)?;
+        }
+        Ok(())
+    })
+}
+
+/// Insert the last killed or copied rectangle with its top left
+/// corner at the cursor.
+///
+/// Each line is inserted at the column of the cursor on consecutive
+/// lines.  Short lines are padded with spaces and lines are added at
+/// the end of the [`Buffer`] as needed.  Returns the location after
+/// the last line inserted.
+///
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn yank_rectangle(window: &Window) -> Result<usize, String> {
+    let strings = KILLED_RECTANGLE.lock().clone();
+    let mut buffer = window.buffer.lock();
+    let cursor = window.cursor.clone().updated(&buffer).get();
+    let options = window.display_options;
+    let mut begin = begin_of_line(&buffer, cursor);
+    let column = line_column(&buffer, &options, cursor);
+    let mut location = cursor;
+    with_undo_group(&mut buffer, |buffer| {
+        for (index, string) in strings.iter().enumerate() {
+            if index != 0 {
+                let end = end_of_line(buffer, begin);
+                if end == buffer.len() {
+                    buffer.insert(end, '\n')?;
+                }
+                begin = end + 1;
+            }
+            location = insert_at_column(buffer, &options, begin, column, string)?;
+        }
+        Ok(())
+    })?;
+    Ok(location)
+}
+
+/// Insert `s` at the left edge of the rectangle on every line.
+/// Short lines are padded with spaces.
+pub fn string_insert_rectangle(window: &Window, s: &str) -> Result<(), String> {
+    let mut buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    with_undo_group(&mut buffer, |buffer| {
+        for &begin in rectangle.lines.iter().rev() {
+            insert_at_column(
+                buffer,
+                &rectangle.options,
+                begin,
+                rectangle.columns.start,
+                s,
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Insert blank space filling the rectangle, shifting the text in it
+/// to the right.  Lines that end before the rectangle are left alone.
+pub fn open_rectangle(window: &Window) -> Result<(), String> {
+    let mut buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    let mut spaces = String::new();
+    pad(&mut spaces, rectangle.width());
+    with_undo_group(&mut buffer, |buffer| {
+        for &begin in rectangle.lines.iter().rev() {
+            let end = end_of_line(buffer, begin);
+            if rectangle.columns.start <= line_column(buffer, &rectangle.options, end) {
+                let range = rectangle.line_range(buffer, begin);
+                buffer.insert_str(range.start, &spaces)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Replace the text in the rectangle with spaces.
+pub fn clear_rectangle(window: &Window) -> Result<(), String> {
+    let mut buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    with_undo_group(&mut buffer, |buffer| {
+        for &begin in rectangle.lines.iter().rev() {
+            let range = rectangle.line_range(buffer, begin);
+            if range.start != range.end {
+                let mut spaces = String::new();
+                pad(&mut spaces, range_width(buffer, &rectangle.options, &range));
+                buffer.delete_region(range.start, range.end)?;
+                buffer.insert_str(range.start, &spaces)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Number the lines of the rectangle starting at 1.
+///
+/// The numbers are right aligned and inserted at the left edge of
+/// the rectangle followed by a space.  Short lines are padded with
+/// spaces.
+pub fn number_rectangle_lines(window: &Window) -> Result<(), String> {
+    let mut buffer = window.buffer.lock();
+    let rectangle = get_rectangle(window, &buffer)?;
+    let width = rectangle.lines.len().to_string().len();
+    with_undo_group(&mut buffer, |buffer| {
+        for (index, &begin) in rectangle.lines.iter().enumerate().rev() {
+            let number = format!("{:>1$} ", index + 1, width);
+            insert_at_column(
+                buffer,
+                &rectangle.options,
+                begin,
+                rectangle.columns.start,
+                &number,
+            )?;
+        }
+        Ok(())
+    })
+}
+
+/// Kill the rectangle between the mark and the cursor of the selected
+/// [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct KillRectangleCommand;
+
+/// Construct a [`KillRectangleCommand`].
+///
+/// [`KillRectangleCommand`]: struct.KillRectangleCommand.html
+pub fn kill_rectangle_command() -> Arc<KillRectangleCommand> {
+    Arc::new(KillRectangleCommand)
+}
+
+impl Command for KillRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        kill_rectangle(&selected_window)?;
+        selected_window.update_cursor();
+        Ok(())
+    }
+}
+
+/// Copy the rectangle between the mark and the cursor of the selected
+/// [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct CopyRectangleCommand;
+
+/// Construct a [`CopyRectangleCommand`].
+///
+/// [`CopyRectangleCommand`]: struct.CopyRectangleCommand.html
+pub fn copy_rectangle_command() -> Arc<CopyRectangleCommand> {
+    Arc::new(CopyRectangleCommand)
+}
+
+impl Command for CopyRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        copy_rectangle(&selected_window)
+    }
+}
+
+/// Yank the last killed rectangle at the cursor of the selected
+/// [`Window`](../ted_core/struct.Window.html).
+///
+/// The cursor is moved to the end of the last line yanked.
+#[derive(Debug)]
+pub struct YankRectangleCommand;
+
+/// Construct a [`YankRectangleCommand`].
+///
+/// [`YankRectangleCommand`]: struct.YankRectangleCommand.html
+pub fn yank_rectangle_command() -> Arc<YankRectangleCommand> {
+    Arc::new(YankRectangleCommand)
+}
+
+impl Command for YankRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        let location = yank_rectangle(&selected_window)?;
+        selected_window.set_cursor(location);
+        Ok(())
+    }
+}
+
+/// Open a blank rectangle in the selected
+/// [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct OpenRectangleCommand;
+
+/// Construct an [`OpenRectangleCommand`].
+///
+/// [`OpenRectangleCommand`]: struct.OpenRectangleCommand.html
+pub fn open_rectangle_command() -> Arc<OpenRectangleCommand> {
+    Arc::new(OpenRectangleCommand)
+}
+
+impl Command for OpenRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        open_rectangle(&selected_window)?;
+        selected_window.update_cursor();
+        Ok(())
+    }
+}
+
+/// Blank out the rectangle in the selected
+/// [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct ClearRectangleCommand;
+
+/// Construct a [`ClearRectangleCommand`].
+///
+/// [`ClearRectangleCommand`]: struct.ClearRectangleCommand.html
+pub fn clear_rectangle_command() -> Arc<ClearRectangleCommand> {
+    Arc::new(ClearRectangleCommand)
+}
+
+impl Command for ClearRectangleCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        clear_rectangle(&selected_window)?;
+        selected_window.update_cursor();
+        Ok(())
+    }
+}
+
+/// Number the lines of the rectangle in the selected
+/// [`Window`](../ted_core/struct.Window.html).
+#[derive(Debug)]
+pub struct NumberRectangleLinesCommand;
+
+/// Construct a [`NumberRectangleLinesCommand`].
+///
+/// [`NumberRectangleLinesCommand`]: struct.NumberRectangleLinesCommand.html
+pub fn number_rectangle_lines_command() -> Arc<NumberRectangleLinesCommand> {
+    Arc::new(NumberRectangleLinesCommand)
+}
+
+impl Command for NumberRectangleLinesCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let mut selected_window = selected_window.lock();
+        number_rectangle_lines(&selected_window)?;
+        selected_window.update_cursor();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {remove_mark, set_mark};
+
+    /// Set up `window` with the mark at `mark` and the cursor at
+    /// `cursor`.  The mark is keyed by the address of the `Window` so
+    /// it can't be moved afterwards.
+    fn set_rectangle(window: &mut Window, contents: &str, mark: usize, cursor: usize) {
+        window.insert_str(contents).unwrap();
+        set_mark(window, mark);
+        window.set_cursor(cursor);
+    }
+
+    #[test]
+    fn get_rectangle_corners() {
+        let mut window = Window::new();
+        set_rectangle(&mut window, "abcd\nef\nghij", 11, 1);
+        {
+            let buffer = window.buffer.lock();
+            assert_eq!(
+                get_rectangle(&window, &buffer).unwrap(),
+                Rectangle {
+                    lines: vec![0, 5, 8],
+                    columns: 1..3,
+                    options: DisplayOptions::default(),
+                }
+            );
+        }
+        assert_eq!(extract_rectangle(&window).unwrap(), vec!["bc", "f ", "hi"]);
+        remove_mark(&window);
+        assert_eq!(
+            extract_rectangle(&window),
+            Err(ERROR_MARK_NOT_SET.to_string())
+        );
+    }
+
+    #[test]
+    fn kill_and_yank_rectangle() {
+        let mut window = Window::new();
+        set_rectangle(&mut window, "abcd\nef\nghij", 1, 11);
+        kill_rectangle(&window).unwrap();
+        window.update_cursor();
+        assert_eq!(window.buffer.lock().to_string(), "ad\ne\ngj");
+        assert_eq!(window.cursor.get(), 6);
+        remove_mark(&window);
+
+        window.set_cursor(4);
+        let location = yank_rectangle(&window).unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ad\nebc\ngf j\n hi");
+        assert_eq!(location, 15);
+
+        // The kill is undone in one step.
+        window.buffer.lock().undo().unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ad\ne\ngj");
+        window.buffer.lock().undo().unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "abcd\nef\nghij");
+    }
+
+    #[test]
+    fn rectangle_columns_are_display_columns() {
+        let mut window = Window::new();
+        window.display_options.tab_width = 4;
+        // The mark is after the tab at column 4 and the cursor is
+        // before the `j` at column 5.
+        set_rectangle(&mut window, "\tab\n日本cd\nefghij", 1, 14);
+        assert_eq!(extract_rectangle(&window).unwrap(), vec!["a", "c", "i"]);
+        string_insert_rectangle(&window, "|").unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "\t|ab\n日本|cd\nefgh|ij");
+        window.buffer.lock().undo().unwrap();
+        remove_mark(&window);
+
+        // Clearing a tab replaces it with as many spaces as it is
+        // wide.
+        let mut window = Window::new();
+        window.display_options.tab_width = 4;
+        set_rectangle(&mut window, "\tab\ncdefg", 0, 8);
+        clear_rectangle(&window).unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "    ab\n    g");
+        remove_mark(&window);
+    }
+
+    #[test]
+    fn edit_rectangle() {
+        let mut window = Window::new();
+        set_rectangle(&mut window, "abcd\ne\nfghi", 2, 11);
+        string_insert_rectangle(&window, "<>").unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ab<>cd\ne <>\nfg<>hi");
+        window.buffer.lock().undo().unwrap();
+
+        open_rectangle(&window).unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ab  cd\ne\nfg  hi");
+        window.buffer.lock().undo().unwrap();
+
+        clear_rectangle(&window).unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ab  \ne\nfg  ");
+        window.buffer.lock().undo().unwrap();
+
+        number_rectangle_lines(&window).unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "ab1 cd\ne 2 \nfg3 hi");
+        window.buffer.lock().undo().unwrap();
+        assert_eq!(window.buffer.lock().to_string(), "abcd\ne\nfghi");
+        remove_mark(&window);
+    }
+}
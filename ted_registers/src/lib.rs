@@ -0,0 +1,609 @@
+#[macro_use]
+extern crate lazy_static;
+extern crate parking_lot;
+extern crate ted_core;
+extern crate ted_mark;
+
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use ted_core::*;
+use ted_mark::*;
+
+/// The contents of a register.
+#[derive(Clone)]
+pub enum Register {
+    /// Text copied from a [`Buffer`].
+    ///
+    /// [`Buffer`]: ../ted_core/struct.Buffer.html
+    Text(String),
+    /// A location in a [`Buffer`] that moves as the [`Buffer`] is
+    /// edited.
+    ///
+    /// [`Buffer`]: ../ted_core/struct.Buffer.html
+    Position {
+        buffer: Arc<Mutex<Buffer>>,
+        marker: Marker,
+    },
+    /// The [`Layout`] of a [`Frame`] and its selected [`Window`].
+    ///
+    /// [`Layout`]: ../ted_core/enum.Layout.html
+    /// [`Frame`]: ../ted_core/struct.Frame.html
+    /// [`Window`]: ../ted_core/struct.Window.html
+    Layout {
+        layout: LayoutSnapshot,
+        /// The index of the selected [`Window`], counting from the
+        /// first [`Window`] of the [`Layout`].
+        ///
+        /// [`Window`]: ../ted_core/struct.Window.html
+        /// [`Layout`]: ../ted_core/enum.Layout.html
+        selected_window: usize,
+    },
+}
+
+/// A [`Window`] saved in a layout register.
+///
+/// The cursor and start are [`Marker`]s so they follow edits made
+/// after the [`Window`] is saved.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Marker`]: ../ted_core/struct.Marker.html
+#[derive(Clone)]
+pub struct WindowSnapshot {
+    /// A copy of the [`Window`] that is never shown.
+    ///
+    /// [`Window`]: ../ted_core/struct.Window.html
+    window: Arc<Mutex<Window>>,
+    cursor: Marker,
+    start: Marker,
+}
+
+impl WindowSnapshot {
+    fn new(window: &Arc<Mutex<Window>>) -> Self {
+        let window = clone_window(window);
+        let (cursor, start) = {
+            let window = window.lock();
+            let buffer = window.buffer.lock();
+            let cursor = window.cursor.clone().updated(&buffer).get();
+            (
+                buffer.add_marker(cursor, Gravity::Right),
+                buffer.add_marker(window.start, Gravity::Left),
+            )
+        };
+        WindowSnapshot {
+            window,
+            cursor,
+            start,
+        }
+    }
+
+    /// Make a new [`Window`] like the saved one.
+    ///
+    /// [`Window`]: ../ted_core/struct.Window.html
+    fn restore(&self) -> Arc<Mutex<Window>> {
+        let window = clone_window(&self.window);
+        {
+            let mut window = window.lock();
+            window.set_cursor(self.cursor.get());
+            window.start = self.start.get();
+        }
+        window
+    }
+}
+
+/// A [`Layout`] of [`WindowSnapshot`]s.
+///
+/// [`Layout`]: ../ted_core/enum.Layout.html
+/// [`WindowSnapshot`]: struct.WindowSnapshot.html
+#[derive(Clone)]
+pub enum LayoutSnapshot {
+    Window(WindowSnapshot),
+    VSplit {
+        left: Box<LayoutSnapshot>,
+        right: Box<LayoutSnapshot>,
+    },
+    HSplit {
+        top: Box<LayoutSnapshot>,
+        bottom: Box<LayoutSnapshot>,
+    },
+}
+
+impl LayoutSnapshot {
+    /// Save each [`Window`] of `layout`.
+    ///
+    /// [`Window`]: ../ted_core/struct.Window.html
+    pub fn new(layout: &Layout) -> Self {
+        match *layout {
+            Layout::Window(ref window) => LayoutSnapshot::Window(WindowSnapshot::new(window)),
+            Layout::VSplit {
+                ref left,
+                ref right,
+            } => LayoutSnapshot::VSplit {
+                left: Box::new(LayoutSnapshot::new(left)),
+                right: Box::new(LayoutSnapshot::new(right)),
+            },
+            Layout::HSplit {
+                ref top,
+                ref bottom,
+            } => LayoutSnapshot::HSplit {
+                top: Box::new(LayoutSnapshot::new(top)),
+                bottom: Box::new(LayoutSnapshot::new(bottom)),
+            },
+        }
+    }
+
+    /// Build a [`Layout`] of new [`Window`]s, pushing each one onto
+    /// `windows` in order.
+    ///
+    /// [`Layout`]: ../ted_core/enum.Layout.html
+    /// [`Window`]: ../ted_core/struct.Window.html
+    pub fn restore(&self, windows: &mut Vec<Arc<Mutex<Window>>>) -> Layout {
+        match *self {
+            LayoutSnapshot::Window(ref snapshot) => {
+                let window = snapshot.restore();
+                windows.push(window.clone());
+                Layout::Window(window)
+            }
+            LayoutSnapshot::VSplit {
+                ref left,
+                ref right,
+            } => Layout::VSplit {
+                left: Box::new(left.restore(windows)),
+                right: Box::new(right.restore(windows)),
+            },
+            LayoutSnapshot::HSplit {
+                ref top,
+                ref bottom,
+            } => Layout::HSplit {
+                top: Box::new(top.restore(windows)),
+                bottom: Box::new(bottom.restore(windows)),
+            },
+        }
+    }
+}
+
+/// Push the [`Window`]s of `layout` onto `windows` in order.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+fn layout_windows(layout: &Layout, windows: &mut Vec<Arc<Mutex<Window>>>) {
+    match *layout {
+        Layout::Window(ref window) => windows.push(window.clone()),
+        Layout::VSplit {
+            ref left,
+            ref right,
+        } => {
+            layout_windows(left, windows);
+            layout_windows(right, windows);
+        }
+        Layout::HSplit {
+            ref top,
+            ref bottom,
+        } => {
+            layout_windows(top, windows);
+            layout_windows(bottom, windows);
+        }
+    }
+}
+
+lazy_static! {
+    static ref REGISTERS: Mutex<BTreeMap<char, Register>> = Mutex::new(BTreeMap::new());
+}
+
+/// Store `value` in `register`, replacing what was there.
+pub fn set_register(register: char, value: Register) {
+    REGISTERS.lock().insert(register, value);
+}
+
+/// Get the contents of `register`.
+pub fn get_register(register: char) -> Option<Register> {
+    REGISTERS.lock().get(&register).cloned()
+}
+
+fn get_register_result(register: char) -> Result<Register, String> {
+    get_register(register).ok_or_else(|| format!("Error: Register {} is empty", register))
+}
+
+/// Describe each register on its own line, starting with its name.
+fn registers_contents() -> String {
+    let mut contents = String::new();
+    for (register, value) in REGISTERS.lock().iter() {
+        let description = match *value {
+            Register::Text(ref text) => {
+                let summary: String = text.chars().take(40).collect();
+                let ellipsis = if summary.len() < text.len() {
+                    "..."
+                } else {
+                    ""
+                };
+                format!("text {:?}{}", summary, ellipsis)
+            }
+            Register::Position {
+                ref buffer,
+                ref marker,
+            } => format!("position {} in {}", marker.get(), buffer.lock().name.name),
+            Register::Layout { .. } => "window layout".to_string(),
+        };
+        contents.push_str(&format!("{} {}\n", register, description));
+    }
+    contents
+}
+
+/// Copy the region of the selected [`Window`] into a register.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct CopyToRegisterCommand {
+    register: char,
+}
+
+/// Construct a [`CopyToRegisterCommand`] that copies to `register`.
+///
+/// [`CopyToRegisterCommand`]: struct.CopyToRegisterCommand.html
+pub fn copy_to_register_command(register: char) -> Arc<CopyToRegisterCommand> {
+    Arc::new(CopyToRegisterCommand { register })
+}
+
+impl Command for CopyToRegisterCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        if !is_mark_active(&selected_window) {
+            Err(ERROR_MARK_NOT_SET)?
+        }
+        let text = {
+            let buffer = selected_window.buffer.lock();
+            substring_region(&selected_window, &buffer).1
+        };
+        set_register(self.register, Register::Text(text));
+        Ok(())
+    }
+}
+
+/// Insert the text in a register at the cursor of the selected
+/// [`Window`].
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+#[derive(Debug)]
+pub struct InsertRegisterCommand {
+    register: char,
+}
+
+/// Construct an [`InsertRegisterCommand`] that inserts `register`.
+///
+/// [`InsertRegisterCommand`]: struct.InsertRegisterCommand.html
+pub fn insert_register_command(register: char) -> Arc<InsertRegisterCommand> {
+    Arc::new(InsertRegisterCommand { register })
+}
+
+impl Command for InsertRegisterCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        match get_register_result(self.register)? {
+            Register::Text(text) => {
+                let selected_window = state.lock().display.selected_window();
+                let mut selected_window = selected_window.lock();
+                selected_window.update_cursor();
+                selected_window.insert_str(&text)
+            }
+            _ => Err(format!(
+                "Error: Register {} doesn't contain text",
+                self.register
+            )),
+        }
+    }
+}
+
+/// Store the cursor of the selected [`Window`] in a register.
+///
+/// The position is a [`Marker`] so it follows later edits.
+///
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Marker`]: ../ted_core/struct.Marker.html
+#[derive(Debug)]
+pub struct PointToRegisterCommand {
+    register: char,
+}
+
+/// Construct a [`PointToRegisterCommand`] that stores in `register`.
+///
+/// [`PointToRegisterCommand`]: struct.PointToRegisterCommand.html
+pub fn point_to_register_command(register: char) -> Arc<PointToRegisterCommand> {
+    Arc::new(PointToRegisterCommand { register })
+}
+
+impl Command for PointToRegisterCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        let buffer = selected_window.buffer.clone();
+        let marker = {
+            let buffer = buffer.lock();
+            let cursor = selected_window.cursor.clone().updated(&buffer);
+            buffer.add_marker(cursor.get(), Gravity::Left)
+        };
+        set_register(self.register, Register::Position { buffer, marker });
+        Ok(())
+    }
+}
+
+/// Store the [`Layout`] of the selected [`Frame`] in a register.
+///
+/// Each [`Window`] is saved with its [`Buffer`], cursor and start.
+///
+/// [`Layout`]: ../ted_core/enum.Layout.html
+/// [`Frame`]: ../ted_core/struct.Frame.html
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+#[derive(Debug)]
+pub struct WindowLayoutToRegisterCommand {
+    register: char,
+}
+
+/// Construct a [`WindowLayoutToRegisterCommand`] that stores in
+/// `register`.
+///
+/// [`WindowLayoutToRegisterCommand`]: struct.WindowLayoutToRegisterCommand.html
+pub fn window_layout_to_register_command(register: char) -> Arc<WindowLayoutToRegisterCommand> {
+    Arc::new(WindowLayoutToRegisterCommand { register })
+}
+
+impl Command for WindowLayoutToRegisterCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let selected_frame = selected_frame.lock();
+        let mut windows = Vec::new();
+        layout_windows(&selected_frame.layout, &mut windows);
+        let selected_window = windows
+            .iter()
+            .position(|window| Arc::ptr_eq(window, &selected_frame.selected_window))
+            .unwrap_or(0);
+        set_register(
+            self.register,
+            Register::Layout {
+                layout: LayoutSnapshot::new(&selected_frame.layout),
+                selected_window,
+            },
+        );
+        Ok(())
+    }
+}
+
+/// Jump to the position or restore the [`Layout`] stored in a
+/// register.
+///
+/// A position in another [`Buffer`] is shown in the selected
+/// [`Window`].  A [`Layout`] is restored in the selected [`Frame`]
+/// with new [`Window`]s, so restoring it in several [`Frame`]s doesn't
+/// share [`Window`]s between them.
+///
+/// [`Layout`]: ../ted_core/enum.Layout.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+/// [`Window`]: ../ted_core/struct.Window.html
+/// [`Frame`]: ../ted_core/struct.Frame.html
+#[derive(Debug)]
+pub struct JumpToRegisterCommand {
+    register: char,
+}
+
+/// Construct a [`JumpToRegisterCommand`] that jumps to `register`.
+///
+/// [`JumpToRegisterCommand`]: struct.JumpToRegisterCommand.html
+pub fn jump_to_register_command(register: char) -> Arc<JumpToRegisterCommand> {
+    Arc::new(JumpToRegisterCommand { register })
+}
+
+impl Command for JumpToRegisterCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let value = get_register_result(self.register)?;
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let mut selected_frame = selected_frame.lock();
+        match value {
+            Register::Text(_) => Err(format!(
+                "Error: Register {} doesn't contain a position or layout",
+                self.register
+            )),
+            Register::Position { buffer, marker } => {
+                let is_shown = {
+                    let selected_window = selected_frame.selected_window.lock();
+                    Arc::ptr_eq(&selected_window.buffer, &buffer)
+                };
+                if !is_shown {
+                    let window = Arc::new(Mutex::new(Window::from(buffer)));
+                    selected_frame.replace_selected_window(window);
+                }
+                selected_frame
+                    .selected_window
+                    .lock()
+                    .set_cursor(marker.get());
+                Ok(())
+            }
+            Register::Layout {
+                layout,
+                selected_window,
+            } => {
+                let mut windows = Vec::new();
+                selected_frame.layout = layout.restore(&mut windows);
+                selected_frame.selected_window = windows[selected_window].clone();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// List the registers in a `*Registers*` buffer.
+#[derive(Debug)]
+pub struct ListRegistersCommand;
+
+/// Construct a [`ListRegistersCommand`].
+///
+/// [`ListRegistersCommand`]: struct.ListRegistersCommand.html
+pub fn list_registers_command() -> Arc<ListRegistersCommand> {
+    Arc::new(ListRegistersCommand)
+}
+
+impl Command for ListRegistersCommand {
+    fn execute(&self, state: Arc<Mutex<State>>) -> Result<(), String> {
+        let mut buffer = Buffer::new_with_contents("*Registers*".into(), &registers_contents());
+        buffer.read_only = true;
+        let window = Arc::new(Mutex::new(Window::from(buffer)));
+        let selected_frame = state.lock().display.selected_frame.clone();
+        selected_frame.lock().replace_selected_window(window);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The registers are shared, so each test uses its own.
+
+    fn new_state(contents: &str) -> Arc<Mutex<State>> {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let selected_window = state.lock().display.selected_window();
+        selected_window.lock().insert_str(contents).unwrap();
+        state
+    }
+
+    #[test]
+    fn copy_and_insert_register() {
+        let state = new_state("abc def");
+        let selected_window = state.lock().display.selected_window();
+        assert!(copy_to_register_command('a')
+            .execute(state.clone())
+            .is_err());
+        {
+            let mut selected_window = selected_window.lock();
+            selected_window.set_cursor(4);
+            set_mark(&selected_window, selected_window.cursor.get());
+            selected_window.set_cursor(7);
+        }
+        copy_to_register_command('a')
+            .execute(state.clone())
+            .unwrap();
+        remove_mark(&selected_window.lock());
+        selected_window.lock().set_cursor(0);
+        insert_register_command('a').execute(state.clone()).unwrap();
+        assert_eq!(
+            selected_window.lock().buffer.lock().to_string(),
+            "defabc def"
+        );
+        assert_eq!(selected_window.lock().cursor.get(), 3);
+
+        assert_eq!(
+            insert_register_command('b').execute(state.clone()),
+            Err("Error: Register b is empty".to_string())
+        );
+        assert!(jump_to_register_command('a')
+            .execute(state.clone())
+            .is_err());
+    }
+
+    #[test]
+    fn point_to_register_follows_edits() {
+        let state = new_state("abc def");
+        let first_window = state.lock().display.selected_window();
+        first_window.lock().set_cursor(4);
+        point_to_register_command('p')
+            .execute(state.clone())
+            .unwrap();
+        first_window
+            .lock()
+            .buffer
+            .lock()
+            .insert_str(0, "xy")
+            .unwrap();
+        assert!(insert_register_command('p').execute(state.clone()).is_err());
+
+        // Jumping from another buffer shows the position's buffer.
+        let selected_frame = state.lock().display.selected_frame.clone();
+        selected_frame
+            .lock()
+            .replace_selected_window(Arc::new(Mutex::new(Window::new())));
+        jump_to_register_command('p')
+            .execute(state.clone())
+            .unwrap();
+        let selected_window = state.lock().display.selected_window();
+        let selected_window = selected_window.lock();
+        assert!(Arc::ptr_eq(
+            &selected_window.buffer,
+            &first_window.lock().buffer
+        ));
+        assert_eq!(selected_window.cursor.get(), 6);
+    }
+
+    #[test]
+    fn window_layout_to_register_restores() {
+        let state = new_state("abc\ndef");
+        let first_window = state.lock().display.selected_window();
+        let selected_frame = state.lock().display.selected_frame.clone();
+        let other_window = Arc::new(Mutex::new(Window::new()));
+        {
+            let mut selected_frame = selected_frame.lock();
+            selected_frame.layout = Layout::VSplit {
+                left: Box::new(Layout::Window(other_window.clone())),
+                right: Box::new(Layout::Window(first_window.clone())),
+            };
+            first_window.lock().set_cursor(5);
+            first_window.lock().start = 4;
+        }
+        window_layout_to_register_command('w')
+            .execute(state.clone())
+            .unwrap();
+        {
+            let mut selected_frame = selected_frame.lock();
+            selected_frame.layout = Layout::Window(other_window.clone());
+            selected_frame.selected_window = other_window.clone();
+        }
+        first_window.lock().set_cursor(0);
+        first_window.lock().insert_str("xy").unwrap();
+
+        // The restored windows are new but show the same buffers at
+        // the saved cursors, moved by the edit.
+        jump_to_register_command('w')
+            .execute(state.clone())
+            .unwrap();
+        let restored = {
+            let selected_frame = selected_frame.lock();
+            let restored = selected_frame.selected_window.clone();
+            match selected_frame.layout {
+                Layout::VSplit {
+                    ref left,
+                    ref right,
+                } => {
+                    assert!(!Arc::ptr_eq(left.unwrap_window(), &other_window));
+                    assert!(Arc::ptr_eq(right.unwrap_window(), &restored));
+                }
+                _ => panic!("The layout wasn't restored"),
+            }
+            restored
+        };
+        assert!(!Arc::ptr_eq(&restored, &first_window));
+        {
+            let restored = restored.lock();
+            assert!(Arc::ptr_eq(&restored.buffer, &first_window.lock().buffer));
+            assert_eq!(restored.cursor.get(), 7);
+            assert_eq!(restored.start, 6);
+        }
+
+        // Restoring again makes different windows.
+        jump_to_register_command('w')
+            .execute(state.clone())
+            .unwrap();
+        assert!(!Arc::ptr_eq(
+            &selected_frame.lock().selected_window,
+            &restored
+        ));
+    }
+
+    #[test]
+    fn list_registers() {
+        set_register('x', Register::Text("one\ntwo".to_string()));
+        let state = new_state("");
+        list_registers_command().execute(state.clone()).unwrap();
+        let buffer = state.lock().display.selected_window_buffer();
+        let buffer = buffer.lock();
+        assert_eq!(buffer.name.name, "*Registers*");
+        assert!(buffer.read_only);
+        assert!(buffer.to_string().contains("x text \"one\\ntwo\"\n"));
+    }
+}
@@ -0,0 +1,134 @@
+use curses_renderer::CursesRenderer;
+use remote_protocol::*;
+use std::io::{BufReader, BufWriter, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use ted_core::draw::DrawableRenderer;
+use ted_core::*;
+
+/// Connect to the server listening at `socket_path` and show its new
+/// [`Frame`] in this terminal until it is closed.
+///
+/// If `file` is given, the server opens it in the new [`Frame`].
+///
+/// See [`start_server`].
+///
+/// [`Frame`]: ../ted_core/struct.Frame.html
+/// [`start_server`]: fn.start_server.html
+pub fn run_client(socket_path: &Path, file: Option<&Path>) -> Result<(), String> {
+    // The server doesn't know our working directory.
+    let file = match file {
+        Some(file) => Some(
+            std::env::current_dir()
+                .map_err(|e| e.to_string())?
+                .join(file),
+        ),
+        None => None,
+    };
+    let stream = UnixStream::connect(socket_path).map_err(|e| {
+        format!(
+            "Error: Couldn't connect to {}: {}",
+            socket_path.display(),
+            e
+        )
+    })?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let mut writer = BufWriter::new(stream);
+    let mut renderer =
+        CursesRenderer::new().map_err(|()| "Error: Couldn't start curses".to_string())?;
+    let (rows, columns) = renderer.size();
+    send(
+        &mut writer,
+        Request::Hello {
+            rows,
+            columns,
+            file,
+        },
+    )?;
+
+    let socket = reader.get_ref().as_raw_fd();
+    loop {
+        let (is_stdin_readable, is_socket_readable) = wait_for_input(socket);
+
+        if is_socket_readable {
+            // Apply every update that has arrived, including the ones
+            // already buffered that the socket won't report again.
+            loop {
+                let update = match read_line(&mut reader)? {
+                    Some(line) => Update::decode(&line)?,
+                    // The server went away.
+                    None => return Ok(()),
+                };
+                if !apply_update(&mut renderer, update)? {
+                    return Ok(());
+                }
+                if reader.buffer().is_empty() {
+                    break;
+                }
+            }
+        }
+
+        if is_stdin_readable {
+            while let Some(input) = renderer.getch() {
+                let request = if input.key == Key::Resize {
+                    let (rows, columns) = renderer.size();
+                    Request::Resize { rows, columns }
+                } else {
+                    Request::Input {
+                        input,
+                        mouse_position: renderer.mouse_position(),
+                        pasted_text: renderer.take_pasted_text(),
+                    }
+                };
+                send(&mut writer, request)?;
+            }
+        }
+    }
+}
+
+/// Sleep until stdin or `socket` is readable.  Returns whether each
+/// of them is.
+///
+/// A signal such as the terminal being resized wakes us up with
+/// neither readable, so stdin is then reported as readable to let
+/// curses handle it.
+fn wait_for_input(socket: RawFd) -> (bool, bool) {
+    let mut fds = [
+        libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        },
+        libc::pollfd {
+            fd: socket,
+            events: libc::POLLIN,
+            revents: 0,
+        },
+    ];
+    let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+    if result <= 0 {
+        return (true, false);
+    }
+    (fds[0].revents != 0, fds[1].revents != 0)
+}
+
+fn send(writer: &mut BufWriter<UnixStream>, request: Request) -> Result<(), String> {
+    write_line(writer, &request.encode())?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// Apply `update` to the terminal.  Returns `false` when the client
+/// should quit.
+fn apply_update(renderer: &mut CursesRenderer, update: Update) -> Result<bool, String> {
+    match update {
+        // The server only erases when the whole screen is redrawn.
+        Update::Erase => renderer.clear()?,
+        Update::Putch(y, x, ch) => renderer.putch(y, x, ch)?,
+        Update::SetAttribute(y, x, at) => renderer.set_attribute(y, x, at)?,
+        Update::ClearCursors => renderer.clear_cursors()?,
+        Update::Refresh => renderer.refresh()?,
+        Update::Quit => return Ok(false),
+    }
+    Ok(true)
+}
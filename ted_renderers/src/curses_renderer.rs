@@ -1,6 +1,10 @@
 use pancurses_result as pancurses;
 use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::os::unix::io::RawFd;
 use std::sync::Arc;
+use std::time::Duration;
 use ted_core::draw::*;
 use ted_core::*;
 
@@ -13,79 +17,249 @@ pub struct CursesRenderer {
     curses: pancurses::Curses,
     cursor: pancurses::Point,
     stalling_escape: bool,
+    mouse_position: Option<(usize, usize)>,
+    pending_inputs: VecDeque<pancurses::Input>,
+    pasted_text: Option<String>,
+    resized: bool,
+    screen: Screen,
 }
 
+/// The rest of the sequence the terminal sends after `ESC` to start a paste.
+const PASTE_START: &str = "[200~";
+/// The sequence the terminal sends to end a paste.
+const PASTE_END: &str = "\x1b[201~";
+/// How long to wait for each character of [`PASTE_START`] after an
+/// `ESC`, in case the terminal's output is split across reads.
+///
+/// [`PASTE_START`]: constant.PASTE_START.html
+const PASTE_START_TIMEOUT: Duration = Duration::from_millis(50);
+
 impl CursesRenderer {
     /// Initialize the curses backend and wrap it in the CursesRenderer object.
     pub fn new() -> Result<Self, ()> {
+        // Use the user's locale so curses outputs UTF-8.
+        unsafe {
+            libc::setlocale(libc::LC_ALL, b"\0".as_ptr() as *const libc::c_char);
+        }
         let mut curses = pancurses::initscr()?;
         curses.set_input_buffering_mode(pancurses::InputBufferingMode::UnbufferedNoSignals)?;
         curses.set_echo_input(false)?;
         curses.window_mut().set_block_on_read(false)?;
+        curses.window_mut().read_interpolate_function_keys(true)?;
+        curses.set_mouse_mask(
+            pancurses::ALL_MOUSE_EVENTS | pancurses::REPORT_MOUSE_POSITION,
+            None,
+        )?;
+        // Report presses and releases separately instead of as clicks.
+        curses.set_mouse_interval(Duration::from_millis(0))?;
+        // Report mouse movement while a button is held so dragging works.
+        set_mouse_drag_tracking(true);
+        set_bracketed_paste(true);
         Ok(Self {
             _print_log_on_destruction: PrintLogOnDestruction,
             curses,
             cursor: (0, 0).into(),
             stalling_escape: false,
+            mouse_position: None,
+            pending_inputs: VecDeque::new(),
+            pasted_text: None,
+            resized: false,
+            screen: Screen::new(),
+        })
+    }
+
+    /// Erase the screen and repaint all of it on the next [`refresh`]
+    /// instead of just the changes.
+    ///
+    /// [`refresh`]: #method.refresh
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.curses
+            .window_mut()
+            .clear()
+            .map_err(|()| "Error: Curses clear()".to_string())
+    }
+
+    /// Move the terminal's cursor to the selected cursor and show
+    /// what has been drawn.
+    pub fn refresh(&mut self) -> Result<(), String> {
+        self.curses
+            .window_mut()
+            .move_to(self.cursor)
+            .map_err(|()| "Error: Curses mv()".to_string())?;
+        self.curses
+            .window_mut()
+            .refresh()
+            .map_err(|()| "Error: Curses refresh()".to_string())
+    }
+
+    fn read_input(&mut self) -> Option<pancurses::Input> {
+        self.pending_inputs
+            .pop_front()
+            .or_else(|| self.curses.window_mut().read_char())
+    }
+
+    /// Try to read the rest of the paste start sequence after an `ESC`.
+    ///
+    /// Each character is waited for up to [`PASTE_START_TIMEOUT`].  If
+    /// the sequence doesn't match, the inputs read are pushed back to
+    /// be handled as normal.
+    ///
+    /// [`PASTE_START_TIMEOUT`]: constant.PASTE_START_TIMEOUT.html
+    fn read_paste_start(&mut self) -> bool {
+        self.curses.window().set_timeout(Some(PASTE_START_TIMEOUT));
+        let matched = self.read_paste_start_sequence();
+        let _ = self.curses.window_mut().set_block_on_read(false);
+        matched
+    }
+
+    fn read_paste_start_sequence(&mut self) -> bool {
+        let mut read = Vec::new();
+        for expected in PASTE_START.chars() {
+            match self.read_input() {
+                Some(pancurses::Input::Character(c)) if c == expected => {
+                    read.push(pancurses::Input::Character(c))
+                }
+                input => {
+                    read.extend(input);
+                    for input in read.into_iter().rev() {
+                        self.pending_inputs.push_front(input);
+                    }
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// Read the pasted text until the paste end sequence.
+    fn read_paste(&mut self) -> Option<Input> {
+        // The terminal always ends a paste, so wait for all of it.
+        let _ = self.curses.window_mut().set_block_on_read(true);
+        let mut text = String::new();
+        while !text.ends_with(PASTE_END) {
+            match self.read_input() {
+                Some(pancurses::Input::Character(c)) => text.push(c),
+                Some(pancurses::Input::KeyEnter) => text.push('\n'),
+                Some(pancurses::Input::KeyBackspace) => text.push(127 as char),
+                Some(input) => log_debug(format!("Ignoring {:?} in paste", input)),
+                None => break,
+            }
+        }
+        let _ = self.curses.window_mut().set_block_on_read(false);
+        if text.ends_with(PASTE_END) {
+            let len = text.len() - PASTE_END.len();
+            text.truncate(len);
+        }
+        self.stalling_escape = false;
+        self.pasted_text = Some(convert_pasted_text(&text));
+        Some(Input {
+            control: false,
+            alt: false,
+            key: Key::Paste,
+        })
+    }
+
+    fn read_mouse(&mut self) -> Option<Input> {
+        let event = match self.curses.mouse_read() {
+            Ok(event) => event,
+            Err(()) => return None,
+        };
+        log_debug(format!(
+            "Mouse {:#x} at ({}, {})",
+            event.bstate, event.y, event.x
+        ));
+        let mouse = convert_to_mouse(event.bstate)?;
+        self.mouse_position = Some((event.y as usize, event.x as usize));
+        let alt = self.stalling_escape || event.bstate & pancurses::BUTTON_ALT != 0;
+        self.stalling_escape = false;
+        Some(Input {
+            control: event.bstate & pancurses::BUTTON_CTRL != 0,
+            alt,
+            key: Key::Mouse(mouse),
         })
     }
 }
 
+impl Drop for CursesRenderer {
+    fn drop(&mut self) {
+        set_mouse_drag_tracking(false);
+        set_bracketed_paste(false);
+    }
+}
+
+/// Turn the terminal's bracketed paste mode on or off.
+///
+/// This makes the terminal surround pasted text with `ESC [200~` and
+/// `ESC [201~` so we can insert it all at once instead of as keys.
+fn set_bracketed_paste(enable: bool) {
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b[?2004{}", if enable { 'h' } else { 'l' });
+    let _ = stdout.flush();
+}
+
+/// Terminals send newlines in pastes as carriage returns.
+fn convert_pasted_text(text: &str) -> String {
+    text.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+/// Turn xterm's button event tracking mode on or off.
+///
+/// curses only asks the terminal for presses and releases.
+fn set_mouse_drag_tracking(enable: bool) {
+    let mut stdout = std::io::stdout();
+    let _ = write!(stdout, "\x1b[?1002{}", if enable { 'h' } else { 'l' });
+    let _ = stdout.flush();
+}
+
 impl Renderer for CursesRenderer {
     fn show(
         &mut self,
         layout: &Layout,
         selected_window: Option<&Arc<Mutex<Window>>>,
+        mode_line: &ModeLine,
         message: Option<&str>,
     ) -> Result<(), String> {
-        let (rows, columns) = self.curses.window().size().into();
-        let rows = rows as usize;
-        let columns = columns as usize;
-        draw(self, layout, selected_window, rows, columns)?;
-        if let Some(message) = message {
-            self.set_attribute(rows - 3, 9, Attribute::Inverted)?;
-            self.putch(rows - 3, 9, Character::Character('.'))?;
-            self.set_attribute(rows - 2, 9, Attribute::Inverted)?;
-            self.putch(rows - 2, 9, Character::VLine)?;
-            for i in 10..=columns - 10 {
-                self.set_attribute(rows - 3, i, Attribute::Inverted)?;
-                self.putch(rows - 3, i, Character::HLine)?;
-                self.set_attribute(rows - 2, i, Attribute::Inverted)?;
-                self.putch(rows - 2, i, Character::Character(' '))?
-            }
-            self.set_attribute(rows - 3, columns - 9, Attribute::Inverted)?;
-            self.putch(rows - 3, columns - 9, Character::Character('.'))?;
-            self.set_attribute(rows - 2, columns - 9, Attribute::Inverted)?;
-            self.putch(rows - 2, columns - 9, Character::VLine)?;
-            draw_window(
-                self,
-                message.chars(),
-                false,
-                None,
-                None,
-                rows - 2,
-                10,
-                1,
-                columns - 20,
-            )?;
+        if self.resized {
+            self.clear()?;
+            self.screen.invalidate();
+            self.resized = false;
         }
-        self.curses
-            .window_mut()
-            .move_to(self.cursor)
-            .map_err(|()| "Error: Curses mv()".to_string())?;
-        self.curses
-            .window_mut()
-            .refresh()
-            .map_err(|()| "Error: Curses refresh()".to_string())?;
-        Ok(())
+        let (rows, columns) = self.size();
+        draw_with(self, rows, columns, |screen| {
+            draw_frame(screen, layout, selected_window, mode_line, message)
+        })?;
+        self.refresh()
     }
 
     fn getch(&mut self) -> Option<Input> {
-        match self.curses.window_mut().read_char() {
+        match self.read_input() {
             Some(pancurses::Input::Character(c)) if c == 27 as char => {
+                if self.read_paste_start() {
+                    return self.read_paste();
+                }
                 self.stalling_escape = true;
                 self.getch()
             }
+            Some(pancurses::Input::KeyMouse) => self.read_mouse(),
+            Some(pancurses::Input::KeyResize) => {
+                let _ = self.curses.resize_terminal(0, 0);
+                self.resized = true;
+                self.stalling_escape = false;
+                Some(Input {
+                    control: false,
+                    alt: false,
+                    key: Key::Resize,
+                })
+            }
+            Some(pancurses::Input::KeyBackspace) => {
+                let alt = self.stalling_escape;
+                self.stalling_escape = false;
+                Some(Input {
+                    control: false,
+                    alt,
+                    key: BACKSPACE,
+                })
+            }
             Some(pancurses::Input::Character(c)) => {
                 let ch = convert_to_key(c, self.stalling_escape);
                 self.stalling_escape = false;
@@ -106,6 +280,61 @@ impl Renderer for CursesRenderer {
             }
         }
     }
+
+    fn size(&self) -> (usize, usize) {
+        let (rows, columns) = self.curses.window().size().into();
+        (rows as usize, columns as usize)
+    }
+
+    fn mouse_position(&self) -> Option<(usize, usize)> {
+        self.mouse_position
+    }
+
+    fn take_pasted_text(&mut self) -> Option<String> {
+        self.pasted_text.take()
+    }
+
+    fn input_fd(&self) -> Option<RawFd> {
+        Some(libc::STDIN_FILENO)
+    }
+}
+
+fn convert_to_mouse(bstate: pancurses::MouseMask) -> Option<Mouse> {
+    let buttons = [
+        (
+            1,
+            pancurses::BUTTON1_PRESSED | pancurses::BUTTON1_CLICKED,
+            pancurses::BUTTON1_RELEASED,
+        ),
+        (
+            2,
+            pancurses::BUTTON2_PRESSED | pancurses::BUTTON2_CLICKED,
+            pancurses::BUTTON2_RELEASED,
+        ),
+        (
+            3,
+            pancurses::BUTTON3_PRESSED | pancurses::BUTTON3_CLICKED,
+            pancurses::BUTTON3_RELEASED,
+        ),
+    ];
+    if bstate & pancurses::BUTTON4_PRESSED != 0 {
+        return Some(Mouse::WheelUp);
+    }
+    if bstate & pancurses::BUTTON5_PRESSED != 0 {
+        return Some(Mouse::WheelDown);
+    }
+    for &(button, pressed, released) in &buttons {
+        if bstate & pressed != 0 {
+            return Some(Mouse::Press(button));
+        }
+        if bstate & released != 0 {
+            return Some(Mouse::Release(button));
+        }
+    }
+    if bstate & pancurses::REPORT_MOUSE_POSITION != 0 {
+        return Some(Mouse::Drag(1));
+    }
+    None
 }
 
 fn convert_to_key(c: char, alt: bool) -> Input {
@@ -137,6 +366,10 @@ fn convert_to_key(c: char, alt: bool) -> Input {
 }
 
 impl DrawableRenderer for CursesRenderer {
+    fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
     fn erase(&mut self) -> Result<(), String> {
         self.curses
             .window_mut()
@@ -150,10 +383,17 @@ impl DrawableRenderer for CursesRenderer {
             Character::VLine => '|',
             Character::HLine => '-',
         };
-        self.curses
-            .window_mut()
-            .move_put_char((y as i32, x as i32), c)
-            .map_err(|()| "Error: Curses putch()".to_string())
+        let p = (y as i32, x as i32);
+        if c.is_ascii() {
+            self.curses.window_mut().move_put_char(p, c)
+        } else {
+            // Characters outside of ASCII have to be written as UTF-8.
+            let mut bytes = [0; 4];
+            self.curses
+                .window_mut()
+                .move_put_str(p, c.encode_utf8(&mut bytes))
+        }
+        .map_err(|()| "Error: Curses putch()".to_string())
     }
 
     fn set_attribute(&mut self, y: usize, x: usize, at: Attribute) -> Result<(), String> {
@@ -163,11 +403,21 @@ impl DrawableRenderer for CursesRenderer {
                 Ok(())
             }
             Attribute::UnselectedCursor => Ok(()),
-            Attribute::Inverted => self
+            Attribute::Inverted | Attribute::UnselectedModeLine => self
                 .curses
                 .window_mut()
                 .move_change_attributes((y as i32, x as i32), 1, pancurses::Attribute::Reverse, 0)
                 .map_err(|()| "Error: Curses mvchgat()".to_string()),
+            Attribute::SelectedModeLine => self
+                .curses
+                .window_mut()
+                .move_change_attributes(
+                    (y as i32, x as i32),
+                    1,
+                    pancurses::Attribute::Reverse | pancurses::Attribute::Bold,
+                    0,
+                )
+                .map_err(|()| "Error: Curses mvchgat()".to_string()),
         }
     }
 }
@@ -189,4 +439,30 @@ mod tests {
         assert_eq!(convert_to_key(1 as char, true), kbd("C-A-a"));
         assert_eq!(convert_to_key(2 as char, false), kbd("C-b"));
     }
+
+    #[test]
+    fn convert_to_mouse_1() {
+        assert_eq!(
+            convert_to_mouse(pancurses::BUTTON1_PRESSED),
+            Some(Mouse::Press(1))
+        );
+        assert_eq!(
+            convert_to_mouse(pancurses::BUTTON3_RELEASED | pancurses::BUTTON_CTRL),
+            Some(Mouse::Release(3))
+        );
+        assert_eq!(
+            convert_to_mouse(pancurses::REPORT_MOUSE_POSITION),
+            Some(Mouse::Drag(1))
+        );
+        assert_eq!(
+            convert_to_mouse(pancurses::BUTTON4_PRESSED),
+            Some(Mouse::WheelUp)
+        );
+        assert_eq!(convert_to_mouse(0), None);
+    }
+
+    #[test]
+    fn convert_pasted_text_1() {
+        assert_eq!(convert_pasted_text("a\r\nb\rc\n"), "a\nb\nc\n");
+    }
 }
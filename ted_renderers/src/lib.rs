@@ -1,6 +1,19 @@
+extern crate libc;
 extern crate pancurses_result;
 extern crate parking_lot;
 extern crate ted_core;
 
 mod curses_renderer;
 pub use curses_renderer::*;
+
+mod remote_protocol;
+mod remote_renderer;
+
+mod stdin;
+pub use stdin::*;
+
+mod server;
+pub use server::*;
+
+mod client;
+pub use client::*;
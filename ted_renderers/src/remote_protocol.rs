@@ -0,0 +1,376 @@
+//! The protocol between a ted server and its clients.
+//!
+//! Each message is sent on its own line as words separated by
+//! spaces.  Strings are escaped so they never contain spaces or
+//! newlines.
+
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::str::SplitWhitespace;
+use ted_core::draw::{Attribute, Character};
+use ted_core::*;
+
+/// A message sent from a client to the server.
+#[derive(Debug, PartialEq)]
+pub enum Request {
+    /// The first message sent by a client.
+    Hello {
+        rows: usize,
+        columns: usize,
+        file: Option<PathBuf>,
+    },
+    /// The client's terminal was resized.
+    Resize { rows: usize, columns: usize },
+    /// The user typed in the client's terminal.
+    Input {
+        input: Input,
+        mouse_position: Option<(usize, usize)>,
+        pasted_text: Option<String>,
+    },
+}
+
+/// A message sent from the server to a client telling it how to
+/// update its terminal.
+#[derive(Debug, PartialEq)]
+pub enum Update {
+    Erase,
+    Putch(usize, usize, Character),
+    SetAttribute(usize, usize, Attribute),
+    ClearCursors,
+    /// The frame is done being drawn.
+    Refresh,
+    /// The client's `Frame` was closed.
+    Quit,
+}
+
+impl Request {
+    pub fn encode(&self) -> String {
+        match self {
+            Request::Hello {
+                rows,
+                columns,
+                file,
+            } => format!(
+                "hello {} {} {}",
+                rows,
+                columns,
+                encode_option(
+                    file.as_ref()
+                        .map(|file| file.to_string_lossy().into_owned())
+                        .as_ref()
+                )
+            ),
+            Request::Resize { rows, columns } => format!("resize {} {}", rows, columns),
+            Request::Input {
+                input,
+                mouse_position,
+                pasted_text,
+            } => format!(
+                "input {} {} {}",
+                encode_input(input),
+                match mouse_position {
+                    Some((y, x)) => format!("{},{}", y, x),
+                    None => "-".to_string(),
+                },
+                encode_option(pasted_text.as_ref())
+            ),
+        }
+    }
+
+    pub fn decode(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let request = match words.next() {
+            Some("hello") => Request::Hello {
+                rows: parse(words.next())?,
+                columns: parse(words.next())?,
+                file: decode_option(words.next())?.map(PathBuf::from),
+            },
+            Some("resize") => Request::Resize {
+                rows: parse(words.next())?,
+                columns: parse(words.next())?,
+            },
+            Some("input") => Request::Input {
+                input: decode_input(words.next())?,
+                mouse_position: match words.next() {
+                    Some("-") => None,
+                    Some(position) => {
+                        let mut position = position.split(',');
+                        Some((parse(position.next())?, parse(position.next())?))
+                    }
+                    None => return Err(invalid()),
+                },
+                pasted_text: decode_option(words.next())?,
+            },
+            _ => return Err(invalid()),
+        };
+        end(words)?;
+        Ok(request)
+    }
+}
+
+impl Update {
+    pub fn encode(&self) -> String {
+        match self {
+            Update::Erase => "erase".to_string(),
+            Update::Putch(y, x, ch) => format!(
+                "putch {} {} {}",
+                y,
+                x,
+                match ch {
+                    Character::Character(c) => (*c as u32).to_string(),
+                    Character::VLine => "vline".to_string(),
+                    Character::HLine => "hline".to_string(),
+                }
+            ),
+            Update::SetAttribute(y, x, at) => format!("attribute {} {} {:?}", y, x, at),
+            Update::ClearCursors => "clear_cursors".to_string(),
+            Update::Refresh => "refresh".to_string(),
+            Update::Quit => "quit".to_string(),
+        }
+    }
+
+    pub fn decode(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let update = match words.next() {
+            Some("erase") => Update::Erase,
+            Some("putch") => {
+                let y = parse(words.next())?;
+                let x = parse(words.next())?;
+                let ch = match words.next() {
+                    Some("vline") => Character::VLine,
+                    Some("hline") => Character::HLine,
+                    word => Character::Character(parse_char(word)?),
+                };
+                Update::Putch(y, x, ch)
+            }
+            Some("attribute") => {
+                let y = parse(words.next())?;
+                let x = parse(words.next())?;
+                let at = match words.next() {
+                    Some("SelectedCursor") => Attribute::SelectedCursor,
+                    Some("UnselectedCursor") => Attribute::UnselectedCursor,
+                    Some("Inverted") => Attribute::Inverted,
+                    Some("SelectedModeLine") => Attribute::SelectedModeLine,
+                    Some("UnselectedModeLine") => Attribute::UnselectedModeLine,
+                    _ => return Err(invalid()),
+                };
+                Update::SetAttribute(y, x, at)
+            }
+            Some("clear_cursors") => Update::ClearCursors,
+            Some("refresh") => Update::Refresh,
+            Some("quit") => Update::Quit,
+            _ => return Err(invalid()),
+        };
+        end(words)?;
+        Ok(update)
+    }
+}
+
+/// Write `line` followed by a newline.
+pub fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<(), String> {
+    writeln!(writer, "{}", line).map_err(|e| e.to_string())
+}
+
+/// Read the next line, or `None` if the connection was closed.
+pub fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(line)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn invalid() -> String {
+    "Error: Invalid message".to_string()
+}
+
+fn end(mut words: SplitWhitespace) -> Result<(), String> {
+    match words.next() {
+        Some(_) => Err(invalid()),
+        None => Ok(()),
+    }
+}
+
+fn parse<T: ::std::str::FromStr>(word: Option<&str>) -> Result<T, String> {
+    word.and_then(|word| word.parse().ok()).ok_or_else(invalid)
+}
+
+fn parse_char(word: Option<&str>) -> Result<char, String> {
+    ::std::char::from_u32(parse(word)?).ok_or_else(invalid)
+}
+
+fn encode_input(input: &Input) -> String {
+    let key = match input.key {
+        Key::Key(c) => format!("k{}", c as u32),
+        Key::Function(function) => format!("f{}", function),
+        Key::Mouse(Mouse::Press(button)) => format!("p{}", button),
+        Key::Mouse(Mouse::Release(button)) => format!("r{}", button),
+        Key::Mouse(Mouse::Drag(button)) => format!("d{}", button),
+        Key::Mouse(Mouse::WheelUp) => "u".to_string(),
+        Key::Mouse(Mouse::WheelDown) => "w".to_string(),
+        Key::Paste => "v".to_string(),
+        Key::Resize => "z".to_string(),
+    };
+    format!(
+        "{}{}{}",
+        if input.control { "C" } else { "" },
+        if input.alt { "A" } else { "" },
+        key
+    )
+}
+
+fn decode_input(word: Option<&str>) -> Result<Input, String> {
+    let mut word = word.ok_or_else(invalid)?;
+    let control = word.starts_with('C');
+    if control {
+        word = &word[1..];
+    }
+    let alt = word.starts_with('A');
+    if alt {
+        word = &word[1..];
+    }
+    if word.is_empty() {
+        return Err(invalid());
+    }
+    let (kind, rest) = word.split_at(1);
+    let rest = Some(rest).filter(|rest| !rest.is_empty());
+    let key = match kind {
+        "k" => Key::Key(parse_char(rest)?),
+        "f" => Key::Function(parse(rest)?),
+        "p" => Key::Mouse(Mouse::Press(parse(rest)?)),
+        "r" => Key::Mouse(Mouse::Release(parse(rest)?)),
+        "d" => Key::Mouse(Mouse::Drag(parse(rest)?)),
+        "u" => Key::Mouse(Mouse::WheelUp),
+        "w" => Key::Mouse(Mouse::WheelDown),
+        "v" => Key::Paste,
+        "z" => Key::Resize,
+        _ => return Err(invalid()),
+    };
+    Ok(Input { control, alt, key })
+}
+
+fn encode_option(s: Option<&String>) -> String {
+    match s {
+        Some(s) => {
+            let mut encoded = "+".to_string();
+            for c in s.chars() {
+                match c {
+                    '\\' => encoded.push_str("\\\\"),
+                    ' ' => encoded.push_str("\\s"),
+                    '\t' => encoded.push_str("\\t"),
+                    '\n' => encoded.push_str("\\n"),
+                    '\r' => encoded.push_str("\\r"),
+                    c => encoded.push(c),
+                }
+            }
+            encoded
+        }
+        None => "-".to_string(),
+    }
+}
+
+fn decode_option(word: Option<&str>) -> Result<Option<String>, String> {
+    let word = word.ok_or_else(invalid)?;
+    if word == "-" {
+        return Ok(None);
+    }
+    if !word.starts_with('+') {
+        return Err(invalid());
+    }
+    let mut decoded = String::new();
+    let mut chars = word[1..].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            decoded.push(match chars.next() {
+                Some('\\') => '\\',
+                Some('s') => ' ',
+                Some('t') => '\t',
+                Some('n') => '\n',
+                Some('r') => '\r',
+                _ => return Err(invalid()),
+            });
+        } else {
+            decoded.push(c);
+        }
+    }
+    Ok(Some(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_round_trip() {
+        let requests = vec![
+            Request::Hello {
+                rows: 24,
+                columns: 80,
+                file: Some(PathBuf::from("/tmp/a file\\with\nodd names")),
+            },
+            Request::Hello {
+                rows: 1,
+                columns: 2,
+                file: None,
+            },
+            Request::Resize {
+                rows: 30,
+                columns: 100,
+            },
+            Request::Input {
+                input: kbd("C-A-x"),
+                mouse_position: None,
+                pasted_text: None,
+            },
+            Request::Input {
+                input: kbd("MouseRelease3"),
+                mouse_position: Some((4, 7)),
+                pasted_text: None,
+            },
+            Request::Input {
+                input: kbd("Paste"),
+                mouse_position: None,
+                pasted_text: Some("fn main() {\n\t \n}".to_string()),
+            },
+            Request::Input {
+                input: kbd("F1"),
+                mouse_position: None,
+                pasted_text: Some(String::new()),
+            },
+        ];
+        for request in requests {
+            let line = request.encode();
+            assert!(!line.contains('\n'));
+            assert_eq!(Request::decode(&line), Ok(request));
+        }
+    }
+
+    #[test]
+    fn updates_round_trip() {
+        let updates = vec![
+            Update::Erase,
+            Update::Putch(1, 2, Character::Character(' ')),
+            Update::Putch(1, 2, Character::Character('\u{4e2d}')),
+            Update::Putch(3, 4, Character::VLine),
+            Update::SetAttribute(5, 6, Attribute::SelectedModeLine),
+            Update::ClearCursors,
+            Update::Refresh,
+            Update::Quit,
+        ];
+        for update in updates {
+            assert_eq!(Update::decode(&update.encode()), Ok(update));
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(Request::decode("").is_err());
+        assert!(Request::decode("resize 1").is_err());
+        assert!(Request::decode("resize 1 2 3").is_err());
+        assert!(Request::decode("input q - -").is_err());
+        assert!(Request::decode("hello 1 2 file").is_err());
+        assert!(Update::decode("putch 1 2 x").is_err());
+        assert!(Update::decode("attribute 1 2 Bold").is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use parking_lot::Mutex;
+use remote_protocol::*;
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::mem;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use ted_core::draw::*;
+use ted_core::*;
+
+/// An [`Input`] along with the mouse position and pasted text sent
+/// with it.
+///
+/// [`Input`]: ../ted_core/struct.Input.html
+type RemoteInput = (Input, Option<(usize, usize)>, Option<String>);
+
+/// What a client has sent that its [`RemoteRenderer`] hasn't used
+/// yet.
+///
+/// [`RemoteRenderer`]: struct.RemoteRenderer.html
+pub struct RemoteEvents {
+    inputs: VecDeque<RemoteInput>,
+    size: (usize, usize),
+    /// Written to whenever an input is queued so the UI thread wakes
+    /// up.  See [`Renderer::input_fd`].
+    ///
+    /// [`Renderer::input_fd`]: ../ted_core/trait.Renderer.html#method.input_fd
+    wake: Option<UnixStream>,
+}
+
+impl RemoteEvents {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        RemoteEvents {
+            inputs: VecDeque::new(),
+            size: (rows, columns),
+            wake: None,
+        }
+    }
+
+    /// Queue up a [`Request`] from the client.
+    ///
+    /// [`Request`]: enum.Request.html
+    pub fn push(&mut self, request: Request) -> Result<(), String> {
+        match request {
+            Request::Hello { .. } => return Err("Error: Client said hello twice".to_string()),
+            Request::Resize { rows, columns } => {
+                self.size = (rows, columns);
+                self.inputs.push_back((kbd("Resize"), None, None));
+            }
+            Request::Input {
+                input,
+                mouse_position,
+                pasted_text,
+            } => self.inputs.push_back((input, mouse_position, pasted_text)),
+        }
+        if let Some(ref mut wake) = self.wake {
+            // The UI thread only needs one byte to wake up, so a full
+            // socket is fine.
+            let _ = wake.write(&[0]);
+        }
+        Ok(())
+    }
+}
+
+/// How many shown frames may be waiting to be written to a client
+/// before it is disconnected for falling behind.
+const MAX_QUEUED_FRAMES: usize = 16;
+
+/// A [`Renderer`] that draws to the terminal of a client connected
+/// to the server.
+///
+/// The client sends its [`Input`]s, which are read into the
+/// [`RemoteEvents`] on another thread.  Only the changes to the
+/// screen are sent back.  They are written by a thread per client so
+/// a slow client can't hold up drawing.  A client that falls too far
+/// behind is disconnected.
+///
+/// [`Renderer`]: ../ted_core/trait.Renderer.html
+/// [`Input`]: ../ted_core/struct.Input.html
+/// [`RemoteEvents`]: struct.RemoteEvents.html
+pub struct RemoteRenderer {
+    stream: UnixStream,
+    /// The encoded updates of the frame being drawn.
+    updates: String,
+    /// Sends the updates of each shown frame to the writer thread.
+    writer: SyncSender<String>,
+    events: Arc<Mutex<RemoteEvents>>,
+    /// Readable when [`RemoteEvents`] has queued an input.
+    ///
+    /// [`RemoteEvents`]: struct.RemoteEvents.html
+    woken: UnixStream,
+    mouse_position: Option<(usize, usize)>,
+    pasted_text: Option<String>,
+    shown_size: (usize, usize),
+    disconnected: Arc<AtomicBool>,
+    screen: Screen,
+}
+
+impl RemoteRenderer {
+    pub fn new(stream: UnixStream, events: Arc<Mutex<RemoteEvents>>) -> Result<Self, String> {
+        let (wake, woken) = UnixStream::pair().map_err(|e| e.to_string())?;
+        wake.set_nonblocking(true).map_err(|e| e.to_string())?;
+        woken.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let (writer, frames) = sync_channel(MAX_QUEUED_FRAMES);
+        let disconnected = Arc::new(AtomicBool::new(false));
+        {
+            let stream = stream.try_clone().map_err(|e| e.to_string())?;
+            let disconnected = disconnected.clone();
+            thread::Builder::new()
+                .name("ted client writer".to_string())
+                .spawn(move || write_frames(stream, &frames, &disconnected))
+                .map_err(|e| e.to_string())?;
+        }
+        events.lock().wake = Some(wake);
+        Ok(RemoteRenderer {
+            stream,
+            updates: String::new(),
+            writer,
+            events,
+            woken,
+            mouse_position: None,
+            pasted_text: None,
+            shown_size: (0, 0),
+            disconnected,
+            screen: Screen::new(),
+        })
+    }
+
+    fn is_disconnected(&self) -> bool {
+        self.disconnected.load(Ordering::SeqCst)
+    }
+
+    fn send(&mut self, update: Update) -> Result<(), String> {
+        if self.is_disconnected() {
+            return Err("Error: Client disconnected".to_string());
+        }
+        self.updates.push_str(&update.encode());
+        self.updates.push('\n');
+        Ok(())
+    }
+
+    /// Queue the updates sent so far to be written to the client.
+    fn flush(&mut self) -> Result<(), String> {
+        let updates = mem::take(&mut self.updates);
+        match self.writer.try_send(updates) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.disconnect();
+                Err("Error: Client fell behind".to_string())
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.disconnect();
+                Err("Error: Client disconnected".to_string())
+            }
+        }
+    }
+
+    /// Close the connection.  The server then removes the [`Frame`].
+    ///
+    /// [`Frame`]: ../ted_core/struct.Frame.html
+    fn disconnect(&mut self) {
+        self.disconnected.store(true, Ordering::SeqCst);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// Write each frame received to the client until the
+/// [`RemoteRenderer`] is dropped or writing fails, and then close the
+/// connection.
+///
+/// [`RemoteRenderer`]: struct.RemoteRenderer.html
+fn write_frames(mut stream: UnixStream, frames: &Receiver<String>, disconnected: &AtomicBool) {
+    for updates in frames {
+        if stream.write_all(updates.as_bytes()).is_err() {
+            disconnected.store(true, Ordering::SeqCst);
+            break;
+        }
+    }
+    let _ = stream.shutdown(Shutdown::Both);
+}
+
+impl Drop for RemoteRenderer {
+    fn drop(&mut self) {
+        // The writer thread closes the connection once the rest of
+        // the queue and this are written.
+        self.updates.clear();
+        if self.send(Update::Quit).is_ok() {
+            let _ = self.flush();
+        }
+    }
+}
+
+impl Renderer for RemoteRenderer {
+    fn show(
+        &mut self,
+        layout: &Layout,
+        selected_window: Option<&Arc<Mutex<Window>>>,
+        mode_line: &ModeLine,
+        message: Option<&str>,
+    ) -> Result<(), String> {
+        if self.is_disconnected() {
+            return Ok(());
+        }
+        let (rows, columns) = self.size();
+        if self.shown_size != (rows, columns) {
+            // The client's terminal lost its contents when resizing.
+            self.screen.invalidate();
+            self.shown_size = (rows, columns);
+        }
+        let result = draw_with(self, rows, columns, |screen| {
+            draw_frame(screen, layout, selected_window, mode_line, message)
+        })
+        .and_then(|()| self.send(Update::Refresh))
+        .and_then(|()| self.flush());
+        if self.is_disconnected() {
+            // The server keeps running when a client goes away.
+            log(format!("Client disconnected: {:?}", result));
+            return Ok(());
+        }
+        result
+    }
+
+    fn getch(&mut self) -> Option<Input> {
+        let mut events = self.events.lock();
+        if events.inputs.is_empty() {
+            // Everything queued so far has been read, so the bytes
+            // waking us up for it can go.
+            let mut buffer = [0; 64];
+            while let Ok(n) = self.woken.read(&mut buffer) {
+                if n == 0 {
+                    break;
+                }
+            }
+        }
+        let (input, mouse_position, pasted_text) = events.inputs.pop_front()?;
+        drop(events);
+        self.mouse_position = mouse_position;
+        self.pasted_text = pasted_text;
+        Some(input)
+    }
+
+    fn size(&self) -> (usize, usize) {
+        self.events.lock().size
+    }
+
+    fn mouse_position(&self) -> Option<(usize, usize)> {
+        self.mouse_position
+    }
+
+    fn take_pasted_text(&mut self) -> Option<String> {
+        self.pasted_text.take()
+    }
+
+    fn input_fd(&self) -> Option<RawFd> {
+        Some(self.woken.as_raw_fd())
+    }
+}
+
+impl DrawableRenderer for RemoteRenderer {
+    fn screen(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    fn erase(&mut self) -> Result<(), String> {
+        self.send(Update::Erase)
+    }
+
+    fn putch(&mut self, y: usize, x: usize, ch: Character) -> Result<(), String> {
+        self.send(Update::Putch(y, x, ch))
+    }
+
+    fn set_attribute(&mut self, y: usize, x: usize, at: Attribute) -> Result<(), String> {
+        self.send(Update::SetAttribute(y, x, at))
+    }
+
+    fn clear_cursors(&mut self) -> Result<(), String> {
+        self.send(Update::ClearCursors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slow_client_is_disconnected() {
+        let (stream, _client) = UnixStream::pair().unwrap();
+        let events = Arc::new(Mutex::new(RemoteEvents::new(3, 10)));
+        let mut renderer = RemoteRenderer::new(stream, events).unwrap();
+        // The client never reads, so the writer thread gets stuck on
+        // the first frame and the rest pile up.
+        let frame = "x".repeat(1 << 20);
+        let mut result = Ok(());
+        for _ in 0..MAX_QUEUED_FRAMES + 2 {
+            renderer.updates.push_str(&frame);
+            result = renderer.flush();
+            if result.is_err() {
+                break;
+            }
+        }
+        assert_eq!(result, Err("Error: Client fell behind".to_string()));
+        assert!(renderer.is_disconnected());
+        assert!(renderer.send(Update::Refresh).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use parking_lot::Mutex;
+use remote_protocol::*;
+use remote_renderer::*;
+use std::fs;
+use std::io::{self, BufReader};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::thread;
+use ted_core::*;
+
+/// Get the path of the socket the server listens on.
+///
+/// This is `ted.socket` in `$XDG_RUNTIME_DIR` if it is set.
+/// Otherwise it is in a directory in the temporary directory named
+/// after the user, which is created if needed.  Since anyone can
+/// write to the temporary directory, it is an error if that
+/// directory isn't private to the current user.
+pub fn default_socket_path() -> Result<PathBuf, String> {
+    let dir = match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let dir = std::env::temp_dir().join(format!("ted-{}", current_uid()));
+            create_private_dir(&dir)?;
+            dir
+        }
+    };
+    Ok(dir.join("ted.socket"))
+}
+
+fn current_uid() -> libc::uid_t {
+    unsafe { libc::getuid() }
+}
+
+/// Create the directory `dir` so that only the current user can use
+/// it, or check that it already is.
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => (),
+        Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => (),
+        Err(e) => return Err(format!("Error: Couldn't create {}: {}", dir.display(), e)),
+    }
+    // Don't follow symbolic links so another user can't point it
+    // somewhere else.
+    let metadata = fs::symlink_metadata(dir).map_err(|e| e.to_string())?;
+    if !metadata.is_dir() || metadata.uid() != current_uid() || metadata.mode() & 0o077 != 0 {
+        return Err(format!(
+            "Error: {} isn't a directory only you can use",
+            dir.display()
+        ));
+    }
+    Ok(())
+}
+
+/// Get the user id of the process on the other end of `stream`.
+#[cfg(target_os = "linux")]
+fn peer_uid(stream: &UnixStream) -> Result<libc::uid_t, String> {
+    let mut credentials = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut credentials as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    Ok(credentials.uid)
+}
+
+/// Get the user id of the process on the other end of `stream`.
+#[cfg(not(target_os = "linux"))]
+fn peer_uid(stream: &UnixStream) -> Result<libc::uid_t, String> {
+    let mut uid = 0;
+    let mut gid = 0;
+    let result = unsafe { libc::getpeereid(stream.as_raw_fd(), &mut uid, &mut gid) };
+    if result != 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    Ok(uid)
+}
+
+/// A running server.  The socket is removed when it is dropped.
+///
+/// See [`start_server`].
+///
+/// [`start_server`]: fn.start_server.html
+#[derive(Debug)]
+pub struct Server {
+    path: PathBuf,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Listen for clients on the Unix socket at `path`.
+///
+/// Only clients run by the current user are accepted.  Each client
+/// that connects gets a new [`Frame`] that draws to its terminal.  The [`Frame`] starts out showing the selected
+/// [`Buffer`].  If the client asks for a file, `open_file` is called
+/// to open it in the new [`Frame`] after it is selected.  The
+/// [`Frame`] is removed when the client disconnects.
+///
+/// [`Frame`]: ../ted_core/struct.Frame.html
+/// [`Buffer`]: ../ted_core/struct.Buffer.html
+pub fn start_server<F>(
+    state: &Arc<Mutex<State>>,
+    path: &Path,
+    open_file: F,
+) -> Result<Server, String>
+where
+    F: Fn(&Mutex<State>, &Path) -> Result<(), String> + Send + Sync + 'static,
+{
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!(
+                "Error: A server is already running at {}",
+                path.display()
+            ));
+        }
+        // The last server didn't clean up after itself.
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    let listener = UnixListener::bind(path).map_err(|e| e.to_string())?;
+    let server = Server {
+        path: path.to_path_buf(),
+    };
+    let state = state.clone();
+    let open_file = Arc::new(open_file);
+    thread::Builder::new()
+        .name("ted server".to_string())
+        .spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        log_error(format!("Server: {}", e));
+                        continue;
+                    }
+                };
+                match peer_uid(&stream) {
+                    Ok(uid) if uid == current_uid() => (),
+                    Ok(uid) => {
+                        log_error(format!("Server: Refused a client run by user {}", uid));
+                        continue;
+                    }
+                    Err(message) => {
+                        log_error(format!("Server: {}", message));
+                        continue;
+                    }
+                }
+                let state = state.clone();
+                let open_file = open_file.clone();
+                let result =
+                    thread::Builder::new()
+                        .name("ted client".to_string())
+                        .spawn(move || {
+                            if let Err(message) = handle_client(&state, stream, &*open_file) {
+                                log_error(format!("Client: {}", message));
+                            }
+                        });
+                if let Err(e) = result {
+                    log_error(format!("Server: {}", e));
+                }
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    Ok(server)
+}
+
+fn handle_client<F>(
+    state: &Arc<Mutex<State>>,
+    stream: UnixStream,
+    open_file: &F,
+) -> Result<(), String>
+where
+    F: Fn(&Mutex<State>, &Path) -> Result<(), String>,
+{
+    let mut reader = BufReader::new(stream.try_clone().map_err(|e| e.to_string())?);
+    let (rows, columns, file) = match read_line(&mut reader)? {
+        Some(line) => match Request::decode(&line)? {
+            Request::Hello {
+                rows,
+                columns,
+                file,
+            } => (rows, columns, file),
+            _ => return Err("Error: Client didn't say hello".to_string()),
+        },
+        None => return Ok(()),
+    };
+    let events = Arc::new(Mutex::new(RemoteEvents::new(rows, columns)));
+    let renderer = RemoteRenderer::new(stream, events.clone())?;
+    // Only the Display owns the Frame so closing it disconnects the
+    // client.
+    let frame = add_frame(state, renderer);
+    if let Some(file) = file {
+        if let Err(message) = open_file(state, &file) {
+            if let Some(frame) = frame.upgrade() {
+                frame.lock().messages.add_error(message);
+            }
+        }
+    }
+    let result = read_requests(&mut reader, &events);
+    if let Some(frame) = frame.upgrade() {
+        let _ = state.lock().display.remove_frame(&frame);
+    }
+    result
+}
+
+fn add_frame(state: &Mutex<State>, renderer: RemoteRenderer) -> Weak<Mutex<Frame>> {
+    let mut state = state.lock();
+    let (window, mode_line) = {
+        let selected_frame = state.display.selected_frame.lock();
+        (
+            Window::from(selected_frame.selected_window_buffer()),
+            selected_frame.mode_line.clone(),
+        )
+    };
+    let mut frame = Frame::new(Arc::new(Mutex::new(window)), Box::new(renderer));
+    frame.mode_line = mode_line;
+    let frame = Arc::new(Mutex::new(frame));
+    state.display.frames.push(frame.clone());
+    state.display.selected_frame = frame.clone();
+    Arc::downgrade(&frame)
+}
+
+fn read_requests(
+    reader: &mut BufReader<UnixStream>,
+    events: &Mutex<RemoteEvents>,
+) -> Result<(), String> {
+    while let Some(line) = read_line(reader)? {
+        events.lock().push(Request::decode(&line)?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libc;
+    use std::collections::VecDeque;
+    use std::io::BufRead;
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::io::RawFd;
+    use std::time::{Duration, Instant};
+    use ted_core::draw::Character;
+
+    fn wait_until<F: FnMut() -> bool>(mut f: F) {
+        let start = Instant::now();
+        while !f() {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn send(stream: &mut UnixStream, request: Request) {
+        write_line(stream, &request.encode()).unwrap();
+    }
+
+    #[test]
+    fn client_gets_its_own_frame() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let path = std::env::temp_dir().join(format!("ted-test-{}.socket", std::process::id()));
+        let server = start_server(&state, &path, |state, path| {
+            let buffer = Buffer::new_with_contents(path.into(), "opened");
+            let window = Arc::new(Mutex::new(Window::from(buffer)));
+            let selected_frame = state.lock().display.selected_frame.clone();
+            selected_frame.lock().replace_selected_window(window);
+            Ok(())
+        })
+        .unwrap();
+        assert!(start_server(&state, &path, |_, _| Ok(())).is_err());
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        send(
+            &mut client,
+            Request::Hello {
+                rows: 3,
+                columns: 10,
+                file: Some(PathBuf::from("/file")),
+            },
+        );
+        wait_until(|| {
+            let buffer = state.lock().display.selected_window_buffer();
+            let is_opened = buffer.lock().to_string() == "opened";
+            is_opened
+        });
+        assert_eq!(state.lock().display.frames.len(), 2);
+
+        send(
+            &mut client,
+            Request::Input {
+                input: kbd("x"),
+                mouse_position: None,
+                pasted_text: None,
+            },
+        );
+        let mut inputs = VecDeque::new();
+        let event = loop {
+            if let Some(event) = read_input_event(&state.lock().display) {
+                break event;
+            }
+        };
+        // Input from the client selects its frame.
+        let first_frame = state.lock().display.frames[0].clone();
+        state.lock().display.selected_frame = first_frame;
+        handle_input_event(&state, &mut inputs, event).unwrap();
+        {
+            let state = state.lock();
+            assert!(Arc::ptr_eq(
+                &state.display.selected_frame,
+                &state.display.frames[1]
+            ));
+        }
+        let buffer = state.lock().display.selected_window_buffer();
+        assert_eq!(buffer.lock().to_string(), "xopened");
+
+        state.lock().display.show().unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+        let mut updates = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let update = Update::decode(&line).unwrap();
+            if update == Update::Refresh {
+                break;
+            }
+            updates.push(update);
+        }
+        assert_eq!(updates[0], Update::Erase);
+        assert!(updates.contains(&Update::Putch(0, 0, Character::Character('x'))));
+
+        client.shutdown(::std::net::Shutdown::Both).unwrap();
+        wait_until(|| state.lock().display.frames.len() == 1);
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    fn is_readable(fd: RawFd, timeout: libc::c_int) -> bool {
+        let mut fds = [libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout) == 1 }
+    }
+
+    #[test]
+    fn client_input_wakes_frame() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let path =
+            std::env::temp_dir().join(format!("ted-test-wake-{}.socket", std::process::id()));
+        let _server = start_server(&state, &path, |_, _| Ok(())).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        send(
+            &mut client,
+            Request::Hello {
+                rows: 3,
+                columns: 10,
+                file: None,
+            },
+        );
+        wait_until(|| state.lock().display.frames.len() == 2);
+        let frame = state.lock().display.frames[1].clone();
+        let fd = frame.lock().renderer.input_fd().unwrap();
+        assert!(!is_readable(fd, 0));
+
+        send(
+            &mut client,
+            Request::Input {
+                input: kbd("x"),
+                mouse_position: None,
+                pasted_text: None,
+            },
+        );
+        assert!(is_readable(fd, 10000));
+        assert_eq!(frame.lock().getch(), Some(kbd("x")));
+        assert_eq!(frame.lock().getch(), None);
+        assert!(!is_readable(fd, 0));
+    }
+
+    #[test]
+    fn socket_directory_is_private() {
+        let dir = std::env::temp_dir().join(format!("ted-test-dir-{}", std::process::id()));
+        create_private_dir(&dir).unwrap();
+        assert_eq!(fs::metadata(&dir).unwrap().mode() & 0o777, 0o700);
+        // Creating it again is fine.
+        create_private_dir(&dir).unwrap();
+
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(create_private_dir(&dir).is_err());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn peer_is_current_user() {
+        let (a, _b) = UnixStream::pair().unwrap();
+        assert_eq!(peer_uid(&a), Ok(current_uid()));
+    }
+
+    #[test]
+    fn closing_frame_disconnects_client() {
+        let state = Arc::new(Mutex::new(State::new(DebugRenderer::new())));
+        let path =
+            std::env::temp_dir().join(format!("ted-test-close-{}.socket", std::process::id()));
+        let _server = start_server(&state, &path, |_, _| Ok(())).unwrap();
+        let mut client = UnixStream::connect(&path).unwrap();
+        send(
+            &mut client,
+            Request::Hello {
+                rows: 3,
+                columns: 10,
+                file: None,
+            },
+        );
+        wait_until(|| state.lock().display.frames.len() == 2);
+        {
+            let mut state = state.lock();
+            let frame = state.display.selected_frame.clone();
+            state.display.remove_frame(&frame).unwrap();
+            let frame = state.display.selected_frame.clone();
+            assert!(state.display.remove_frame(&frame).is_err());
+        }
+        let mut lines = BufReader::new(client).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "quit");
+        assert!(lines.next().is_none());
+    }
+}
@@ -0,0 +1,31 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+
+/// Take the text piped into ted as a [`File`].
+///
+/// Stdin is replaced with the controlling terminal so the
+/// [`CursesRenderer`] can still read keys.  This must be called
+/// before the [`CursesRenderer`] is created.
+///
+/// [`File`]: https://doc.rust-lang.org/std/fs/struct.File.html
+/// [`CursesRenderer`]: struct.CursesRenderer.html
+pub fn take_piped_stdin() -> Result<File, String> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        return Err("Error: Nothing is piped into stdin".to_string());
+    }
+    let tty = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .map_err(|e| format!("Error: Opening /dev/tty: {}", e))?;
+    let piped = unsafe { libc::dup(libc::STDIN_FILENO) };
+    if piped < 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    let piped = unsafe { File::from_raw_fd(piped) };
+    if unsafe { libc::dup2(tty.as_raw_fd(), libc::STDIN_FILENO) } < 0 {
+        return Err(io::Error::last_os_error().to_string());
+    }
+    Ok(piped)
+}
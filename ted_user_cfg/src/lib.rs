@@ -4,6 +4,7 @@ extern crate ted_git;
 extern crate ted_kill_ring;
 extern crate ted_mark;
 extern crate ted_other_window;
+extern crate ted_registers;
 
 use ted_common_commands::*;
 use ted_core::*;
@@ -11,6 +12,7 @@ use ted_git::*;
 use ted_kill_ring::*;
 use ted_mark::*;
 use ted_other_window::*;
+use ted_registers::*;
 
 pub fn setup_state(state: &mut State) -> Result<(), String> {
     let mut default_key_map = state.default_key_map.lock();
@@ -37,17 +39,105 @@ pub fn setup_state(state: &mut State) -> Result<(), String> {
     default_key_map.map(vec![kbd("C-x"), kbd("4"), kbd("f")], vec![kbd("C-x"), kbd("4"), kbd("C-f")]);
     default_key_map.bind(vec![kbd("C-x"), kbd("4"), kbd("C-f")], with_other_window_clockwise(open_file_command()));
     default_key_map.bind(vec![kbd("C-x"), kbd("0")], close_window_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("5"), kbd("0")], close_frame_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("C-c")], close_ted_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("C-f")], open_file_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("g")], git_open_repository_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("j")], list_jobs_command());
+    default_key_map.bind(vec![kbd("C-h"), kbd("e")], show_messages_command());
+    default_key_map.bind(vec![kbd("C-h"), kbd("p")], previous_message_command());
+    default_key_map.bind(vec![kbd("C-h"), kbd("n")], next_message_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("n")], end_of_buffer_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("p")], begin_of_buffer_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("C-s")], save_file_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("o")], other_window_clockwise_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("c"), kbd("n")], add_cursor_next_line_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("c"), kbd("p")], add_cursor_previous_line_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("c"), kbd("m")], add_cursor_next_match_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("c"), kbd("g")], remove_cursors_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("k")], kill_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("A-w")], copy_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("y")], yank_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("t")], string_insert_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("o")], open_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("c")], clear_rectangle_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("N")], number_rectangle_lines_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("l")], list_registers_command());
+    for register in "abcdefghijklmnopqrstuvwxyz0123456789".chars() {
+        let key = kbd(&register.to_string());
+        default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("s"), key], copy_to_register_command(register));
+        default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("i"), key], insert_register_command(register));
+        default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("Space"), key], point_to_register_command(register));
+        default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("j"), key], jump_to_register_command(register));
+        default_key_map.bind(vec![kbd("C-x"), kbd("r"), kbd("w"), key], window_layout_to_register_command(register));
+    }
+    default_key_map.bind(vec![kbd("C-x"), kbd("<")], scroll_left_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd(">")], scroll_right_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("t"), kbd("n")], toggle_line_numbers_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("t"), kbd("w")], toggle_wrap_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("t"), kbd("s")], toggle_show_whitespace_command());
+    default_key_map.bind(vec![kbd("C-x"), kbd("t"), kbd("v")], toggle_visual_line_movement_command());
     default_key_map.bind(vec![kbd("C-x"), kbd("C-o")], other_window_counter_clockwise_command());
     default_key_map.bind(vec![kbd("C-z")], undo_command());
     default_key_map.bind(vec![kbd("A-z")], redo_command());
     default_key_map.bind(vec![kbd("Backspace")], delete_backward_char_command());
     default_key_map.bind(vec![kbd("C-@")], set_mark_command());
+    default_key_map.bind(vec![kbd("Mouse1")], mouse_press_command());
+    default_key_map.bind(vec![kbd("MouseDrag1")], mouse_drag_command());
+    default_key_map.bind(vec![kbd("MouseRelease1")], blank_command());
+    default_key_map.bind(vec![kbd("WheelUp")], scroll_up_command());
+    default_key_map.bind(vec![kbd("WheelDown")], scroll_down_command());
+    drop(default_key_map);
+    state.commands.insert("begin-of-line".to_string(), begin_of_line_command());
+    state.commands.insert("backward-char".to_string(), backward_char_command());
+    state.commands.insert("backward-word".to_string(), backward_word_command());
+    state.commands.insert("backward-group".to_string(), backward_group_command());
+    state.commands.insert("delete-forward-char".to_string(), delete_forward_char_command());
+    state.commands.insert("delete-backward-char".to_string(), delete_backward_char_command());
+    state.commands.insert("end-of-line".to_string(), end_of_line_command());
+    state.commands.insert("forward-char".to_string(), forward_char_command());
+    state.commands.insert("forward-word".to_string(), forward_word_command());
+    state.commands.insert("forward-group".to_string(), forward_group_command());
+    state.commands.insert("forward-line".to_string(), forward_line_command());
+    state.commands.insert("backward-line".to_string(), backward_line_command());
+    state.commands.insert("up-group".to_string(), up_group_command());
+    state.commands.insert("begin-of-buffer".to_string(), begin_of_buffer_command());
+    state.commands.insert("end-of-buffer".to_string(), end_of_buffer_command());
+    state.commands.insert("set-mark".to_string(), set_mark_command());
+    state.commands.insert("remove-mark".to_string(), remove_mark_command());
+    state.commands.insert("kill-region".to_string(), kill_region_command());
+    state.commands.insert("copy-region".to_string(), copy_region_command());
+    state.commands.insert("paste".to_string(), paste_command());
+    state.commands.insert("paste-pop".to_string(), paste_pop_command());
+    state.commands.insert("kill-rectangle".to_string(), kill_rectangle_command());
+    state.commands.insert("copy-rectangle".to_string(), copy_rectangle_command());
+    state.commands.insert("yank-rectangle".to_string(), yank_rectangle_command());
+    state.commands.insert("string-insert-rectangle".to_string(), string_insert_rectangle_command());
+    state.commands.insert("open-rectangle".to_string(), open_rectangle_command());
+    state.commands.insert("clear-rectangle".to_string(), clear_rectangle_command());
+    state.commands.insert("number-rectangle-lines".to_string(), number_rectangle_lines_command());
+    state.commands.insert("list-registers".to_string(), list_registers_command());
+    state.commands.insert("undo".to_string(), undo_command());
+    state.commands.insert("redo".to_string(), redo_command());
+    state.commands.insert("add-cursor-next-line".to_string(), add_cursor_next_line_command());
+    state.commands.insert("add-cursor-previous-line".to_string(), add_cursor_previous_line_command());
+    state.commands.insert("add-cursor-next-match".to_string(), add_cursor_next_match_command());
+    state.commands.insert("remove-cursors".to_string(), remove_cursors_command());
+    state.commands.insert("horizontal-split".to_string(), horizontal_split_command());
+    state.commands.insert("vertical-split".to_string(), vertical_split_command());
+    state.commands.insert("close-window".to_string(), close_window_command());
+    state.commands.insert("close-other-windows".to_string(), close_other_windows_command());
+    state.commands.insert("other-window-clockwise".to_string(), other_window_clockwise_command());
+    state.commands.insert("other-window-counter-clockwise".to_string(), other_window_counter_clockwise_command());
+    state.commands.insert("save-file".to_string(), save_file_command());
+    state.commands.insert("close-ted".to_string(), close_ted_command());
+    state.commands.insert("toggle-line-numbers".to_string(), toggle_line_numbers_command());
+    state.commands.insert("toggle-wrap".to_string(), toggle_wrap_command());
+    state.commands.insert("toggle-show-whitespace".to_string(), toggle_show_whitespace_command());
+    state.commands.insert("toggle-visual-line-movement".to_string(), toggle_visual_line_movement_command());
+    state.display.selected_frame.lock().mode_line = ModeLine {
+        segments: vec![buffer_flags_segment(), buffer_name_segment(), line_column_segment(), percentage_segment(), modes_segment(), git_branch_segment(), jobs_segment(&state.jobs), encoding_segment()],
+        separator: "  ".to_string(),
+    };
     Ok(())
 }